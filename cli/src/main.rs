@@ -17,22 +17,32 @@ struct Arguments {
     /// Verbose mode
     #[arg(short, long)]
     verbose: bool,
+    /// How close numeric methods, such as integration, must get to the exact
+    /// answer
+    #[arg(long)]
+    tolerance: Option<f64>,
 }
 
 fn main() {
     let args = Arguments::parse();
+    let mut interpreter = Interpreter::default();
+    if let Some(tolerance) = args.tolerance {
+        interpreter.tolerance = tolerance;
+    }
 
     match args.file {
         Some(file) => {
             let path = Path::new(&file);
             let input = fs::read_to_string(path).expect("File should be read successfully!");
-            run(input, args.verbose, &mut Interpreter::default());
+            run(&input, 0, args.verbose, &mut interpreter);
         }
         None => {
             let stdin = io::stdin();
             let mut stdout = io::stdout();
 
-            let mut interpreter = Interpreter::default();
+            // Earlier lines stay in the source so that errors in functions
+            // defined on them can still be shown
+            let mut history = String::new();
 
             loop {
                 write!(&stdout, "> ").expect("PROMPT string should be written successfully!");
@@ -43,20 +53,29 @@ fn main() {
 
                 let mut input = String::new();
 
-                if let Err(e) = stdin.read_line(&mut input) {
-                    writeln!(&stdout, "Error: {e}")
-                        .expect("Error message should be written successfully!");
-                    return;
+                match stdin.read_line(&mut input) {
+                    Ok(0) => return,
+                    Ok(_) => {}
+                    Err(e) => {
+                        writeln!(&stdout, "Error: {e}")
+                            .expect("Error message should be written successfully!");
+                        return;
+                    }
                 }
 
-                run(input, args.verbose, &mut interpreter);
+                let offset = history.chars().count();
+                history.push_str(&input);
+                run(&history, offset, args.verbose, &mut interpreter);
             }
         }
     }
 }
 
-fn run(input: String, verbose: bool, interpreter: &mut Interpreter) {
-    let mut lexer = Lexer::new(input.clone());
+/// Runs the part of `source` starting at character `offset`, reporting errors
+/// against the whole of it.
+fn run(source: &str, offset: usize, verbose: bool, interpreter: &mut Interpreter) {
+    let input = source.chars().skip(offset).collect();
+    let mut lexer = Lexer::with_offset(input, offset);
     match lexer.lex() {
         Ok(tokens) => {
             if verbose {
@@ -77,16 +96,18 @@ fn run(input: String, verbose: bool, interpreter: &mut Interpreter) {
                         println!("AST: {}", ast);
                     }
 
-                    let value = interpreter.run(ast);
-                    println!("{}", value);
+                    match interpreter.run(ast) {
+                        Ok(value) => println!("{}", value),
+                        Err(e) => print_error(e, source),
+                    }
                 }
                 Err(e) => {
-                    print_error(e, &input);
+                    print_error(e, source);
                 }
             }
         }
         Err(e) => {
-            print_error(e, &input);
+            print_error(e, source);
         }
     }
 }
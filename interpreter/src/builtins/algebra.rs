@@ -0,0 +1,88 @@
+use crate::{error, ops::polynomial, Interpreter, Polynomial, Value};
+
+impl Interpreter {
+    pub fn algebra(&mut self) {
+        macro_rules! add_fn {
+            ($name:literal, |$interpreter:ident, $args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|$interpreter, $args| $body));
+            };
+        }
+
+        add_fn!("poly", |interpreter, args| {
+            match args {
+                // A function is applied to x itself, so that its body builds
+                // the polynomial
+                [function @ (Value::Function(_) | Value::NativeFunction(_))] => {
+                    match interpreter
+                        .call(function.clone(), vec![Value::Polynomial(Polynomial::x())])?
+                    {
+                        p @ Value::Polynomial(_) => Ok(p),
+                        value if value.is_number() => {
+                            Ok(Value::Polynomial(Polynomial::new(vec![value])))
+                        }
+                        value => error("type error", format!("{} is not a polynomial", value)),
+                    }
+                }
+                [] => error(
+                    "invalid arguments",
+                    "poly expects at least 1 argument".to_string(),
+                ),
+                coefficients => {
+                    if let Some(value) = coefficients.iter().find(|c| !c.is_number()) {
+                        return error(
+                            "type error",
+                            format!("Coefficients must be numbers, got {}", value),
+                        );
+                    }
+                    // Written from the highest power down, as polynomials are
+                    Ok(Value::Polynomial(Polynomial::new(
+                        coefficients.iter().rev().cloned().collect(),
+                    )))
+                }
+            }
+        });
+
+        // The quotient and remainder of long division, as `/` only divides
+        // exactly
+        add_fn!("divrem", |_interpreter, args| {
+            let [a, b] = args else {
+                return error(
+                    "invalid arguments",
+                    format!("divrem expects 2 arguments, got {}", args.len()),
+                );
+            };
+            let (Some(a), Some(b)) = (polynomial(a), polynomial(b)) else {
+                return error(
+                    "type error",
+                    format!("divrem expects polynomials, got {} and {}", a, b),
+                );
+            };
+            let (quotient, remainder) = a.div_rem(&b)?;
+            Ok(Value::List(vec![
+                Value::Polynomial(quotient),
+                Value::Polynomial(remainder),
+            ]))
+        });
+
+        add_fn!("roots", |interpreter, args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("roots expects 1 argument, got {}", args.len()),
+                );
+            }
+            match &args[0] {
+                Value::Polynomial(p) => Ok(Value::Vector(
+                    p.roots(interpreter.tolerance)?
+                        .into_iter()
+                        .map(Value::from)
+                        .collect(),
+                )),
+                value => error(
+                    "type error",
+                    format!("roots expects a polynomial, got {}", value),
+                ),
+            }
+        });
+    }
+}
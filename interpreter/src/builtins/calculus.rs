@@ -0,0 +1,600 @@
+use std::{ops::Range, rc::Rc};
+
+use common::SpectreError;
+use parser::{BinaryOp, Node, Spanned, UnaryOp};
+
+use crate::{
+    error,
+    ops::{self, real},
+    simplify, Function, Interpreter, RuntimeResult, Value,
+};
+
+impl Interpreter {
+    pub fn calculus(&mut self) {
+        macro_rules! add_fn {
+            ($name:literal, |$args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|_, $args| $body));
+            };
+            ($name:literal, |$interpreter:ident, $args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|$interpreter, $args| $body));
+            };
+        }
+
+        add_fn!("deriv", |interpreter, args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("deriv expects 1 argument, got {}", args.len()),
+                );
+            }
+            interpreter.derivative(args[0].clone())
+        });
+
+        add_fn!("simplify", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("simplify expects 1 argument, got {}", args.len()),
+                );
+            }
+            match &args[0] {
+                Value::Function(function) => Ok(function.map_body(|body| body)),
+                value => Ok(value.clone()),
+            }
+        });
+
+        add_fn!("integrate", |interpreter, args| {
+            // Polynomials have exact antiderivatives, so integrate(p) is one
+            // and integrate(p, a, b) is exact for exact bounds
+            if let Some(Value::Polynomial(p)) = args.first() {
+                let antiderivative = p.integral()?;
+                return match &args[1..] {
+                    [] => Ok(Value::Polynomial(antiderivative)),
+                    [a, b] => ops::binary(
+                        BinaryOp::Sub,
+                        antiderivative.evaluate(b)?,
+                        antiderivative.evaluate(a)?,
+                    ),
+                    _ => error(
+                        "invalid arguments",
+                        format!("integrate expects 1 or 3 arguments, got {}", args.len()),
+                    ),
+                };
+            }
+            if args.len() != 3 {
+                return error(
+                    "invalid arguments",
+                    format!("integrate expects 3 arguments, got {}", args.len()),
+                );
+            }
+            let function = match &args[0] {
+                function @ (Value::Function(_) | Value::NativeFunction(_)) => function.clone(),
+                value => return error("type error", format!("Cannot integrate {}", value)),
+            };
+            let (start, end) = (real_number(&args[1])?, real_number(&args[2])?);
+            let tolerance = interpreter.tolerance;
+            let integrand =
+                |x| real_number(&interpreter.call(function.clone(), vec![Value::Float(x)])?);
+            Ok(Value::Float(integrate(integrand, start, end, tolerance)?))
+        });
+    }
+
+    /// Differentiates `function` as [`derivative`] does, but also built-in
+    /// functions with a known derivative, by differentiating `x ↦ f(x)`.
+    pub fn derivative(&self, function: Value) -> RuntimeResult {
+        let Value::NativeFunction(_) = function else {
+            return derivative(function);
+        };
+        let range = 0..0;
+        let names = self.scope.borrow().names_of(&function);
+        let Some(name) = names
+            .into_iter()
+            .find(|name| known_derivative(name, int(0, &range), &range).is_some())
+        else {
+            return derivative(function);
+        };
+        let var: Rc<str> = "x".into();
+        let body = call(&name, spanned(Node::Identifier(Rc::clone(&var)), &range));
+        let function = Function::new(name, vec![var], body, Rc::clone(&self.scope));
+        derivative(Value::Function(Rc::new(function)))
+    }
+}
+
+/// Differentiates a function with respect to its first parameter, giving a
+/// new function with the same parameters.
+pub fn derivative(function: Value) -> RuntimeResult {
+    let function = match function {
+        Value::Function(function) => function,
+        Value::Polynomial(p) => return Ok(Value::Polynomial(p.derivative()?)),
+        Value::NativeFunction(_) => {
+            return error(
+                "not differentiable",
+                "This built-in function has no known derivative".to_string(),
+            )
+        }
+        value => return error("type error", format!("Cannot differentiate {}", value)),
+    };
+    let Some(var) = function.params.first() else {
+        return error(
+            "not differentiable",
+            format!("{} has no parameter to differentiate by", function.name),
+        );
+    };
+    let body = differentiate(&function.body, var)?;
+    Ok(Value::Function(Rc::new(Function::new(
+        format!("{}′", function.name).into(),
+        function.params.clone(),
+        simplify(&body),
+        Rc::clone(&function.scope),
+    ))))
+}
+
+/// Builds the derivative of `node` with respect to `var` by the usual rules,
+/// leaving out terms that are obviously zero.
+fn differentiate(node: &Spanned<Node>, var: &str) -> Result<Spanned<Node>, SpectreError> {
+    let range = &node.range;
+    if !depends(&node.node, var) {
+        return Ok(int(0, range));
+    }
+
+    Ok(match &node.node {
+        Node::Identifier(_) => int(1, range),
+        Node::Subscript(name, ..) if &**name == var => int(1, range),
+        // Elements are differentiated along with what they're taken from
+        Node::Index(u, index) if !depends(&index.node, var) => spanned(
+            Node::Index(Box::new(differentiate(u, var)?), index.clone()),
+            range,
+        ),
+        Node::Vector(nodes) => spanned(
+            Node::Vector(
+                nodes
+                    .iter()
+                    .map(|node| differentiate(node, var))
+                    .collect::<Result<_, _>>()?,
+            ),
+            range,
+        ),
+        Node::Matrix(rows) => spanned(
+            Node::Matrix(
+                rows.iter()
+                    .map(|row| row.iter().map(|node| differentiate(node, var)).collect())
+                    .collect::<Result<_, _>>()?,
+            ),
+            range,
+        ),
+        Node::Unary(op, u) => {
+            let du = differentiate(u, var)?;
+            let u = (**u).clone();
+            match op {
+                UnaryOp::Pos => du,
+                UnaryOp::Neg => neg(du),
+                UnaryOp::Degree => unary(UnaryOp::Degree, du),
+                // |u|′ = u′ u / |u|
+                UnaryOp::Abs => div(mul(du, u.clone()), unary(UnaryOp::Abs, u)),
+                // (ⁿ√u)′ = u′ / (n (ⁿ√u)ⁿ⁻¹)
+                UnaryOp::Sqrt => div(du, mul(int(2, range), unary(UnaryOp::Sqrt, u))),
+                UnaryOp::Cbrt => div(
+                    du,
+                    mul(int(3, range), pow(unary(UnaryOp::Cbrt, u), int(2, range))),
+                ),
+                UnaryOp::Fort => div(
+                    du,
+                    mul(int(4, range), pow(unary(UnaryOp::Fort, u), int(3, range))),
+                ),
+                _ => return not_differentiable(node, var),
+            }
+        }
+        Node::Binary(u, op, v) => {
+            let (du, dv) = (differentiate(u, var)?, differentiate(v, var)?);
+            let (u, v) = ((**u).clone(), (**v).clone());
+            match op {
+                BinaryOp::Add => add(du, dv),
+                BinaryOp::Sub => sub(du, dv),
+                // The product rule holds for every kind of product
+                BinaryOp::Mul | BinaryOp::Dot | BinaryOp::Cross => {
+                    add(binary(du, *op, v.clone()), binary(u, *op, dv))
+                }
+                BinaryOp::Div if !depends(&v.node, var) => div(du, v),
+                BinaryOp::Div => div(sub(mul(du, v.clone()), mul(u, dv)), pow(v, int(2, range))),
+                // (uⁿ)′ = n uⁿ⁻¹ u′
+                BinaryOp::Pow if !depends(&v.node, var) => {
+                    let n_minus_1 = match v.node {
+                        Node::Int(n) if n > i32::MIN => int(n - 1, range),
+                        _ => sub(v.clone(), int(1, range)),
+                    };
+                    mul(mul(v, pow(u, n_minus_1)), du)
+                }
+                // (aᵛ)′ = aᵛ ln(a) v′
+                BinaryOp::Pow if !depends(&u.node, var) => {
+                    mul(mul((*node).clone(), call("ln", u)), dv)
+                }
+                // (uᵛ)′ = uᵛ (v′ ln(u) + v u′ / u)
+                BinaryOp::Pow => mul(
+                    (*node).clone(),
+                    add(mul(dv, call("ln", u.clone())), div(mul(v, du), u)),
+                ),
+                _ => return not_differentiable(node, var),
+            }
+        }
+        // Piecewise functions are differentiated piece by piece
+        Node::If(cond, then, else_case) => spanned(
+            Node::If(
+                cond.clone(),
+                Box::new(differentiate(then, var)?),
+                match else_case {
+                    Some(else_case) => Some(Box::new(differentiate(else_case, var)?)),
+                    None => None,
+                },
+            ),
+            range,
+        ),
+        Node::Call(function, args) if args.len() == 1 => {
+            let u = args[0].clone();
+            let du = differentiate(&u, var)?;
+            let outer = match &function.node {
+                Node::Identifier(name) => known_derivative(name, u.clone(), range),
+                _ => None,
+            };
+            // Anything else is differentiated when it's called, by the chain
+            // rule: f(u)′ = f′(u) u′
+            let outer = outer.unwrap_or_else(|| {
+                let prime = unary(UnaryOp::Prime, (**function).clone());
+                spanned(Node::Call(Box::new(prime), vec![u]), range)
+            });
+            mul(outer, du)
+        }
+        Node::BigOperator(BinaryOp::Add, index, iterable, body)
+            if !depends(&iterable.node, var) =>
+        {
+            spanned(
+                Node::BigOperator(
+                    BinaryOp::Add,
+                    Rc::clone(index),
+                    iterable.clone(),
+                    Box::new(differentiate(body, var)?),
+                ),
+                range,
+            )
+        }
+        // Integrals with fixed bounds are differentiated under the integral sign
+        Node::Integral(index, start, end, body)
+            if !depends(&start.node, var) && !depends(&end.node, var) =>
+        {
+            spanned(
+                Node::Integral(
+                    Rc::clone(index),
+                    start.clone(),
+                    end.clone(),
+                    Box::new(differentiate(body, var)?),
+                ),
+                range,
+            )
+        }
+        Node::Statements(nodes) if nodes.len() == 1 => differentiate(&nodes[0], var)?,
+        _ => return not_differentiable(node, var),
+    })
+}
+
+/// Abscissae of the 15-point Kronrod rule on [-1, 1], from the outside in.
+/// The odd-numbered ones and the center are those of the 7-point Gauss rule.
+const KRONROD_NODES: [f64; 8] = [
+    0.9914553711208126,
+    0.9491079123427585,
+    0.8648644233597691,
+    0.7415311855993945,
+    0.5860872354676911,
+    0.4058451513773972,
+    0.20778495500789848,
+    0.0,
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022935322010529224,
+    0.06309209262997856,
+    0.10479001032225019,
+    0.14065325971552592,
+    0.1690047266392679,
+    0.19035057806478542,
+    0.20443294007529889,
+    0.20948214108472782,
+];
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.1294849661688697,
+    0.27970539148927664,
+    0.3818300505051189,
+    0.4179591836734694,
+];
+/// How many pieces an integral may be split into before giving up on it.
+const MAX_PIECES: usize = 1000;
+
+/// Integrates `f` from `a` to `b` to within `tolerance`, by adaptive
+/// Gauss–Kronrod quadrature. Infinite bounds are handled by a change of
+/// variables onto a finite interval, which the rule never evaluates the ends
+/// of.
+pub fn integrate(
+    mut f: impl FnMut(f64) -> Result<f64, SpectreError>,
+    a: f64,
+    b: f64,
+    tolerance: f64,
+) -> Result<f64, SpectreError> {
+    if a.is_nan() || b.is_nan() {
+        return error("invalid arguments", "Bounds can't be NaN".to_string());
+    }
+    if a > b {
+        return Ok(-integrate(f, b, a, tolerance)?);
+    }
+    if a == b {
+        return Ok(0.0);
+    }
+
+    let mut f = |x: f64| {
+        let y = f(x)?;
+        if !y.is_finite() {
+            return error("not integrable", format!("The integrand is {} at {}", y, x));
+        }
+        Ok(y)
+    };
+    match (a.is_finite(), b.is_finite()) {
+        (true, true) => adaptive(&mut f, a, b, tolerance),
+        // x = a + t / (1 - t)
+        (true, false) => adaptive(
+            &mut |t| Ok(f(a + t / (1.0 - t))? / ((1.0 - t) * (1.0 - t))),
+            0.0,
+            1.0,
+            tolerance,
+        ),
+        // x = b - (1 - t) / t
+        (false, true) => adaptive(
+            &mut |t| Ok(f(b - (1.0 - t) / t)? / (t * t)),
+            0.0,
+            1.0,
+            tolerance,
+        ),
+        // x = t / (1 - t²)
+        (false, false) => adaptive(
+            &mut |t| {
+                let s = 1.0 - t * t;
+                Ok(f(t / s)? * (1.0 + t * t) / (s * s))
+            },
+            -1.0,
+            1.0,
+            tolerance,
+        ),
+    }
+}
+
+/// Integrates `f` over `[a, b]`, repeatedly halving whichever piece has the
+/// largest error estimate until the total error is within `tolerance`, either
+/// absolutely or relative to the result.
+fn adaptive(
+    f: &mut dyn FnMut(f64) -> Result<f64, SpectreError>,
+    a: f64,
+    b: f64,
+    tolerance: f64,
+) -> Result<f64, SpectreError> {
+    let (estimate, error_estimate) = kronrod(f, a, b)?;
+    // Each piece with its integral and error estimate
+    let mut pieces = vec![(a, b, estimate, error_estimate)];
+    loop {
+        let (total, total_error) = pieces.iter().fold(
+            (0.0, 0.0),
+            |(total, total_error), &(_, _, estimate, error)| {
+                (total + estimate, total_error + error)
+            },
+        );
+        if total_error <= tolerance.max(tolerance * total.abs()) {
+            return Ok(total);
+        }
+
+        let worst = (0..pieces.len())
+            .max_by(|&i, &j| pieces[i].3.total_cmp(&pieces[j].3))
+            .unwrap();
+        let (a, b, ..) = pieces[worst];
+        let mid = (a + b) / 2.0;
+        if pieces.len() >= MAX_PIECES || mid <= a || mid >= b {
+            return error(
+                "not convergent",
+                format!("The integral didn't converge to within {}", tolerance),
+            );
+        }
+        let (left, left_error) = kronrod(f, a, mid)?;
+        let (right, right_error) = kronrod(f, mid, b)?;
+        pieces[worst] = (a, mid, left, left_error);
+        pieces.push((mid, b, right, right_error));
+    }
+}
+
+/// Applies the 15-point Kronrod rule to `f` over `[a, b]`, returning the
+/// estimate and its difference from the embedded 7-point Gauss rule.
+fn kronrod(
+    f: &mut dyn FnMut(f64) -> Result<f64, SpectreError>,
+    a: f64,
+    b: f64,
+) -> Result<(f64, f64), SpectreError> {
+    let (center, half) = ((a + b) / 2.0, (b - a) / 2.0);
+    let f_center = f(center)?;
+    let mut kronrod = KRONROD_WEIGHTS[7] * f_center;
+    let mut gauss = GAUSS_WEIGHTS[3] * f_center;
+    for i in 0..7 {
+        let dx = half * KRONROD_NODES[i];
+        let sum = f(center - dx)? + f(center + dx)?;
+        kronrod += KRONROD_WEIGHTS[i] * sum;
+        if i % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[i / 2] * sum;
+        }
+    }
+    Ok((kronrod * half, ((kronrod - gauss) * half).abs()))
+}
+
+/// Converts a bound or value of an integral to a float.
+pub(crate) fn real_number(value: &Value) -> Result<f64, SpectreError> {
+    if value.is_number() && !matches!(value, Value::Complex(..)) {
+        return Ok(real(value));
+    }
+    error(
+        "type error",
+        format!("Expected a real number, got {}", value),
+    )
+}
+
+/// The derivative of the built-in function `name` at `u`, if it's known.
+fn known_derivative(name: &str, u: Spanned<Node>, range: &Range<usize>) -> Option<Spanned<Node>> {
+    Some(match name {
+        "sin" => call("cos", u),
+        "cos" => neg(call("sin", u)),
+        "tan" => div(int(1, range), pow(call("cos", u), int(2, range))),
+        "exp" => call("exp", u),
+        "ln" => div(int(1, range), u),
+        "sqrt" => div(int(1, range), mul(int(2, range), call("sqrt", u))),
+        "cbrt" => div(
+            int(1, range),
+            mul(int(3, range), pow(call("cbrt", u), int(2, range))),
+        ),
+        "sinh" => call("cosh", u),
+        "cosh" => call("sinh", u),
+        "tanh" => div(int(1, range), pow(call("cosh", u), int(2, range))),
+        "abs" => div(u.clone(), call("abs", u)),
+        _ => return None,
+    })
+}
+
+/// Whether `node` refers to `var`.
+fn depends(node: &Node, var: &str) -> bool {
+    let any = |nodes: &[Spanned<Node>]| nodes.iter().any(|node| depends(&node.node, var));
+    match node {
+        Node::Int(_)
+        | Node::BigInt(_)
+        | Node::Float(_)
+        | Node::Str(_)
+        | Node::Break
+        | Node::Continue
+        | Node::Eof => false,
+        Node::Return(value) => value
+            .as_ref()
+            .is_some_and(|value| depends(&value.node, var)),
+        Node::Identifier(name) => &**name == var,
+        Node::Subscript(name, base, index) => {
+            &**name == var || &**base == var || depends(&index.node, var)
+        }
+        Node::Index(node, index) => depends(&node.node, var) || depends(&index.node, var),
+        Node::Vector(nodes)
+        | Node::List(nodes)
+        | Node::Interpolation(nodes)
+        | Node::Statements(nodes) => any(nodes),
+        Node::Matrix(rows) => rows.iter().any(|row| any(row)),
+        Node::Assignment(_, value) => depends(&value.node, var),
+        Node::Unary(_, node) => depends(&node.node, var),
+        Node::Binary(left, _, right) | Node::While(left, right) => {
+            depends(&left.node, var) || depends(&right.node, var)
+        }
+        Node::If(cond, then, else_case) => {
+            depends(&cond.node, var)
+                || depends(&then.node, var)
+                || else_case
+                    .as_ref()
+                    .is_some_and(|else_case| depends(&else_case.node, var))
+        }
+        Node::FnDef(_, params, body) | Node::Lambda(params, body) => {
+            !params.iter().any(|param| &**param == var) && depends(&body.node, var)
+        }
+        Node::Call(function, args) => depends(&function.node, var) || any(args),
+        Node::Integral(index, start, end, body) => {
+            depends(&start.node, var)
+                || depends(&end.node, var)
+                || (&**index != var && depends(&body.node, var))
+        }
+        Node::For(index, iterable, body) | Node::BigOperator(_, index, iterable, body) => {
+            depends(&iterable.node, var) || (&**index != var && depends(&body.node, var))
+        }
+        Node::Range(start, end, step, _) => {
+            depends(&start.node, var)
+                || depends(&end.node, var)
+                || step.as_ref().is_some_and(|step| depends(&step.node, var))
+        }
+    }
+}
+
+fn not_differentiable<T>(node: &Spanned<Node>, var: &str) -> Result<T, SpectreError> {
+    error(
+        "not differentiable",
+        format!("Cannot differentiate {} with respect to {}", node, var),
+    )
+}
+
+fn spanned(node: Node, range: &Range<usize>) -> Spanned<Node> {
+    Spanned::new(node, range.clone())
+}
+
+fn int(x: i32, range: &Range<usize>) -> Spanned<Node> {
+    spanned(Node::Int(x), range)
+}
+
+fn is_int(node: &Spanned<Node>, x: i32) -> bool {
+    node.node == Node::Int(x)
+}
+
+fn call(name: &str, arg: Spanned<Node>) -> Spanned<Node> {
+    let range = arg.range.clone();
+    let function = spanned(Node::Identifier(name.into()), &range);
+    spanned(Node::Call(Box::new(function), vec![arg]), &range)
+}
+
+fn unary(op: UnaryOp, node: Spanned<Node>) -> Spanned<Node> {
+    let range = node.range.clone();
+    spanned(Node::Unary(op, Box::new(node)), &range)
+}
+
+fn binary(left: Spanned<Node>, op: BinaryOp, right: Spanned<Node>) -> Spanned<Node> {
+    let range = left.range.start.min(right.range.start)..left.range.end.max(right.range.end);
+    spanned(Node::Binary(Box::new(left), op, Box::new(right)), &range)
+}
+
+fn neg(node: Spanned<Node>) -> Spanned<Node> {
+    if is_int(&node, 0) {
+        return node;
+    }
+    unary(UnaryOp::Neg, node)
+}
+
+fn add(left: Spanned<Node>, right: Spanned<Node>) -> Spanned<Node> {
+    if is_int(&left, 0) {
+        return right;
+    }
+    if is_int(&right, 0) {
+        return left;
+    }
+    binary(left, BinaryOp::Add, right)
+}
+
+fn sub(left: Spanned<Node>, right: Spanned<Node>) -> Spanned<Node> {
+    if is_int(&right, 0) {
+        return left;
+    }
+    if is_int(&left, 0) {
+        return neg(right);
+    }
+    binary(left, BinaryOp::Sub, right)
+}
+
+fn mul(left: Spanned<Node>, right: Spanned<Node>) -> Spanned<Node> {
+    if is_int(&left, 0) || is_int(&right, 1) {
+        return left;
+    }
+    if is_int(&right, 0) || is_int(&left, 1) {
+        return right;
+    }
+    binary(left, BinaryOp::Mul, right)
+}
+
+fn div(left: Spanned<Node>, right: Spanned<Node>) -> Spanned<Node> {
+    if is_int(&left, 0) || is_int(&right, 1) {
+        return left;
+    }
+    binary(left, BinaryOp::Div, right)
+}
+
+fn pow(base: Spanned<Node>, exponent: Spanned<Node>) -> Spanned<Node> {
+    if is_int(&exponent, 1) {
+        return base;
+    }
+    binary(base, BinaryOp::Pow, exponent)
+}
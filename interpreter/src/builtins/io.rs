@@ -2,11 +2,13 @@ use crate::{Interpreter, Value};
 
 impl Interpreter {
     pub fn io(&mut self) {
+        // Prints its arguments separated by spaces, with strings unquoted
         self.add_var(
             "print",
-            Value::NativeFunction(|args| {
-                println!("{}", args[0]);
-                Value::Int(0)
+            Value::NativeFunction(|_, args| {
+                let line: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                println!("{}", line.join(" "));
+                Ok(Value::Int(0))
             }),
         );
     }
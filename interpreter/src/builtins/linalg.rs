@@ -0,0 +1,357 @@
+use common::SpectreError;
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+use parser::BinaryOp;
+
+use crate::{
+    error,
+    ops::{binary, float, map, ratio},
+    Interpreter, RuntimeResult, Value,
+};
+
+impl Interpreter {
+    pub fn linalg(&mut self) {
+        macro_rules! add_fn {
+            ($name:literal, |$args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|_, $args| $body));
+            };
+        }
+
+        add_fn!("transpose", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("transpose expects 1 argument, got {}", args.len()),
+                );
+            }
+            transpose(args[0].clone())
+        });
+        add_fn!("det", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("det expects 1 argument, got {}", args.len()),
+                );
+            }
+            match &args[0] {
+                Value::Matrix(rows) => det(rows),
+                _ => error("type error", "det expects a matrix".to_string()),
+            }
+        });
+        add_fn!("inv", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("inv expects 1 argument, got {}", args.len()),
+                );
+            }
+            match &args[0] {
+                Value::Matrix(rows) => inverse(rows),
+                _ => error("type error", "inv expects a matrix".to_string()),
+            }
+        });
+        add_fn!("rank", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("rank expects 1 argument, got {}", args.len()),
+                );
+            }
+            match &args[0] {
+                Value::Matrix(rows) => Ok(Value::Int(rank(rows)? as i32)),
+                _ => error("type error", "rank expects a matrix".to_string()),
+            }
+        });
+        add_fn!("trace", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("trace expects 1 argument, got {}", args.len()),
+                );
+            }
+            match &args[0] {
+                Value::Matrix(rows) => {
+                    square(rows, "trace")?;
+                    rows.iter()
+                        .enumerate()
+                        .map(|(i, row)| row[i].clone())
+                        .try_fold(Value::Int(0), |sum, x| binary(BinaryOp::Add, sum, x))
+                }
+                _ => error("type error", "trace expects a matrix".to_string()),
+            }
+        });
+    }
+}
+
+/// Transposes a matrix. A vector is treated as a column, so its transpose is
+/// a single-row matrix; scalars are their own transpose.
+pub fn transpose(value: Value) -> RuntimeResult {
+    Ok(match value {
+        Value::Matrix(rows) => {
+            let cols = rows.first().map_or(0, |row| row.len());
+            Value::Matrix(
+                (0..cols)
+                    .map(|j| rows.iter().map(|row| row[j].clone()).collect())
+                    .collect(),
+            )
+        }
+        Value::Vector(v) => Value::Matrix(vec![v]),
+        value if value.is_number() => value,
+        value => return error("type error", format!("Cannot transpose {}", value)),
+    })
+}
+
+pub fn multiply(a: &[Vec<Value>], b: &[Vec<Value>]) -> Result<Vec<Vec<Value>>, SpectreError> {
+    let inner = a.first().map_or(0, |row| row.len());
+    if inner != b.len() {
+        return error(
+            "type error",
+            format!(
+                "Cannot multiply a {}×{} matrix by a {}×{} matrix",
+                a.len(),
+                inner,
+                b.len(),
+                b.first().map_or(0, |row| row.len())
+            ),
+        );
+    }
+    let cols = b.first().map_or(0, |row| row.len());
+    a.iter()
+        .map(|row| {
+            (0..cols)
+                .map(|j| {
+                    row.iter()
+                        .zip(b)
+                        .try_fold(Value::Int(0), |sum, (x, b_row)| {
+                            let product = binary(BinaryOp::Mul, x.clone(), b_row[j].clone())?;
+                            binary(BinaryOp::Add, sum, product)
+                        })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn identity(n: usize) -> Vec<Vec<Value>> {
+    (0..n)
+        .map(|i| (0..n).map(|j| Value::Int((i == j) as i32)).collect())
+        .collect()
+}
+
+fn square(rows: &[Vec<Value>], name: &str) -> Result<usize, SpectreError> {
+    let n = rows.len();
+    if rows.iter().any(|row| row.len() != n) {
+        return error("type error", format!("{} expects a square matrix", name));
+    }
+    Ok(n)
+}
+
+fn to_f64(rows: &[Vec<Value>], name: &str) -> Result<Vec<Vec<f64>>, SpectreError> {
+    map(rows.to_vec(), |row| {
+        map(row, |x| match x {
+            Value::Int(x) => Ok(x as f64),
+            Value::BigInt(x) => Ok(float(&x)),
+            Value::Rational(n, d) => Ok(ratio(&n, &d)),
+            Value::Float(x) => Ok(x),
+            _ => error(
+                "type error",
+                format!("{} expects a matrix of real numbers", name),
+            ),
+        })
+    })
+}
+
+/// Row-reduces `m` in place with partial pivoting, returning the pivot
+/// columns and whether an odd number of row swaps was made.
+fn eliminate(m: &mut [Vec<f64>]) -> (Vec<usize>, bool) {
+    let rows = m.len();
+    let cols = m.first().map_or(0, |row| row.len());
+    let max = m.iter().flatten().fold(0.0_f64, |max, x| max.max(x.abs()));
+    let epsilon = f64::EPSILON * max * rows.max(cols) as f64;
+
+    let mut pivots = vec![];
+    let mut swapped = false;
+    for j in 0..cols {
+        let r = pivots.len();
+        if r == rows {
+            break;
+        }
+        let (p, pivot) = (r..rows)
+            .map(|i| (i, m[i][j]))
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .unwrap();
+        if pivot.abs() <= epsilon {
+            continue;
+        }
+        if p != r {
+            m.swap(p, r);
+            swapped = !swapped;
+        }
+        for i in r + 1..rows {
+            let factor = m[i][j] / pivot;
+            subtract_row(m, i, r, factor, j);
+        }
+        pivots.push(j);
+    }
+    (pivots, swapped)
+}
+
+/// Subtracts `factor` times row `src` from row `dst`, starting at column `from`.
+fn subtract_row(m: &mut [Vec<f64>], dst: usize, src: usize, factor: f64, from: usize) {
+    let (src, dst) = if src < dst {
+        let (a, b) = m.split_at_mut(dst);
+        (&a[src], &mut b[0])
+    } else {
+        let (a, b) = m.split_at_mut(src);
+        (&b[0], &mut a[dst])
+    };
+    for (x, y) in dst[from..].iter_mut().zip(&src[from..]) {
+        *x -= factor * y;
+    }
+}
+
+fn det(rows: &[Vec<Value>]) -> RuntimeResult {
+    let n = square(rows, "det")?;
+
+    // Bareiss elimination keeps integer determinants exact
+    if rows
+        .iter()
+        .flatten()
+        .all(|x| matches!(x, Value::Int(_) | Value::BigInt(_)))
+    {
+        let mut m: Vec<Vec<BigInt>> = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|x| match x {
+                        Value::Int(x) => BigInt::from(*x),
+                        Value::BigInt(x) => x.clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut negate = false;
+        let mut previous = BigInt::one();
+        for k in 0..n {
+            if m[k][k].is_zero() {
+                match (k + 1..n).find(|&i| !m[i][k].is_zero()) {
+                    Some(i) => {
+                        m.swap(i, k);
+                        negate = !negate;
+                    }
+                    None => return Ok(Value::Int(0)),
+                }
+            }
+            for i in k + 1..n {
+                for j in k + 1..n {
+                    m[i][j] = (&m[i][j] * &m[k][k] - &m[i][k] * &m[k][j]) / &previous;
+                }
+            }
+            previous = m[k][k].clone();
+        }
+        let det = if n == 0 { BigInt::one() } else { previous };
+        return Ok(Value::from(if negate { -det } else { det }));
+    }
+
+    det_f64(rows)
+}
+
+fn det_f64(rows: &[Vec<Value>]) -> RuntimeResult {
+    let mut m = to_f64(rows, "det")?;
+    let (pivots, swapped) = eliminate(&mut m);
+    if pivots.len() < m.len() {
+        return Ok(Value::Float(0.0));
+    }
+    let det: f64 = (0..m.len()).map(|i| m[i][i]).product();
+    Ok(Value::Float(if swapped { -det } else { det }))
+}
+
+pub fn inverse(rows: &[Vec<Value>]) -> RuntimeResult {
+    let n = square(rows, "inv")?;
+    if rows.iter().flatten().all(Value::is_exact) {
+        return inverse_exact(rows, n);
+    }
+    let mut m: Vec<Vec<f64>> = to_f64(rows, "inv")?
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut row)| {
+            row.extend((0..n).map(|j| if i == j { 1.0 } else { 0.0 }));
+            row
+        })
+        .collect();
+
+    let (pivots, _) = eliminate(&mut m);
+    if pivots.len() < n || pivots.iter().any(|&j| j >= n) {
+        return singular();
+    }
+    for i in (0..n).rev() {
+        let pivot = m[i][i];
+        for value in m[i].iter_mut() {
+            *value /= pivot;
+        }
+        for r in 0..i {
+            let factor = m[r][i];
+            subtract_row(&mut m, r, i, factor, 0);
+        }
+    }
+
+    Ok(Value::Matrix(
+        m.into_iter()
+            .map(|row| row[n..].iter().map(|&x| Value::Float(x)).collect())
+            .collect(),
+    ))
+}
+
+/// Gauss–Jordan elimination with exact arithmetic, so that inverses of
+/// integer and rational matrices come out exact.
+fn inverse_exact(rows: &[Vec<Value>], n: usize) -> RuntimeResult {
+    let mut m: Vec<Vec<Value>> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row = row.clone();
+            row.extend((0..n).map(|j| Value::Int((i == j) as i32)));
+            row
+        })
+        .collect();
+
+    for k in 0..n {
+        // Exact zeros are always `Int(0)`
+        let Some(p) = (k..n).find(|&i| m[i][k] != Value::Int(0)) else {
+            return singular();
+        };
+        m.swap(p, k);
+        let pivot = m[k][k].clone();
+        m[k] = map(std::mem::take(&mut m[k]), |x| {
+            binary(BinaryOp::Div, x, pivot.clone())
+        })?;
+        let pivot_row = m[k].clone();
+        for (i, row) in m.iter_mut().enumerate() {
+            let factor = row[k].clone();
+            if i == k || factor == Value::Int(0) {
+                continue;
+            }
+            for (x, p) in row.iter_mut().zip(&pivot_row).skip(k) {
+                let product = binary(BinaryOp::Mul, factor.clone(), p.clone())?;
+                *x = binary(BinaryOp::Sub, x.clone(), product)?;
+            }
+        }
+    }
+
+    Ok(Value::Matrix(
+        m.into_iter().map(|mut row| row.split_off(n)).collect(),
+    ))
+}
+
+fn singular<T>() -> Result<T, SpectreError> {
+    error(
+        "singular matrix",
+        "Cannot invert a singular matrix".to_string(),
+    )
+}
+
+fn rank(rows: &[Vec<Value>]) -> Result<usize, SpectreError> {
+    let mut m = to_f64(rows, "rank")?;
+    Ok(eliminate(&mut m).0.len())
+}
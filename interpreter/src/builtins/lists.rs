@@ -0,0 +1,204 @@
+use parser::BinaryOp;
+
+use crate::{
+    error,
+    ops::{binary, iterate, iterate_all},
+    Interpreter, RuntimeResult, Value,
+};
+
+impl Interpreter {
+    pub fn lists(&mut self) {
+        macro_rules! add_fn {
+            ($name:literal, |$args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|_, $args| $body));
+            };
+            ($name:literal, |$interpreter:ident, $args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|$interpreter, $args| $body));
+            };
+        }
+
+        add_fn!("push", |args| {
+            match args {
+                [Value::List(items), item] => {
+                    let mut items = items.clone();
+                    items.push(item.clone());
+                    Ok(Value::List(items))
+                }
+                [Value::Vector(v), x] => {
+                    let mut v = v.clone();
+                    v.push(x.clone());
+                    Ok(Value::Vector(v))
+                }
+                [value, _] => error("type error", format!("Cannot push onto {}", value)),
+                _ => error(
+                    "invalid arguments",
+                    format!("push expects 2 arguments, got {}", args.len()),
+                ),
+            }
+        });
+        add_fn!("map", |interpreter, args| {
+            let [items, function] = args else {
+                return error(
+                    "invalid arguments",
+                    format!("map expects 2 arguments, got {}", args.len()),
+                );
+            };
+            let mut mapped = vec![];
+            for item in iterate_all(items.clone())? {
+                mapped.push(interpreter.call(function.clone(), vec![item])?);
+            }
+            Ok(like(items, mapped))
+        });
+        add_fn!("filter", |interpreter, args| {
+            let [items, predicate] = args else {
+                return error(
+                    "invalid arguments",
+                    format!("filter expects 2 arguments, got {}", args.len()),
+                );
+            };
+            let mut kept = vec![];
+            for item in iterate_all(items.clone())? {
+                if bool::from(interpreter.call(predicate.clone(), vec![item.clone()])?) {
+                    kept.push(item);
+                }
+            }
+            Ok(like(items, kept))
+        });
+        // Folds from the left, starting from the first item unless given an
+        // initial value
+        add_fn!("reduce", |interpreter, args| {
+            let (items, function, initial) = match args {
+                [items, function] => (items, function, None),
+                [items, function, initial] => (items, function, Some(initial.clone())),
+                _ => {
+                    return error(
+                        "invalid arguments",
+                        format!("reduce expects 2 or 3 arguments, got {}", args.len()),
+                    )
+                }
+            };
+            let mut items = iterate_all(items.clone())?;
+            let Some(mut result) = initial.or_else(|| items.next()) else {
+                return error(
+                    "invalid arguments",
+                    "Cannot reduce nothing without an initial value".to_string(),
+                );
+            };
+            for item in items {
+                result = interpreter.call(function.clone(), vec![result, item])?;
+            }
+            Ok(result)
+        });
+        add_fn!("sort", |args| {
+            let [items] = args else {
+                return error(
+                    "invalid arguments",
+                    format!("sort expects 1 argument, got {}", args.len()),
+                );
+            };
+            let mut sorted: Vec<Value> = iterate_all(items.clone())?.collect();
+            // A merge sort, since comparisons can fail
+            let mut width = 1;
+            while width < sorted.len() {
+                let mut merged = Vec::with_capacity(sorted.len());
+                for chunk in sorted.chunks(2 * width) {
+                    let (mut left, mut right) = chunk.split_at(width.min(chunk.len()));
+                    while let (Some(l), Some(r)) = (left.first(), right.first()) {
+                        if bool::from(binary(BinaryOp::Lt, r.clone(), l.clone())?) {
+                            merged.push(r.clone());
+                            right = &right[1..];
+                        } else {
+                            merged.push(l.clone());
+                            left = &left[1..];
+                        }
+                    }
+                    merged.extend_from_slice(left);
+                    merged.extend_from_slice(right);
+                }
+                sorted = merged;
+                width *= 2;
+            }
+            Ok(like(items, sorted))
+        });
+        add_fn!("reverse", |args| {
+            let [items] = args else {
+                return error(
+                    "invalid arguments",
+                    format!("reverse expects 1 argument, got {}", args.len()),
+                );
+            };
+            if let Value::Str(text) = items {
+                return Ok(Value::Str(text.chars().rev().collect::<String>().into()));
+            }
+            let mut reversed: Vec<Value> = iterate_all(items.clone())?.collect();
+            reversed.reverse();
+            Ok(like(items, reversed))
+        });
+        // Pairs up the items at each index, stopping at the end of the
+        // shortest
+        add_fn!("zip", |args| {
+            if args.len() < 2 {
+                return error(
+                    "invalid arguments",
+                    format!("zip expects at least 2 arguments, got {}", args.len()),
+                );
+            }
+            let endless =
+                |items: &Value| matches!(items, Value::Range(range) if range.count().is_none());
+            if args.iter().all(endless) {
+                return error("type error", "Cannot zip only endless ranges".to_string());
+            }
+            let mut iterators = args
+                .iter()
+                .map(|items| iterate(items.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut zipped = vec![];
+            loop {
+                let tuple: Option<Vec<Value>> = iterators.iter_mut().map(Iterator::next).collect();
+                match tuple {
+                    Some(tuple) => zipped.push(Value::List(tuple)),
+                    None => return Ok(Value::List(zipped)),
+                }
+            }
+        });
+        add_fn!("sum", |args| total("sum", BinaryOp::Add, args));
+        add_fn!("prod", |args| total("prod", BinaryOp::Mul, args));
+    }
+}
+
+/// Collects `items` into a collection of the same kind as `original`, so
+/// that vectors stay vectors and everything else becomes a list.
+fn like(original: &Value, items: Vec<Value>) -> Value {
+    match original {
+        Value::Vector(_) => Value::Vector(items),
+        _ => Value::List(items),
+    }
+}
+
+/// Folds the items of the only argument with `op`, as `sum` and `prod` do.
+fn total(name: &str, op: BinaryOp, args: &[Value]) -> RuntimeResult {
+    let [items] = args else {
+        return error(
+            "invalid arguments",
+            format!("{} expects 1 argument, got {}", name, args.len()),
+        );
+    };
+    if let (BinaryOp::Add, Value::Range(range)) = (op, items) {
+        if let Some(sum) = range.sum() {
+            return Ok(sum);
+        }
+    }
+    // Start from the first item rather than the identity so sums of vectors
+    // and matrices work
+    let mut items = iterate_all(items.clone())?;
+    let Some(mut result) = items.next() else {
+        return Ok(Value::Int(match op {
+            BinaryOp::Mul => 1,
+            _ => 0,
+        }));
+    };
+    for item in items {
+        result = binary(op, result, item)?;
+    }
+    Ok(result)
+}
@@ -1,4 +1,15 @@
-use crate::{Interpreter, Value};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, Zero};
+use parser::BinaryOp;
+
+use crate::{
+    error,
+    ops::{
+        binary, complex, float, iterate_all, ln, polynomial, ratio, rational, real, round, sqrt,
+    },
+    Complex, Interpreter, RuntimeResult, Value,
+};
 
 impl Interpreter {
     pub fn math(&mut self) {
@@ -17,264 +28,494 @@ impl Interpreter {
         add_var!("i", (0.0, 1.0));
 
         macro_rules! add_fn {
-            ($name:literal, $value:expr) => {
-                self.add_var($name, Value::NativeFunction($value));
+            ($name:literal, |$args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|_, $args| $body));
             };
         }
 
         add_fn!("abs", |args| {
             if args.len() != 1 {
-                panic!("abs expects 1 argument, got {}", args.len());
-            }
-            match args[0] {
-                Value::Int(value) => Value::Int(value.abs()),
+                return error(
+                    "invalid arguments",
+                    format!("abs expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => match value.checked_abs() {
+                    Some(value) => Value::Int(value),
+                    None => Value::from(BigInt::from(value).abs()),
+                },
+                Value::BigInt(ref value) => Value::BigInt(value.abs()),
+                Value::Rational(ref n, ref d) => Value::Rational(n.abs(), d.clone()),
+                Value::Complex(re, im) => Value::Float(re.hypot(im)),
                 Value::Float(value) => Value::Float(value.abs()),
-                _ => panic!("abs expects a number"),
-            }
+                _ => return error("type error", "abs expects a number".to_string()),
+            })
         });
         add_fn!("floor", |args| {
             if args.len() != 1 {
-                panic!("floor expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("floor expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Int(value),
+                Value::BigInt(ref value) => Value::BigInt(value.clone()),
+                Value::Rational(ref n, ref d) => Value::from(n.div_floor(d)),
                 Value::Float(value) => Value::Float(value.floor()),
-                _ => panic!("floor expects a number"),
-            }
+                _ => return error("type error", "floor expects a number".to_string()),
+            })
         });
         add_fn!("ceil", |args| {
             if args.len() != 1 {
-                panic!("ceil expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("ceil expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Int(value),
+                Value::BigInt(ref value) => Value::BigInt(value.clone()),
+                Value::Rational(ref n, ref d) => Value::from(n.div_ceil(d)),
                 Value::Float(value) => Value::Float(value.ceil()),
-                _ => panic!("ceil expects a number"),
-            }
+                _ => return error("type error", "ceil expects a number".to_string()),
+            })
         });
         add_fn!("round", |args| {
             if args.len() != 1 {
-                panic!("round expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("round expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Int(value),
+                Value::BigInt(ref value) => Value::BigInt(value.clone()),
+                Value::Rational(ref n, ref d) => round(n, d),
                 Value::Float(value) => Value::Float(value.round()),
-                _ => panic!("round expects a number"),
-            }
+                _ => return error("type error", "round expects a number".to_string()),
+            })
         });
         add_fn!("trunc", |args| {
             if args.len() != 1 {
-                panic!("trunc expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("trunc expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Int(value),
+                Value::BigInt(ref value) => Value::BigInt(value.clone()),
+                Value::Rational(ref n, ref d) => Value::from(n / d),
                 Value::Float(value) => Value::Float(value.trunc()),
-                _ => panic!("trunc expects a number"),
-            }
+                _ => return error("type error", "trunc expects a number".to_string()),
+            })
         });
         add_fn!("fract", |args| {
             if args.len() != 1 {
-                panic!("fract expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("fract expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Float((value as f64).fract()),
+                Value::BigInt(_) => Value::Float(0.0),
+                Value::Rational(ref n, ref d) => rational(n % d, d.clone())?,
                 Value::Float(value) => Value::Float(value.fract()),
-                _ => panic!("fract expects a number"),
-            }
+                _ => return error("type error", "fract expects a number".to_string()),
+            })
         });
         add_fn!("sqrt", |args| {
             if args.len() != 1 {
-                panic!("sqrt expects 1 argument, got {}", args.len());
-            }
-            match args[0] {
-                Value::Int(value) => Value::Float((value as f64).sqrt()),
-                Value::Float(value) => Value::Float(value.sqrt()),
-                _ => panic!("sqrt expects a number"),
-            }
+                return error(
+                    "invalid arguments",
+                    format!("sqrt expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => sqrt(value as f64),
+                Value::BigInt(ref value) => sqrt(float(value)),
+                Value::Rational(ref n, ref d) => sqrt(ratio(n, d)),
+                Value::Float(value) => sqrt(value),
+                Value::Complex(re, im) => Complex::new(re, im).sqrt().into(),
+                _ => return error("type error", "sqrt expects a number".to_string()),
+            })
         });
-        add_fn!("sqrt", |args| {
+        add_fn!("cbrt", |args| {
             if args.len() != 1 {
-                panic!("sqrt expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("cbrt expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Float((value as f64).cbrt()),
+                Value::BigInt(ref value) => Value::Float(float(value).cbrt()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).cbrt()),
                 Value::Float(value) => Value::Float(value.cbrt()),
-                _ => panic!("sqrt expects a number"),
-            }
+                Value::Complex(re, im) => Complex::new(re, im).pow((1.0 / 3.0).into()).into(),
+                _ => return error("type error", "cbrt expects a number".to_string()),
+            })
         });
         add_fn!("ln", |args| {
             if args.len() != 1 {
-                panic!("ln expects 1 argument, got {}", args.len());
-            }
-            match args[0] {
-                Value::Int(value) => Value::Float((value as f64).ln()),
-                Value::Float(value) => Value::Float(value.ln()),
-                _ => panic!("ln expects a number"),
-            }
+                return error(
+                    "invalid arguments",
+                    format!("ln expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => ln(value as f64),
+                Value::BigInt(ref value) => ln(float(value)),
+                Value::Rational(ref n, ref d) => ln(ratio(n, d)),
+                Value::Float(value) => ln(value),
+                Value::Complex(re, im) => Complex::new(re, im).ln().into(),
+                _ => return error("type error", "ln expects a number".to_string()),
+            })
+        });
+        // The base comes last, so that `log₂(x)` is `log(x, 2)`
+        add_fn!("log", |args| {
+            let (x, base) = match args {
+                [x] => (x, &Value::Int(10)),
+                [x, base] => (x, base),
+                _ => {
+                    return error(
+                        "invalid arguments",
+                        format!("log expects 1 or 2 arguments, got {}", args.len()),
+                    )
+                }
+            };
+            let (Some(z), Some(b)) = (complex(x), complex(base)) else {
+                return error("type error", "log expects numbers".to_string());
+            };
+            // Negative numbers have complex logarithms too
+            let is_complex =
+                |value: &Value| matches!(value, Value::Complex(..)) || value.is_negative();
+            if is_complex(x) || is_complex(base) {
+                return Ok((z.ln() / b.ln()).into());
+            }
+            Ok(Value::Float(match b.re {
+                2.0 => z.re.log2(),
+                10.0 => z.re.log10(),
+                b => z.re.ln() / b.ln(),
+            }))
         });
         add_fn!("sin", |args| {
             if args.len() != 1 {
-                panic!("sin expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("sin expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Float((value as f64).sin()),
+                Value::BigInt(ref value) => Value::Float(float(value).sin()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).sin()),
                 Value::Float(value) => Value::Float(value.sin()),
-                _ => panic!("sin expects a number"),
-            }
+                Value::Complex(re, im) => Complex::new(re, im).sin().into(),
+                _ => return error("type error", "sin expects a number".to_string()),
+            })
         });
         add_fn!("cos", |args| {
             if args.len() != 1 {
-                panic!("cos expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("cos expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Float((value as f64).cos()),
+                Value::BigInt(ref value) => Value::Float(float(value).cos()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).cos()),
                 Value::Float(value) => Value::Float(value.cos()),
-                _ => panic!("cos expects a number"),
-            }
+                Value::Complex(re, im) => Complex::new(re, im).cos().into(),
+                _ => return error("type error", "cos expects a number".to_string()),
+            })
         });
         add_fn!("tan", |args| {
             if args.len() != 1 {
-                panic!("tan expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("tan expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(value) => Value::Float((value as f64).tan()),
+                Value::BigInt(ref value) => Value::Float(float(value).tan()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).tan()),
                 Value::Float(value) => Value::Float(value.tan()),
-                _ => panic!("tan expects a number"),
-            }
+                Value::Complex(re, im) => Complex::new(re, im).tan().into(),
+                _ => return error("type error", "tan expects a number".to_string()),
+            })
+        });
+        add_fn!("exp", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("exp expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => Value::Float((value as f64).exp()),
+                Value::BigInt(ref value) => Value::Float(float(value).exp()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).exp()),
+                Value::Float(value) => Value::Float(value.exp()),
+                Value::Complex(re, im) => Complex::new(re, im).exp().into(),
+                _ => return error("type error", "exp expects a number".to_string()),
+            })
+        });
+        add_fn!("sinh", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("sinh expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => Value::Float((value as f64).sinh()),
+                Value::BigInt(ref value) => Value::Float(float(value).sinh()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).sinh()),
+                Value::Float(value) => Value::Float(value.sinh()),
+                Value::Complex(re, im) => Complex::new(re, im).sinh().into(),
+                _ => return error("type error", "sinh expects a number".to_string()),
+            })
+        });
+        add_fn!("cosh", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("cosh expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => Value::Float((value as f64).cosh()),
+                Value::BigInt(ref value) => Value::Float(float(value).cosh()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).cosh()),
+                Value::Float(value) => Value::Float(value.cosh()),
+                Value::Complex(re, im) => Complex::new(re, im).cosh().into(),
+                _ => return error("type error", "cosh expects a number".to_string()),
+            })
+        });
+        add_fn!("tanh", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("tanh expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Int(value) => Value::Float((value as f64).tanh()),
+                Value::BigInt(ref value) => Value::Float(float(value).tanh()),
+                Value::Rational(ref n, ref d) => Value::Float(ratio(n, d).tanh()),
+                Value::Float(value) => Value::Float(value.tanh()),
+                Value::Complex(re, im) => Complex::new(re, im).tanh().into(),
+                _ => return error("type error", "tanh expects a number".to_string()),
+            })
         });
         add_fn!("gcd", |args| {
             if args.len() != 2 {
-                panic!("gcd expects 2 arguments, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("gcd expects 2 arguments, got {}", args.len()),
+                );
             }
-            match args[0] {
-                Value::Int(a) => match args[1] {
-                    Value::Int(b) => Value::Int(gcd(a, b)),
-                    _ => panic!("gcd expects 2 integers"),
-                },
-                _ => panic!("gcd expects 2 integers"),
+            if matches!(args[0], Value::Polynomial(_)) || matches!(args[1], Value::Polynomial(_)) {
+                return match (polynomial(&args[0]), polynomial(&args[1])) {
+                    (Some(a), Some(b)) => Ok(Value::Polynomial(a.gcd(&b)?)),
+                    _ => error("type error", "gcd expects 2 polynomials".to_string()),
+                };
+            }
+            match (integer(&args[0]), integer(&args[1])) {
+                (Some(a), Some(b)) => Ok(Value::from(gcd(&a, &b))),
+                _ => error("type error", "gcd expects 2 integers".to_string()),
             }
         });
         add_fn!("lcm", |args| {
             if args.len() != 2 {
-                panic!("lcm expects 2 arguments, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("lcm expects 2 arguments, got {}", args.len()),
+                );
             }
-            match args[0] {
-                Value::Int(a) => match args[1] {
-                    Value::Int(b) => Value::Int(a * b / gcd(a, b)),
-                    _ => panic!("lcm expects 2 integers"),
-                },
-                _ => panic!("lcm expects 2 integers"),
+            match (integer(&args[0]), integer(&args[1])) {
+                (Some(a), Some(b)) if a.is_zero() || b.is_zero() => Ok(Value::Int(0)),
+                (Some(a), Some(b)) => Ok(Value::from((&a * &b).abs() / gcd(&a, &b))),
+                _ => error("type error", "lcm expects 2 integers".to_string()),
             }
         });
-        add_fn!("min", |args| {
-            if args.len() != 2 {
-                panic!("min expects 2 arguments, got {}", args.len());
-            }
-            match args[0] {
-                Value::Int(a) => match args[1] {
-                    Value::Int(b) => Value::Int(a.min(b)),
-                    _ => panic!("min expects 2 integers"),
-                },
-                Value::Float(a) => match args[1] {
-                    Value::Float(b) => Value::Float(a.min(b)),
-                    _ => panic!("min expects 2 floats"),
-                },
-                _ => panic!("min expects 2 numbers"),
-            }
+        add_fn!("numerator", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("numerator expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Rational(ref n, _) => Value::from(n.clone()),
+                ref value if value.is_exact() => value.clone(),
+                _ => return error("type error", "numerator expects a rational".to_string()),
+            })
         });
-        add_fn!("max", |args| {
-            if args.len() != 2 {
-                panic!("max expects 2 arguments, got {}", args.len());
+        add_fn!("denominator", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("denominator expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(match args[0] {
+                Value::Rational(_, ref d) => Value::from(d.clone()),
+                ref value if value.is_exact() => Value::Int(1),
+                _ => return error("type error", "denominator expects a rational".to_string()),
+            })
+        });
+        add_fn!("float", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("float expects 1 argument, got {}", args.len()),
+                );
             }
             match args[0] {
-                Value::Int(a) => match args[1] {
-                    Value::Int(b) => Value::Int(a.max(b)),
-                    _ => panic!("max expects 2 integers"),
-                },
-                Value::Float(a) => match args[1] {
-                    Value::Float(b) => Value::Float(a.max(b)),
-                    _ => panic!("max expects 2 floats"),
-                },
-                _ => panic!("max expects 2 numbers"),
+                ref value if value.is_exact() => Ok(Value::Float(real(value))),
+                Value::Float(value) => Ok(Value::Float(value)),
+                _ => error("type error", "float expects a real number".to_string()),
             }
         });
+        add_fn!("min", |args| extreme("min", BinaryOp::Lt, args));
+        add_fn!("max", |args| extreme("max", BinaryOp::Gt, args));
         add_fn!("clamp", |args| {
             if args.len() != 3 {
-                panic!("clamp expects 3 arguments, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("clamp expects 3 arguments, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Int(a) => match args[1] {
                     Value::Int(b) => match args[2] {
                         Value::Int(c) => Value::Int(a.max(b).min(c)),
-                        _ => panic!("clamp expects 3 integers"),
+                        _ => return error("type error", "clamp expects 3 integers".to_string()),
                     },
-                    _ => panic!("clamp expects 3 integers"),
+                    _ => return error("type error", "clamp expects 3 integers".to_string()),
                 },
                 Value::Float(a) => match args[1] {
                     Value::Float(b) => match args[2] {
                         Value::Float(c) => Value::Float(a.max(b).min(c)),
-                        _ => panic!("clamp expects 3 floats"),
+                        _ => return error("type error", "clamp expects 3 floats".to_string()),
                     },
-                    _ => panic!("clamp expects 3 floats"),
+                    _ => return error("type error", "clamp expects 3 floats".to_string()),
                 },
-                _ => panic!("clamp expects 3 numbers"),
-            }
+                _ => return error("type error", "clamp expects 3 numbers".to_string()),
+            })
         });
         add_fn!("Re", |args| {
             if args.len() != 1 {
-                panic!("Re expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("Re expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Complex(re, _) => Value::Float(re),
-                _ => panic!("Re expects a complex number"),
-            }
+                _ => return error("type error", "Re expects a complex number".to_string()),
+            })
         });
         add_fn!("Im", |args| {
             if args.len() != 1 {
-                panic!("Im expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("Im expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Complex(_, im) => Value::Float(im),
-                _ => panic!("Im expects a complex number"),
-            }
+                _ => return error("type error", "Im expects a complex number".to_string()),
+            })
         });
-        fn arg(args: &Vec<Value>) -> Value {
+        fn arg(args: &[Value]) -> RuntimeResult {
             if args.len() != 1 {
-                panic!("arg expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("arg expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Complex(re, im) => Value::Float(im.atan2(re)),
-                _ => panic!("arg expects a complex number"),
-            }
+                _ => return error("type error", "arg expects a complex number".to_string()),
+            })
         }
-        add_fn!("arg", arg);
-        add_fn!("phase", arg);
+        add_fn!("arg", |args| arg(args));
+        add_fn!("phase", |args| arg(args));
         add_fn!("conj", |args| {
             if args.len() != 1 {
-                panic!("conj expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("conj expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
+            Ok(match args[0] {
                 Value::Complex(re, im) => Value::Complex(re, -im),
-                _ => panic!("conj expects a complex number"),
-            }
+                _ => return error("type error", "conj expects a complex number".to_string()),
+            })
         });
         add_fn!("cis", |args| {
             if args.len() != 1 {
-                panic!("cis expects 1 argument, got {}", args.len());
+                return error(
+                    "invalid arguments",
+                    format!("cis expects 1 argument, got {}", args.len()),
+                );
             }
-            match args[0] {
-                Value::Float(x) => Value::Complex(x.cos(), x.sin()),
-                _ => panic!("cis expects a number"),
+            match complex(&args[0]) {
+                Some(z) => Ok((Complex::I * z).exp().into()),
+                None => error("type error", "cis expects a number".to_string()),
             }
         });
     }
 }
 
-fn gcd(mut a: i32, mut b: i32) -> i32 {
-    while b != 0 {
-        let t = b;
-        b = a % b;
-        a = t;
+/// The least or greatest of the arguments, or of the items of a lone list,
+/// vector or range, for `min` and `max`. An item replaces the extreme so far
+/// when `op` holds between them.
+fn extreme(name: &str, op: BinaryOp, args: &[Value]) -> RuntimeResult {
+    let mut values: Box<dyn Iterator<Item = Value> + '_> = match args {
+        [items @ (Value::List(_) | Value::Vector(_) | Value::Range(_))] => {
+            iterate_all(items.clone())?
+        }
+        args => Box::new(args.iter().cloned()),
+    };
+    let Some(mut result) = values.next() else {
+        return error(
+            "invalid arguments",
+            format!("{} expects at least 1 value", name),
+        );
+    };
+    for value in values {
+        if bool::from(binary(op, value.clone(), result.clone())?) {
+            result = value;
+        }
+    }
+    Ok(result)
+}
+
+/// The greatest common divisor of `a` and `b`, which is never negative.
+pub(crate) fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while !b.is_zero() {
+        let t = &a % &b;
+        a = b;
+        b = t;
     }
     a
 }
+
+/// Widens an `Int` or `BigInt` so the two can be used together.
+fn integer(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(x) => Some(BigInt::from(*x)),
+        Value::BigInt(x) => Some(x.clone()),
+        _ => None,
+    }
+}
@@ -1,2 +1,8 @@
+mod algebra;
+pub mod calculus;
 mod io;
-mod math;
+pub mod linalg;
+mod lists;
+pub mod math;
+mod solve;
+mod strings;
@@ -0,0 +1,215 @@
+use common::SpectreError;
+
+use super::calculus::real_number;
+use crate::{error, Interpreter, RuntimeResult, Value};
+
+/// How many steps Newton's method and the secant method take before giving
+/// up.
+const MAX_STEPS: usize = 100;
+/// The interval `solve(f)` searches when it isn't given one.
+const DEFAULT_INTERVAL: (f64, f64) = (-100.0, 100.0);
+/// How many pieces an interval is split into when searching it for roots.
+/// Roots closer together than a piece may be missed.
+const SAMPLES: usize = 10_000;
+
+impl Interpreter {
+    pub fn solver(&mut self) {
+        macro_rules! add_fn {
+            ($name:literal, |$interpreter:ident, $args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|$interpreter, $args| $body));
+            };
+        }
+
+        add_fn!("solve", |interpreter, args| {
+            let function = function(args.first(), "solve")?;
+            match args[1..] {
+                [] => roots(interpreter, &function, DEFAULT_INTERVAL),
+                [ref guess] => {
+                    let guess = real_number(guess)?;
+                    Ok(Value::Float(newton(interpreter, &function, guess)?))
+                }
+                [ref a, ref b] => {
+                    let interval = (real_number(a)?, real_number(b)?);
+                    roots(interpreter, &function, interval)
+                }
+                _ => error(
+                    "invalid arguments",
+                    format!("solve expects 1 to 3 arguments, got {}", args.len()),
+                ),
+            }
+        });
+        add_fn!("bisect", |interpreter, args| {
+            if args.len() != 3 {
+                return error(
+                    "invalid arguments",
+                    format!("bisect expects 3 arguments, got {}", args.len()),
+                );
+            }
+            let function = function(args.first(), "bisect")?;
+            let (a, b) = (real_number(&args[1])?, real_number(&args[2])?);
+            let (fa, fb) = (
+                evaluate(interpreter, &function, a)?,
+                evaluate(interpreter, &function, b)?,
+            );
+            if fa * fb > 0.0 || fa.is_nan() || fb.is_nan() {
+                return error(
+                    "invalid arguments",
+                    format!("The function must change sign between {} and {}", a, b),
+                );
+            }
+            Ok(Value::Float(bisect(interpreter, &function, a, b)?))
+        });
+    }
+}
+
+/// Checks that the first argument of `name` is a function.
+fn function(value: Option<&Value>, name: &str) -> RuntimeResult {
+    match value {
+        Some(function @ (Value::Function(_) | Value::NativeFunction(_))) => Ok(function.clone()),
+        Some(value) => error("type error", format!("Cannot solve {}", value)),
+        None => error(
+            "invalid arguments",
+            format!("{} expects a function to solve", name),
+        ),
+    }
+}
+
+/// Calls `function` at `x`. Complex results are NaN, since they're no nearer
+/// a real root than anywhere else.
+fn evaluate(interpreter: &mut Interpreter, function: &Value, x: f64) -> Result<f64, SpectreError> {
+    match interpreter.call(function.clone(), vec![Value::Float(x)])? {
+        Value::Complex(..) => Ok(f64::NAN),
+        value => real_number(&value),
+    }
+}
+
+/// Finds a root of `function` near `guess` by Newton's method, using its
+/// symbolic derivative, or the secant method if it can't be differentiated.
+fn newton(
+    interpreter: &mut Interpreter,
+    function: &Value,
+    guess: f64,
+) -> Result<f64, SpectreError> {
+    let Ok(derivative) = interpreter.derivative(function.clone()) else {
+        return secant(interpreter, function, guess);
+    };
+
+    let mut x = guess;
+    for _ in 0..MAX_STEPS {
+        let y = evaluate(interpreter, function, x)?;
+        if y == 0.0 {
+            return Ok(x);
+        }
+        // Derivatives that can't be evaluated, like those of built-in
+        // functions passed in, are approximated instead
+        let Ok(slope) = evaluate(interpreter, &derivative, x) else {
+            return secant(interpreter, function, guess);
+        };
+        let step = y / slope;
+        x -= step;
+        if !x.is_finite() {
+            break;
+        }
+        if step.abs() <= interpreter.tolerance * x.abs().max(1.0) {
+            return Ok(x);
+        }
+    }
+    error(
+        "not convergent",
+        format!("Newton's method didn't converge from {}", guess),
+    )
+}
+
+/// Finds a root of `function` near `guess` by the secant method, which
+/// approximates the derivative from the last two steps.
+fn secant(
+    interpreter: &mut Interpreter,
+    function: &Value,
+    guess: f64,
+) -> Result<f64, SpectreError> {
+    let (mut x0, mut x1) = (guess, guess + 1e-4 * guess.abs().max(1.0));
+    let mut y0 = evaluate(interpreter, function, x0)?;
+    for _ in 0..MAX_STEPS {
+        let y1 = evaluate(interpreter, function, x1)?;
+        if y1 == 0.0 {
+            return Ok(x1);
+        }
+        let step = y1 * (x1 - x0) / (y1 - y0);
+        (x0, y0) = (x1, y1);
+        x1 -= step;
+        if !x1.is_finite() {
+            break;
+        }
+        if step.abs() <= interpreter.tolerance * x1.abs().max(1.0) {
+            return Ok(x1);
+        }
+    }
+    error(
+        "not convergent",
+        format!("The secant method didn't converge from {}", guess),
+    )
+}
+
+/// Narrows `[a, b]`, over which `function` changes sign, down to a root.
+/// Halving always terminates, so this goes to full precision.
+fn bisect(
+    interpreter: &mut Interpreter,
+    function: &Value,
+    a: f64,
+    b: f64,
+) -> Result<f64, SpectreError> {
+    let (mut a, mut b) = (a.min(b), a.max(b));
+    let mut fa = evaluate(interpreter, function, a)?;
+    if fa == 0.0 {
+        return Ok(a);
+    }
+    loop {
+        let mid = a + (b - a) / 2.0;
+        if mid <= a || mid >= b {
+            return Ok(mid);
+        }
+        let fm = evaluate(interpreter, function, mid)?;
+        if fm == 0.0 {
+            return Ok(mid);
+        }
+        if fm.signum() == fa.signum() {
+            (a, fa) = (mid, fm);
+        } else {
+            b = mid;
+        }
+    }
+}
+
+/// Finds every root of `function` in `interval` where it crosses zero, by
+/// looking for sign changes between evenly spaced samples and bisecting them.
+fn roots(interpreter: &mut Interpreter, function: &Value, interval: (f64, f64)) -> RuntimeResult {
+    let (a, b) = (interval.0.min(interval.1), interval.0.max(interval.1));
+    if !a.is_finite() || !b.is_finite() {
+        return error(
+            "invalid arguments",
+            format!("Can't search for roots between {} and {}", a, b),
+        );
+    }
+
+    let step = (b - a) / SAMPLES as f64;
+    let mut roots = vec![];
+    let (mut x0, mut y0) = (a, evaluate(interpreter, function, a)?);
+    if y0 == 0.0 {
+        roots.push(x0);
+    }
+    for i in 1..=SAMPLES {
+        let x1 = if i == SAMPLES { b } else { a + i as f64 * step };
+        let y1 = evaluate(interpreter, function, x1)?;
+        if y1 == 0.0 {
+            roots.push(x1);
+        } else if y0 * y1 < 0.0 {
+            let root = bisect(interpreter, function, x0, x1)?;
+            // A sign change across a pole, as in 1/x, isn't a root
+            if evaluate(interpreter, function, root)?.abs() <= y0.abs().min(y1.abs()) {
+                roots.push(root);
+            }
+        }
+        (x0, y0) = (x1, y1);
+    }
+    Ok(Value::Vector(roots.into_iter().map(Value::Float).collect()))
+}
@@ -0,0 +1,84 @@
+use num_bigint::BigInt;
+
+use crate::{error, Interpreter, Value};
+
+impl Interpreter {
+    pub fn strings(&mut self) {
+        macro_rules! add_fn {
+            ($name:literal, |$args:ident| $body:expr) => {
+                self.add_var($name, Value::NativeFunction(|_, $args| $body));
+            };
+        }
+
+        add_fn!("str", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("str expects 1 argument, got {}", args.len()),
+                );
+            }
+            Ok(Value::Str(args[0].to_string().into()))
+        });
+        add_fn!("len", |args| {
+            if args.len() != 1 {
+                return error(
+                    "invalid arguments",
+                    format!("len expects 1 argument, got {}", args.len()),
+                );
+            }
+            let len = match &args[0] {
+                Value::Str(text) => text.chars().count(),
+                Value::Vector(v) | Value::List(v) => v.len(),
+                // The number of rows, as indexing and iterating go by rows
+                Value::Matrix(rows) => rows.len(),
+                Value::Range(range) => match range.count() {
+                    Some(len) => len,
+                    None => return error("type error", format!("{} is endless", range)),
+                },
+                value => return error("type error", format!("{} has no length", value)),
+            };
+            Ok(Value::from(BigInt::from(len)))
+        });
+        // Counting from 0 and up to but not including the end, as indices do
+        add_fn!("slice", |args| {
+            let (value, start, end) = match args {
+                [value, Value::Int(start)] => (value, *start, None),
+                [value, Value::Int(start), Value::Int(end)] => (value, *start, Some(*end)),
+                [_, _] | [_, _, _] => {
+                    return error("type error", "slice expects integer bounds".to_string())
+                }
+                _ => {
+                    return error(
+                        "invalid arguments",
+                        format!("slice expects 2 or 3 arguments, got {}", args.len()),
+                    )
+                }
+            };
+            let len = match value {
+                Value::Str(text) => text.chars().count(),
+                Value::Vector(v) | Value::List(v) => v.len(),
+                value => return error("type error", format!("Cannot slice {}", value)),
+            };
+            let end = end.unwrap_or(len as i32);
+            if start < 0 || end < start || end as usize > len {
+                return error(
+                    "index out of range",
+                    format!("Cannot slice from {} to {} with length {}", start, end, len),
+                );
+            }
+            let (start, end) = (start as usize, end as usize);
+            Ok(match value {
+                Value::Str(text) => Value::Str(
+                    text.chars()
+                        .skip(start)
+                        .take(end - start)
+                        .collect::<String>()
+                        .into(),
+                ),
+                Value::Vector(v) => Value::Vector(v[start..end].to_vec()),
+                Value::List(items) => Value::List(items[start..end].to_vec()),
+                _ => unreachable!("checked when finding the length"),
+            })
+        });
+    }
+}
@@ -0,0 +1,547 @@
+use std::{cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
+
+use parser::{BinaryOp, Node, Spanned, UnaryOp};
+
+use crate::{Function, Layout, Scope, Value};
+
+/// A single VM instruction. Operands are popped off the VM's stack and
+/// results pushed back onto it.
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    Constant(Value),
+    /// Pushes the variable in a slot of the scope a number of levels up the
+    /// chain.
+    Load(usize, usize),
+    /// Assigns the top of the stack to a variable, leaving it on the stack.
+    Store(usize, usize),
+    /// Pops a value and binds it to a slot of the current scope, shadowing
+    /// any enclosing variable.
+    Define(usize),
+    Pop,
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    /// Joins the top values into a string.
+    Interpolate(usize),
+    /// Collects the top values into a vector.
+    Vector(usize),
+    /// Collects the top values into a list.
+    List(usize),
+    /// Collects the top values into a matrix with rows of the given lengths.
+    Matrix(Vec<usize>),
+    Jump(usize),
+    /// Runs the second chunk for as long as the first evaluates to true. The
+    /// chunks run in the current scope on top of the caller's stack, which
+    /// is cut back to where it was however `break` or `continue` leave them.
+    While(Rc<Chunk>, Rc<Chunk>),
+    /// Leaves the innermost loop.
+    Break,
+    /// Skips to the next iteration of the innermost loop.
+    Continue,
+    /// Pops a value and returns it from the innermost function.
+    Return,
+    /// Pushes a variable like [`Instruction::Load`] and jumps, unless the
+    /// variable is unset, for a subscripted name that may be a variable of
+    /// its own.
+    LoadOrJump(usize, usize, usize),
+    /// Pops a condition and jumps if it is false.
+    JumpIfFalse(usize),
+    /// Calls the function under the given number of arguments.
+    Call(usize),
+    /// Calls a function under its subscript and the given number of
+    /// arguments, passing the subscript last, or indexes it with the
+    /// subscript and calls the element.
+    CallSubscripted(usize),
+    /// Pops an index and indexes the value under it.
+    Index,
+    /// Creates a closure over the current scope.
+    Function(Rc<Prototype>),
+    /// Pops a range or collection and folds the body over its elements, for a
+    /// `∑` or `∏` over it.
+    Fold(BinaryOp, Rc<Code>),
+    /// Pops a range or collection and runs the body with each of its
+    /// elements.
+    For(Rc<Code>),
+    /// Pops the bounds of a range, after its step if the first flag is set,
+    /// and builds it. The second flag includes the end.
+    Range(bool, bool),
+    /// Pops the bounds of a `∫` and integrates its body between them.
+    Integral(Rc<Code>),
+}
+
+/// A sequence of instructions, each with the source range it came from.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Code that runs in a scope of its own, such as a function body.
+#[derive(Debug)]
+pub struct Code {
+    pub layout: Layout,
+    /// The slots the arguments are stored in, in order.
+    pub params: Vec<usize>,
+    pub chunk: Chunk,
+}
+
+/// Everything needed to create a function at runtime, compiled ahead of time.
+#[derive(Debug)]
+pub struct Prototype {
+    pub name: Rc<str>,
+    pub params: Vec<Rc<str>>,
+    pub body: Spanned<Node>,
+    pub code: Rc<Code>,
+}
+
+/// Compiles nodes to bytecode, resolving every variable to a slot.
+pub struct Compiler {
+    /// Layouts of the scopes enclosing the code being compiled, outermost
+    /// first. The globals come before all of them.
+    scopes: Vec<HashMap<Rc<str>, usize>>,
+    globals: Rc<RefCell<Scope>>,
+    chunk: Chunk,
+}
+
+impl Compiler {
+    pub fn new(globals: Rc<RefCell<Scope>>) -> Self {
+        Self {
+            scopes: vec![],
+            globals,
+            chunk: Chunk::default(),
+        }
+    }
+
+    /// Compiles top-level code, which runs in the global scope.
+    pub fn compile(mut self, node: &Spanned<Node>) -> Chunk {
+        self.expr(node);
+        self.chunk
+    }
+
+    /// Compiles the body of a function that wasn't compiled ahead of time,
+    /// such as one built by arithmetic on other functions, resolving its
+    /// variables against the scopes it closes over.
+    pub fn function(function: &Function) -> Code {
+        let mut scopes = vec![];
+        let mut scope = Rc::clone(&function.scope);
+        loop {
+            let parent = scope.borrow().parent.clone();
+            match parent {
+                Some(parent) => {
+                    scopes.push((**scope.borrow().layout()).clone());
+                    scope = parent;
+                }
+                None => break,
+            }
+        }
+        scopes.reverse();
+
+        let mut compiler = Self {
+            scopes,
+            globals: scope,
+            chunk: Chunk::default(),
+        };
+        compiler.scoped(&function.params, &function.body)
+    }
+
+    fn emit(&mut self, instruction: Instruction, range: &Range<usize>) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.ranges.push(range.clone());
+        self.chunk.code.len() - 1
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = self.chunk.code.len();
+        match &mut self.chunk.code[at] {
+            Instruction::Jump(to)
+            | Instruction::JumpIfFalse(to)
+            | Instruction::LoadOrJump(_, _, to) => *to = target,
+            _ => unreachable!("only jumps are patched"),
+        }
+    }
+
+    /// Finds the depth and slot of the nearest variable called `name`.
+    fn resolve(&self, name: &str) -> Option<(usize, usize)> {
+        for (depth, layout) in self.scopes.iter().rev().enumerate() {
+            if let Some(&slot) = layout.get(name) {
+                return Some((depth, slot));
+            }
+        }
+        let globals = self.globals.borrow();
+        globals
+            .layout()
+            .get(name)
+            .map(|&slot| (self.scopes.len(), slot))
+    }
+
+    /// Resolves a variable that is read, which may be a global that is
+    /// defined later.
+    fn variable(&mut self, name: &Rc<str>) -> (usize, usize) {
+        match self.resolve(name) {
+            Some(variable) => variable,
+            // Functions may refer to globals that are defined later
+            None => (
+                self.scopes.len(),
+                self.globals.borrow_mut().declare(Rc::clone(name)),
+            ),
+        }
+    }
+
+    /// Adds `name` to the innermost scope, or the globals at the top level,
+    /// returning its slot.
+    fn declare(&mut self, name: &Rc<str>) -> usize {
+        match self.scopes.last_mut() {
+            Some(layout) => {
+                let slot = layout.len();
+                *layout.entry(Rc::clone(name)).or_insert(slot)
+            }
+            None => self.globals.borrow_mut().declare(Rc::clone(name)),
+        }
+    }
+
+    /// Compiles `body` to run in a new scope holding `params` and every
+    /// variable the body assigns that no enclosing scope already has.
+    fn scoped(&mut self, params: &[Rc<str>], body: &Spanned<Node>) -> Code {
+        let mut layout = HashMap::new();
+        let params = params
+            .iter()
+            .map(|name| {
+                let slot = layout.len();
+                *layout.entry(Rc::clone(name)).or_insert(slot)
+            })
+            .collect();
+
+        let mut assigned = vec![];
+        assignments(&body.node, &mut assigned);
+        for (name, defines) in assigned {
+            if defines || self.resolve(&name).is_none() {
+                let slot = layout.len();
+                layout.entry(name).or_insert(slot);
+            }
+        }
+
+        self.scopes.push(layout);
+        let chunk = self.chunk(body);
+        let layout = self.scopes.pop().unwrap();
+
+        Code {
+            layout: Rc::new(layout),
+            params,
+            chunk,
+        }
+    }
+
+    fn expr(&mut self, node: &Spanned<Node>) {
+        let range = &node.range;
+        match &node.node {
+            Node::Int(x) => {
+                self.emit(Instruction::Constant(Value::Int(*x)), range);
+            }
+            Node::BigInt(x) => {
+                self.emit(Instruction::Constant(Value::BigInt(x.clone())), range);
+            }
+            Node::Float(x) => {
+                self.emit(Instruction::Constant(Value::Float(*x)), range);
+            }
+            Node::Str(text) => {
+                self.emit(Instruction::Constant(Value::Str(Rc::clone(text))), range);
+            }
+            Node::Interpolation(parts) => {
+                for part in parts {
+                    self.expr(part);
+                }
+                self.emit(Instruction::Interpolate(parts.len()), range);
+            }
+            Node::Identifier(name) => {
+                let (depth, slot) = self.variable(name);
+                self.emit(Instruction::Load(depth, slot), range);
+            }
+            Node::Subscript(name, base, index) => {
+                let (depth, slot) = self.variable(name);
+                let jump_to_end = self.emit(Instruction::LoadOrJump(depth, slot, 0), range);
+                let (depth, slot) = self.variable(base);
+                self.emit(Instruction::Load(depth, slot), range);
+                self.expr(index);
+                self.emit(Instruction::Index, range);
+                self.patch(jump_to_end);
+            }
+            Node::Index(node, index) => {
+                self.expr(node);
+                self.expr(index);
+                self.emit(Instruction::Index, range);
+            }
+            Node::Vector(nodes) => {
+                for node in nodes {
+                    self.expr(node);
+                }
+                self.emit(Instruction::Vector(nodes.len()), range);
+            }
+            Node::List(nodes) => {
+                for node in nodes {
+                    self.expr(node);
+                }
+                self.emit(Instruction::List(nodes.len()), range);
+            }
+            Node::Matrix(rows) => {
+                for node in rows.iter().flatten() {
+                    self.expr(node);
+                }
+                let lengths = rows.iter().map(|row| row.len()).collect();
+                self.emit(Instruction::Matrix(lengths), range);
+            }
+            Node::Assignment(name, value) => {
+                self.expr(value);
+                let (depth, slot) = match self.resolve(name) {
+                    Some(variable) => variable,
+                    None => (0, self.declare(name)),
+                };
+                self.emit(Instruction::Store(depth, slot), range);
+            }
+            Node::Unary(op, node) => {
+                self.expr(node);
+                self.emit(Instruction::Unary(*op), range);
+            }
+            Node::Binary(left, op @ (BinaryOp::And | BinaryOp::Or | BinaryOp::Implies), right) => {
+                self.logical(*op, left, right, range);
+            }
+            Node::Binary(left, op, right) => {
+                self.expr(left);
+                self.expr(right);
+                self.emit(Instruction::Binary(*op), range);
+            }
+            Node::If(cond, then, else_case) => {
+                self.expr(cond);
+                let jump_to_else = self.emit(Instruction::JumpIfFalse(0), range);
+                self.expr(then);
+                let jump_to_end = self.emit(Instruction::Jump(0), range);
+                self.patch(jump_to_else);
+                match else_case {
+                    Some(else_case) => self.expr(else_case),
+                    None => {
+                        self.emit(Instruction::Constant(Value::Int(0)), range);
+                    }
+                }
+                self.patch(jump_to_end);
+            }
+            Node::While(cond, body) => {
+                let cond = self.chunk(cond);
+                let body = self.chunk(body);
+                self.emit(Instruction::While(Rc::new(cond), Rc::new(body)), range);
+            }
+            Node::Break => {
+                self.emit(Instruction::Break, range);
+            }
+            Node::Continue => {
+                self.emit(Instruction::Continue, range);
+            }
+            Node::Return(value) => {
+                match value {
+                    Some(value) => self.expr(value),
+                    None => {
+                        self.emit(Instruction::Constant(Value::Int(0)), range);
+                    }
+                }
+                self.emit(Instruction::Return, range);
+            }
+            Node::For(var, iterable, body) => {
+                self.expr(iterable);
+                let code = self.scoped(std::slice::from_ref(var), body);
+                self.emit(Instruction::For(Rc::new(code)), range);
+            }
+            Node::Range(start, end, step, inclusive) => {
+                self.expr(start);
+                self.expr(end);
+                if let Some(step) = step {
+                    self.expr(step);
+                }
+                self.emit(Instruction::Range(step.is_some(), *inclusive), range);
+            }
+            Node::FnDef(name, params, body) => {
+                // Declared first so the body can call itself
+                let slot = self.declare(name);
+                let prototype = self.prototype(Rc::clone(name), params, body);
+                self.emit(Instruction::Function(prototype), range);
+                self.emit(Instruction::Define(slot), range);
+                self.emit(Instruction::Constant(Value::Int(0)), range);
+            }
+            Node::Lambda(params, body) => {
+                let prototype = self.prototype("λ".into(), params, body);
+                self.emit(Instruction::Function(prototype), range);
+            }
+            Node::Call(function, args) => {
+                if let Node::Subscript(name, base, index) = &function.node {
+                    // Whether the subscript is part of the name is only known
+                    // once it runs
+                    let (depth, slot) = self.variable(name);
+                    let jump_to_named = self.emit(Instruction::LoadOrJump(depth, slot, 0), range);
+                    let (depth, slot) = self.variable(base);
+                    self.emit(Instruction::Load(depth, slot), range);
+                    self.expr(index);
+                    for arg in args {
+                        self.expr(arg);
+                    }
+                    self.emit(Instruction::CallSubscripted(args.len()), range);
+                    let jump_to_end = self.emit(Instruction::Jump(0), range);
+                    self.patch(jump_to_named);
+                    for arg in args {
+                        self.expr(arg);
+                    }
+                    self.emit(Instruction::Call(args.len()), range);
+                    self.patch(jump_to_end);
+                    return;
+                }
+                self.expr(function);
+                for arg in args {
+                    self.expr(arg);
+                }
+                self.emit(Instruction::Call(args.len()), range);
+            }
+            Node::BigOperator(op, index, iterable, body) => {
+                self.expr(iterable);
+                let code = self.scoped(std::slice::from_ref(index), body);
+                self.emit(Instruction::Fold(*op, Rc::new(code)), range);
+            }
+            Node::Integral(var, start, end, body) => {
+                self.expr(start);
+                self.expr(end);
+                let code = self.scoped(std::slice::from_ref(var), body);
+                self.emit(Instruction::Integral(Rc::new(code)), range);
+            }
+            Node::Statements(nodes) => {
+                if nodes.is_empty() {
+                    self.emit(Instruction::Constant(Value::Int(0)), range);
+                }
+                for (i, node) in nodes.iter().enumerate() {
+                    if i > 0 {
+                        self.emit(Instruction::Pop, range);
+                    }
+                    self.expr(node);
+                }
+            }
+            Node::Eof => {
+                self.emit(Instruction::Constant(Value::Int(0)), range);
+            }
+        }
+    }
+
+    /// Compiles `node` on its own, in the current scope.
+    fn chunk(&mut self, node: &Spanned<Node>) -> Chunk {
+        let outer = std::mem::take(&mut self.chunk);
+        self.expr(node);
+        std::mem::replace(&mut self.chunk, outer)
+    }
+
+    /// Compiles `and`, `or` or `→`, jumping over the right side when the
+    /// left settles the result.
+    fn logical(
+        &mut self,
+        op: BinaryOp,
+        left: &Spanned<Node>,
+        right: &Spanned<Node>,
+        range: &Range<usize>,
+    ) {
+        self.expr(left);
+        if op == BinaryOp::Or {
+            self.emit(Instruction::Unary(UnaryOp::Not), range);
+        }
+        let skip_right = self.emit(Instruction::JumpIfFalse(0), range);
+        self.expr(right);
+        let jump_to_false = self.emit(Instruction::JumpIfFalse(0), range);
+        if op != BinaryOp::And {
+            self.patch(skip_right);
+        }
+        self.emit(Instruction::Constant(Value::Bool(true)), range);
+        let jump_to_end = self.emit(Instruction::Jump(0), range);
+        self.patch(jump_to_false);
+        if op == BinaryOp::And {
+            self.patch(skip_right);
+        }
+        self.emit(Instruction::Constant(Value::Bool(false)), range);
+        self.patch(jump_to_end);
+    }
+
+    fn prototype(
+        &mut self,
+        name: Rc<str>,
+        params: &[Rc<str>],
+        body: &Spanned<Node>,
+    ) -> Rc<Prototype> {
+        let code = self.scoped(params, body);
+        Rc::new(Prototype {
+            name,
+            params: params.to_vec(),
+            body: body.clone(),
+            code: Rc::new(code),
+        })
+    }
+}
+
+/// Collects the variables `node` assigns in its own scope, and whether each
+/// is defined there regardless of enclosing scopes, as functions are.
+fn assignments(node: &Node, names: &mut Vec<(Rc<str>, bool)>) {
+    match node {
+        Node::Int(_)
+        | Node::BigInt(_)
+        | Node::Float(_)
+        | Node::Str(_)
+        | Node::Identifier(_)
+        | Node::Break
+        | Node::Continue
+        | Node::Return(None)
+        | Node::Eof => {}
+        Node::Vector(nodes)
+        | Node::List(nodes)
+        | Node::Interpolation(nodes)
+        | Node::Statements(nodes) => {
+            for node in nodes {
+                assignments(&node.node, names);
+            }
+        }
+        Node::Matrix(rows) => {
+            for node in rows.iter().flatten() {
+                assignments(&node.node, names);
+            }
+        }
+        Node::Assignment(name, value) => {
+            names.push((Rc::clone(name), false));
+            assignments(&value.node, names);
+        }
+        Node::Unary(_, node) | Node::Subscript(_, _, node) | Node::Return(Some(node)) => {
+            assignments(&node.node, names)
+        }
+        Node::Binary(left, _, right) | Node::While(left, right) | Node::Index(left, right) => {
+            assignments(&left.node, names);
+            assignments(&right.node, names);
+        }
+        Node::If(cond, then, else_case) => {
+            assignments(&cond.node, names);
+            assignments(&then.node, names);
+            if let Some(else_case) = else_case {
+                assignments(&else_case.node, names);
+            }
+        }
+        // Function bodies and big operators have scopes of their own
+        Node::FnDef(name, ..) => names.push((Rc::clone(name), true)),
+        Node::Lambda(..) => {}
+        Node::Call(function, args) => {
+            assignments(&function.node, names);
+            for arg in args {
+                assignments(&arg.node, names);
+            }
+        }
+        Node::Integral(_, start, end, _) => {
+            assignments(&start.node, names);
+            assignments(&end.node, names);
+        }
+        Node::For(_, iterable, _) | Node::BigOperator(_, _, iterable, _) => {
+            assignments(&iterable.node, names)
+        }
+        Node::Range(start, end, step, _) => {
+            assignments(&start.node, names);
+            assignments(&end.node, names);
+            if let Some(step) = step {
+                assignments(&step.node, names);
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    f64::consts::PI,
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// A complex number, along with the elementary functions extended to the
+/// complex plane. Multivalued functions return their principal value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex::new(0.0, 0.0);
+    pub const ONE: Complex = Complex::new(1.0, 0.0);
+    pub const I: Complex = Complex::new(0.0, 1.0);
+
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self::new(r * theta.cos(), r * theta.sin())
+    }
+
+    pub fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// The angle from the positive real axis, in (-π, π].
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn recip(self) -> Self {
+        let norm = self.norm_sqr();
+        Self::new(self.re / norm, -self.im / norm)
+    }
+
+    pub fn exp(self) -> Self {
+        Self::from_polar(self.re.exp(), self.im)
+    }
+
+    /// The principal logarithm, with imaginary part in (-π, π].
+    pub fn ln(self) -> Self {
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    /// The principal square root, with non-negative real part.
+    pub fn sqrt(self) -> Self {
+        // Found directly rather than through `ln` so that perfect squares come
+        // out exact
+        let r = self.abs();
+        Self::new(
+            ((r + self.re) / 2.0).sqrt(),
+            ((r - self.re) / 2.0).sqrt().copysign(self.im),
+        )
+    }
+
+    /// Raises to an integer power by repeated squaring, which is exact
+    /// wherever the products are.
+    pub fn powi(self, n: i32) -> Self {
+        let mut base = if n < 0 { self.recip() } else { self };
+        let mut n = n.unsigned_abs();
+        let mut result = Self::ONE;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+        result
+    }
+
+    /// The principal value of `self` raised to `w`.
+    pub fn pow(self, w: Self) -> Self {
+        if w.im == 0.0 && w.re.fract() == 0.0 && w.re.abs() <= i32::MAX as f64 {
+            return self.powi(w.re as i32);
+        }
+        if self == Self::ZERO && w.re > 0.0 {
+            return Self::ZERO;
+        }
+        (w * self.ln()).exp()
+    }
+
+    pub fn sin(self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+
+    pub fn sinh(self) -> Self {
+        Self::new(
+            self.re.sinh() * self.im.cos(),
+            self.re.cosh() * self.im.sin(),
+        )
+    }
+
+    pub fn cosh(self) -> Self {
+        Self::new(
+            self.re.cosh() * self.im.cos(),
+            self.re.sinh() * self.im.sin(),
+        )
+    }
+
+    pub fn tanh(self) -> Self {
+        self.sinh() / self.cosh()
+    }
+
+    /// Converts degrees to radians.
+    pub fn to_radians(self) -> Self {
+        self * Self::new(PI / 180.0, 0.0)
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let norm = rhs.norm_sqr();
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / norm,
+            (self.im * rhs.re - self.re * rhs.im) / norm,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im.is_sign_negative() {
+            write!(f, "{} - {}i", self.re, -self.im)
+        } else {
+            write!(f, "{} + {}i", self.re, self.im)
+        }
+    }
+}
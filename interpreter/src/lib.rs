@@ -1,8 +1,19 @@
 mod builtins;
+mod compile;
+mod complex;
+mod ops;
+mod polynomial;
+mod range;
 mod run;
 mod scope;
+mod simplify;
 mod value;
+mod vm;
 
+pub use complex::Complex;
+pub use polynomial::Polynomial;
+pub use range::Range;
 pub use run::*;
 pub use scope::*;
+pub use simplify::simplify;
 pub use value::*;
@@ -0,0 +1,971 @@
+use std::rc::Rc;
+
+use common::SpectreError;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use parser::{BinaryOp, Node, Spanned, UnaryOp};
+
+use crate::{
+    builtins::calculus::derivative,
+    builtins::linalg::{identity, inverse, multiply, transpose},
+    builtins::math::gcd,
+    error, Complex, Polynomial, Range, RuntimeResult, Value, DEFAULT_TOLERANCE,
+};
+
+pub fn unary(op: UnaryOp, value: Value) -> RuntimeResult {
+    use UnaryOp::*;
+    Ok(match op {
+        Pos => value,
+        Neg => match value {
+            Value::Int(x) => match x.checked_neg() {
+                Some(x) => Value::Int(x),
+                None => Value::from(-BigInt::from(x)),
+            },
+            Value::BigInt(x) => Value::from(-x),
+            Value::Rational(n, d) => Value::Rational(-n, d),
+            Value::Float(x) => Value::Float(-x),
+            Value::Complex(r, i) => Value::Complex(-r, -i),
+            Value::Vector(v) => Value::Vector(map(v, |x| unary(Neg, x))?),
+            Value::Matrix(rows) => Value::Matrix(map(rows, |row| map(row, |x| unary(Neg, x)))?),
+            Value::Polynomial(p) => Value::Polynomial(p.neg()?),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Abs => match value {
+            Value::Int(x) => match x.checked_abs() {
+                Some(x) => Value::Int(x),
+                None => Value::from(BigInt::from(x).abs()),
+            },
+            Value::BigInt(x) => Value::from(x.abs()),
+            Value::Rational(n, d) => Value::Rational(n.abs(), d),
+            Value::Float(x) => Value::Float(x.abs()),
+            Value::Complex(r, i) => Value::Float(r.hypot(i)),
+            Value::Vector(v) => unary(Sqrt, dot(v.clone(), v)?)?,
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Floor => match value {
+            Value::Int(x) => Value::Int(x),
+            Value::BigInt(x) => Value::BigInt(x),
+            Value::Rational(n, d) => Value::from(n.div_floor(&d)),
+            Value::Float(x) => Value::Float(x.floor()),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Ceil => match value {
+            Value::Int(x) => Value::Int(x),
+            Value::BigInt(x) => Value::BigInt(x),
+            Value::Rational(n, d) => Value::from(n.div_ceil(&d)),
+            Value::Float(x) => Value::Float(x.ceil()),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Round => match value {
+            Value::Int(x) => Value::Int(x),
+            Value::BigInt(x) => Value::BigInt(x),
+            Value::Rational(n, d) => round(&n, &d),
+            Value::Float(x) => Value::Float(x.round()),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Degree => match value {
+            Value::Int(x) => Value::Float((x as f64).to_radians()),
+            Value::BigInt(x) => Value::Float(float(&x).to_radians()),
+            Value::Rational(n, d) => Value::Float(ratio(&n, &d).to_radians()),
+            Value::Complex(r, i) => Complex::new(r, i).to_radians().into(),
+            Value::Float(x) => Value::Float(x.to_radians()),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Fact => match value {
+            Value::Int(x) if x >= 0 => factorial(x)?,
+            Value::BigInt(x) if x.is_positive() => return overflow(),
+            Value::Float(x) if x >= 0.0 && x.fract() == 0.0 && x <= i32::MAX as f64 => {
+                factorial(x as i32)?
+            }
+            value if value.is_number() => {
+                return error(
+                    "type error",
+                    format!(
+                        "Cannot take the factorial of {}, which isn't a natural number",
+                        value
+                    ),
+                )
+            }
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Sqrt => match value {
+            Value::Int(x) => sqrt(x as f64),
+            Value::BigInt(x) => sqrt(float(&x)),
+            Value::Rational(n, d) => sqrt(ratio(&n, &d)),
+            Value::Complex(r, i) => Complex::new(r, i).sqrt().into(),
+            Value::Float(x) => sqrt(x),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Cbrt => match value {
+            Value::Int(x) => Value::Int((x as f64).cbrt() as i32),
+            Value::BigInt(x) => Value::Float(float(&x).cbrt()),
+            Value::Rational(n, d) => Value::Float(ratio(&n, &d).cbrt()),
+            Value::Complex(r, i) => Complex::new(r, i).pow((1.0 / 3.0).into()).into(),
+            Value::Float(x) => Value::Float(x.cbrt()),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Fort => match value {
+            Value::Int(x) => Value::Int((x as f64).sqrt().sqrt() as i32),
+            Value::BigInt(x) => Value::Float(float(&x).sqrt().sqrt()),
+            Value::Rational(n, d) => Value::Float(ratio(&n, &d).sqrt().sqrt()),
+            Value::Complex(r, i) => Complex::new(r, i).sqrt().sqrt().into(),
+            Value::Float(x) => Value::Float(x.sqrt().sqrt()),
+            value => return error("type error", format!("Cannot apply {} to {}", op, value)),
+        },
+        Transpose => transpose(value)?,
+        Prime => derivative(value)?,
+        Not => Value::Bool(!(bool::from(value))),
+    })
+}
+
+pub fn binary(op: BinaryOp, l_value: Value, r_value: Value) -> RuntimeResult {
+    // These treat their operands the same whatever their types
+    match op {
+        BinaryOp::Approx => return approx(l_value, r_value, DEFAULT_TOLERANCE),
+        BinaryOp::In => return Ok(Value::Bool(contains(r_value, l_value)?)),
+        BinaryOp::NotIn => return Ok(Value::Bool(!contains(r_value, l_value)?)),
+        BinaryOp::Implies => return Ok(Value::Bool(!bool::from(l_value) || r_value.into())),
+        _ => {}
+    }
+
+    // Small integers are the most common operands by far, as in loops, so
+    // they skip the checks for every other type below
+    if let (Value::Int(a), Value::Int(b)) = (&l_value, &r_value) {
+        if let Some(value) = int_binary(op, *a, *b) {
+            return Ok(value);
+        }
+    }
+
+    if matches!(l_value, Value::Str(_)) || matches!(r_value, Value::Str(_)) {
+        return string_binary(op, l_value, r_value);
+    }
+
+    if matches!(l_value, Value::List(_)) || matches!(r_value, Value::List(_)) {
+        return list_binary(op, l_value, r_value);
+    }
+
+    // Any number mixed with a polynomial is treated as a constant polynomial
+    if matches!(l_value, Value::Polynomial(_)) || matches!(r_value, Value::Polynomial(_)) {
+        return polynomial_binary(op, l_value, r_value);
+    }
+
+    // Any number mixed with a complex one is treated as complex
+    if let (Some(a), Some(b)) = (complex(&l_value), complex(&r_value)) {
+        if matches!(l_value, Value::Complex(..)) || matches!(r_value, Value::Complex(..)) {
+            return complex_binary(op, a, b);
+        }
+    }
+
+    // Big integers and rationals stay exact when mixed with each other and
+    // with integers, and are approximated by floats when mixed with anything
+    // else
+    let (l_value, r_value) = match (l_value, r_value) {
+        (a @ (Value::BigInt(_) | Value::Rational(..)), b)
+        | (a @ Value::Int(_), b @ (Value::BigInt(_) | Value::Rational(..)))
+            if b.is_exact() =>
+        {
+            return exact(op, a, b);
+        }
+        (a @ (Value::BigInt(_) | Value::Rational(..)), b @ Value::Float(_)) => {
+            (Value::Float(real(&a)), b)
+        }
+        (a @ Value::Float(_), b @ (Value::BigInt(_) | Value::Rational(..))) => {
+            (a, Value::Float(real(&b)))
+        }
+        values => values,
+    };
+
+    macro_rules! simple_binary_op {
+        ($op:tt, $name:ident, $checked:ident) => {
+            match (l_value, r_value) {
+                (Value::Int(a), Value::Int(b)) => match a.$checked(b) {
+                    Some(x) => Value::Int(x),
+                    None => return exact($name, Value::Int(a), Value::Int(b)),
+                },
+                (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) $op b),
+                (Value::Float(a), Value::Int(b)) => Value::Float(a $op (b as f64)),
+                (Value::Float(a), Value::Float(b)) => Value::Float(a $op b),
+                (Value::Vector(a), Value::Vector(b)) => {
+                    if a.len() != b.len() {
+                        return error(
+                            "type error",
+                            format!("Cannot apply {} to vectors of lengths {} and {}", $name, a.len(), b.len()),
+                        );
+                    }
+                    Value::Vector(zip(a, b, |a, b| binary($name, a, b))?)
+                }
+                (Value::Matrix(a), Value::Matrix(b)) => {
+                    if a.len() != b.len() || a.first().map(|row| row.len()) != b.first().map(|row| row.len()) {
+                        return error(
+                            "type error",
+                            format!("Cannot apply {} to matrices of different sizes", $name),
+                        );
+                    }
+                    Value::Matrix(zip(a, b, |a, b| zip(a, b, |a, b| binary($name, a, b)))?)
+                }
+                (l, r) => return error("type error", format!("Cannot apply {} to {} and {}", $name, l, r)),
+            }
+        };
+    }
+
+    use BinaryOp::*;
+    Ok(match op {
+        Add => simple_binary_op!(+, Add, checked_add),
+        Sub => simple_binary_op!(-, Sub, checked_sub),
+        Mul => match (l_value, r_value) {
+            (Value::Int(a), Value::Int(b)) => match a.checked_mul(b) {
+                Some(x) => Value::Int(x),
+                None => return exact(Mul, Value::Int(a), Value::Int(b)),
+            },
+            (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) * b),
+            (Value::Int(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Int(a), Mul, body))
+            }
+            (Value::Float(a), Value::Int(b)) => Value::Float(a * (b as f64)),
+            (Value::Float(a), Value::Float(b)) => Value::Float(a * b),
+            (Value::Float(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Float(a), Mul, body))
+            }
+            (Value::Function(f), Value::Int(a)) => {
+                f.map_body(|body| op_constant(body, Mul, Node::Int(a)))
+            }
+            (Value::Function(f), Value::Float(a)) => {
+                f.map_body(|body| op_constant(body, Mul, Node::Float(a)))
+            }
+            (a, Value::Vector(v)) if a.is_number() => {
+                Value::Vector(map(v, |x| binary(Mul, a.clone(), x))?)
+            }
+            (Value::Vector(v), b) if b.is_number() => {
+                Value::Vector(map(v, |x| binary(Mul, x, b.clone()))?)
+            }
+            (a, Value::Matrix(rows)) if a.is_number() => {
+                Value::Matrix(map(rows, |row| map(row, |x| binary(Mul, a.clone(), x)))?)
+            }
+            (Value::Matrix(rows), b) if b.is_number() => {
+                Value::Matrix(map(rows, |row| map(row, |x| binary(Mul, x, b.clone())))?)
+            }
+            (Value::Matrix(a), Value::Matrix(b)) => Value::Matrix(multiply(&a, &b)?),
+            (Value::Matrix(a), Value::Vector(v)) => {
+                let column: Vec<Vec<Value>> = v.into_iter().map(|x| vec![x]).collect();
+                Value::Vector(
+                    multiply(&a, &column)?
+                        .into_iter()
+                        .map(|mut row| row.remove(0))
+                        .collect(),
+                )
+            }
+            (Value::Vector(v), Value::Matrix(b)) => Value::Vector(multiply(&[v], &b)?.remove(0)),
+            (l, r) => return error("type error", format!("Cannot multiply {} by {}", l, r)),
+        },
+        Dot => match (l_value, r_value) {
+            (Value::Vector(a), Value::Vector(b)) => {
+                if a.len() != b.len() {
+                    return error(
+                        "type error",
+                        format!(
+                            "Cannot take the dot product of vectors of lengths {} and {}",
+                            a.len(),
+                            b.len()
+                        ),
+                    );
+                }
+                dot(a, b)?
+            }
+            (l, r) => binary(Mul, l, r)?,
+        },
+        Cross => match (l_value, r_value) {
+            (Value::Vector(a), Value::Vector(b)) => {
+                if a.len() != 3 || b.len() != 3 {
+                    return error(
+                        "type error",
+                        "The cross product is only defined for 3-dimensional vectors".to_string(),
+                    );
+                }
+                let component = |i: usize, j: usize| {
+                    binary(
+                        Sub,
+                        binary(Mul, a[i].clone(), b[j].clone())?,
+                        binary(Mul, a[j].clone(), b[i].clone())?,
+                    )
+                };
+                Value::Vector(vec![component(1, 2)?, component(2, 0)?, component(0, 1)?])
+            }
+            (l, r) => binary(Mul, l, r)?,
+        },
+        Div => match (l_value, r_value) {
+            (Value::Int(_), Value::Int(0)) => return division_by_zero(),
+            // Dividing integers only gives an integer if the division is exact
+            (Value::Int(a), Value::Int(b)) => match a.checked_rem(b) {
+                Some(0) => Value::Int(a / b),
+                _ => return exact(Div, Value::Int(a), Value::Int(b)),
+            },
+            (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) / b),
+            (Value::Int(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Int(a), Div, body))
+            }
+            (Value::Float(a), Value::Int(b)) => Value::Float(a / (b as f64)),
+            (Value::Float(a), Value::Float(b)) => Value::Float(a / b),
+            (Value::Float(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Float(a), Div, body))
+            }
+            (Value::Function(f), Value::Int(a)) => {
+                f.map_body(|body| op_constant(body, Div, Node::Int(a)))
+            }
+            (Value::Function(f), Value::Float(a)) => {
+                f.map_body(|body| op_constant(body, Div, Node::Float(a)))
+            }
+            (Value::Vector(v), b) if b.is_number() => {
+                Value::Vector(map(v, |x| binary(Div, x, b.clone()))?)
+            }
+            (Value::Matrix(rows), b) if b.is_number() => {
+                Value::Matrix(map(rows, |row| map(row, |x| binary(Div, x, b.clone())))?)
+            }
+            (l, r) => return error("type error", format!("Cannot divide {} by {}", l, r)),
+        },
+        Rem => match (l_value, r_value) {
+            (Value::Int(_), Value::Int(0)) => return division_by_zero(),
+            (Value::Int(a), Value::Int(b)) => match a.checked_rem(b) {
+                Some(x) => Value::Int(x),
+                None => return exact(Rem, Value::Int(a), Value::Int(b)),
+            },
+            (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) % b),
+            (Value::Int(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Int(a), Rem, body))
+            }
+            (Value::Float(a), Value::Int(b)) => Value::Float(a % (b as f64)),
+            (Value::Float(a), Value::Float(b)) => Value::Float(a % b),
+            (Value::Float(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Float(a), Rem, body))
+            }
+            (Value::Function(f), Value::Int(a)) => {
+                f.map_body(|body| op_constant(body, Rem, Node::Int(a)))
+            }
+            (Value::Function(f), Value::Float(a)) => {
+                f.map_body(|body| op_constant(body, Rem, Node::Float(a)))
+            }
+            (l, r) => {
+                return error(
+                    "type error",
+                    format!("Cannot take remainder of {} and {}", l, r),
+                )
+            }
+        },
+        Pow => match (l_value, r_value) {
+            (Value::Int(a), Value::Int(b)) if b >= 0 => match a.checked_pow(b as u32) {
+                Some(x) => Value::Int(x),
+                None => return exact(Pow, Value::Int(a), Value::Int(b)),
+            },
+            (Value::Int(a), Value::Int(b)) => return exact(Pow, Value::Int(a), Value::Int(b)),
+            (Value::Int(a), Value::Float(b)) => powf(a as f64, b),
+            (Value::Int(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Int(a), Pow, body))
+            }
+            (Value::Float(a), Value::Int(b)) => Value::Float(a.powi(b)),
+            (Value::Float(a), Value::Float(b)) => powf(a, b),
+            (Value::Float(a), Value::Function(f)) => {
+                f.map_body(|body| constant_op(Node::Float(a), Pow, body))
+            }
+            (Value::Function(f), Value::Int(a)) => {
+                f.map_body(|body| op_constant(body, Pow, Node::Int(a)))
+            }
+            (Value::Function(f), Value::Float(a)) => {
+                f.map_body(|body| op_constant(body, Pow, Node::Float(a)))
+            }
+            (Value::Matrix(rows), Value::Int(n)) => {
+                let mut base = if n < 0 {
+                    match inverse(&rows)? {
+                        Value::Matrix(rows) => rows,
+                        _ => unreachable!(),
+                    }
+                } else {
+                    rows
+                };
+                // Square the base for each bit of the exponent, multiplying
+                // it in for the bits that are set
+                let mut result = identity(base.len());
+                let mut n = n.unsigned_abs();
+                while n > 0 {
+                    if n & 1 == 1 {
+                        result = multiply(&result, &base)?;
+                    }
+                    n >>= 1;
+                    if n > 0 {
+                        base = multiply(&base, &base)?;
+                    }
+                }
+                Value::Matrix(result)
+            }
+            (l, r) => {
+                return error(
+                    "type error",
+                    format!("Cannot raise {} to the power of {}", l, r),
+                )
+            }
+        },
+        EqEq => Value::Bool(equal(&l_value, &r_value)),
+        Neq => Value::Bool(!equal(&l_value, &r_value)),
+        Lt => Value::Bool(match (l_value, r_value) {
+            (Value::Int(a), Value::Int(b)) => a < b,
+            (Value::Int(a), Value::Float(b)) => (a as f64) < b,
+            (Value::Float(a), Value::Int(b)) => a < (b as f64),
+            (Value::Float(a), Value::Float(b)) => a < b,
+            (l, r) => return error("type error", format!("Cannot compare {} < {}", l, r)),
+        }),
+        Lte => Value::Bool(match (l_value, r_value) {
+            (Value::Int(a), Value::Int(b)) => a <= b,
+            (Value::Int(a), Value::Float(b)) => (a as f64) <= b,
+            (Value::Float(a), Value::Int(b)) => a <= (b as f64),
+            (Value::Float(a), Value::Float(b)) => a <= b,
+            (l, r) => return error("type error", format!("Cannot compare {} <= {}", l, r)),
+        }),
+        Gt => Value::Bool(match (l_value, r_value) {
+            (Value::Int(a), Value::Int(b)) => a > b,
+            (Value::Int(a), Value::Float(b)) => (a as f64) > b,
+            (Value::Float(a), Value::Int(b)) => a > (b as f64),
+            (Value::Float(a), Value::Float(b)) => a > b,
+            (l, r) => return error("type error", format!("Cannot compare {} > {}", l, r)),
+        }),
+        Gte => Value::Bool(match (l_value, r_value) {
+            (Value::Int(a), Value::Int(b)) => a >= b,
+            (Value::Int(a), Value::Float(b)) => (a as f64) >= b,
+            (Value::Float(a), Value::Int(b)) => a >= (b as f64),
+            (Value::Float(a), Value::Float(b)) => a >= b,
+            (l, r) => return error("type error", format!("Cannot compare {} >= {}", l, r)),
+        }),
+        And => Value::Bool(l_value.into() && r_value.into()),
+        Or => Value::Bool(l_value.into() || r_value.into()),
+        Approx | In | NotIn | Implies => unreachable!("handled before dispatching on types"),
+    })
+}
+
+/// Whether `l` and `r` are equal to within `tolerance`, relative to their
+/// size once they're larger than 1. Vectors and matrices are compared
+/// elementwise, and anything else must be exactly equal.
+pub fn approx(l: Value, r: Value, tolerance: f64) -> RuntimeResult {
+    Ok(Value::Bool(match (l, r) {
+        (Value::Vector(a), Value::Vector(b)) => {
+            a.len() == b.len() && all(zip(a, b, |a, b| approx(a, b, tolerance))?)
+        }
+        (Value::Matrix(a), Value::Matrix(b)) => {
+            a.len() == b.len()
+                && a.iter().zip(&b).all(|(a, b)| a.len() == b.len())
+                && all(zip(a, b, |a, b| {
+                    Ok(Value::Bool(all(zip(a, b, |a, b| approx(a, b, tolerance))?)))
+                })?)
+        }
+        (l, r) => match (complex(&l), complex(&r)) {
+            (Some(a), Some(b)) => (a - b).abs() <= tolerance * a.abs().max(b.abs()).max(1.0),
+            _ => l == r,
+        },
+    }))
+}
+
+/// Whether `l` and `r` are equal, for `==`. Numbers are compared by value
+/// whatever their types, as they are for `<`, and collections elementwise.
+fn equal(l: &Value, r: &Value) -> bool {
+    match (l, r) {
+        (Value::Vector(a), Value::Vector(b)) | (Value::List(a), Value::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b))
+        }
+        (Value::Matrix(a), Value::Matrix(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b)))
+        }
+        // Exact numbers have only one representation each
+        (l, r) if l.is_exact() && r.is_exact() => l == r,
+        (l, r) => match (complex(l), complex(r)) {
+            (Some(a), Some(b)) => a == b,
+            _ => l == r,
+        },
+    }
+}
+
+/// Whether every one of `values` is true.
+fn all(values: Vec<Value>) -> bool {
+    values.into_iter().all(bool::from)
+}
+
+/// Whether `collection` has an element equal to `x`, for `∈` and `∉`. A
+/// string contains the strings it has as substrings.
+fn contains(collection: Value, x: Value) -> Result<bool, SpectreError> {
+    let elements = match (collection, x.clone()) {
+        (Value::Str(text), Value::Str(x)) => return Ok(text.contains(&*x)),
+        (Value::Range(range), x) => return Ok(range.contains(&x)),
+        (Value::Matrix(rows), _) => rows.into_iter().flatten().collect(),
+        (Value::Vector(v) | Value::List(v), _) => v,
+        (collection, _) => {
+            return error(
+                "type error",
+                format!("Cannot look for elements in {}", collection),
+            )
+        }
+    };
+    for element in elements {
+        if bool::from(binary(BinaryOp::EqEq, element, x.clone())?) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The elements of a range or collection, in order, for `for` loops and
+/// big operators. Matrices are iterated by row.
+pub fn iterate(value: Value) -> Result<Box<dyn Iterator<Item = Value>>, SpectreError> {
+    Ok(match value {
+        Value::Range(range) => Box::new(Range::clone(&range).elements()),
+        Value::Vector(v) | Value::List(v) => Box::new(v.into_iter()),
+        Value::Matrix(rows) => Box::new(rows.into_iter().map(Value::Vector)),
+        Value::Str(text) => Box::new(
+            text.chars()
+                .map(|ch| Value::Str(ch.to_string().into()))
+                .collect::<Vec<_>>()
+                .into_iter(),
+        ),
+        value => return error("type error", format!("Cannot iterate over {}", value)),
+    })
+}
+
+/// The elements of `value`, as `iterate` gives them, for code that needs all
+/// of them, so that an endless range is an error rather than never finishing.
+pub fn iterate_all(value: Value) -> Result<Box<dyn Iterator<Item = Value>>, SpectreError> {
+    if let Value::Range(range) = &value {
+        if range.count().is_none() {
+            return error("type error", format!("{} is endless", range));
+        }
+    }
+    iterate(value)
+}
+
+/// Builds a range, for `a..b` and `a..=b`.
+pub fn range(start: Value, end: Value, step: Option<Value>, inclusive: bool) -> RuntimeResult {
+    Ok(Value::Range(Rc::new(Range::new(
+        start, end, step, inclusive,
+    )?)))
+}
+
+/// The element of a collection, range or string, or the row of a matrix, at
+/// `index`, counting from 0. Indexing with a range takes the elements at each
+/// of its indices, as a collection of the same kind.
+pub fn index(value: Value, index: Value) -> RuntimeResult {
+    if let Value::Range(indices) = index {
+        let elements = Range::clone(&indices)
+            .elements()
+            .map(|i| self::index(value.clone(), i));
+        let elements: Vec<Value> = elements.collect::<Result<_, _>>()?;
+        return Ok(match value {
+            Value::Vector(_) => Value::Vector(elements),
+            Value::Matrix(_) => Value::Matrix(
+                elements
+                    .into_iter()
+                    .map(|row| match row {
+                        Value::Vector(row) => row,
+                        _ => unreachable!("the elements of a matrix are its rows"),
+                    })
+                    .collect(),
+            ),
+            Value::Str(_) => interpolate(elements),
+            _ => Value::List(elements),
+        });
+    }
+    let Value::Int(i) = index else {
+        return error(
+            "type error",
+            format!("Indices must be integers, got {}", index),
+        );
+    };
+    if let Value::Range(range) = &value {
+        if let Some(element) = usize::try_from(i).ok().and_then(|i| range.get(i)) {
+            return Ok(element);
+        }
+        return error(
+            "index out of range",
+            format!("Index {} is out of range for {}", i, range),
+        );
+    }
+    let elements = match value {
+        Value::Vector(v) | Value::List(v) => v,
+        Value::Matrix(rows) => rows.into_iter().map(Value::Vector).collect(),
+        Value::Str(text) => text
+            .chars()
+            .map(|ch| Value::Str(ch.to_string().into()))
+            .collect(),
+        value => return error("type error", format!("Cannot index {}", value)),
+    };
+    let len = elements.len();
+    match usize::try_from(i)
+        .ok()
+        .and_then(|i| elements.into_iter().nth(i))
+    {
+        Some(element) => Ok(element),
+        None => error(
+            "index out of range",
+            format!("Index {} is out of range for length {}", i, len),
+        ),
+    }
+}
+
+/// Joins the pieces of an interpolated string, writing values as they're
+/// printed.
+pub fn interpolate(parts: Vec<Value>) -> Value {
+    Value::Str(
+        parts
+            .iter()
+            .map(|part| part.to_string())
+            .collect::<String>()
+            .into(),
+    )
+}
+
+/// Applies `op` to strings, which can be joined with `+` and compared in
+/// lexicographic order.
+fn string_binary(op: BinaryOp, l_value: Value, r_value: Value) -> RuntimeResult {
+    use BinaryOp::*;
+    Ok(match (op, l_value, r_value) {
+        (Add, Value::Str(a), Value::Str(b)) => Value::Str(format!("{}{}", a, b).into()),
+        (EqEq, a, b) => Value::Bool(a == b),
+        (Neq, a, b) => Value::Bool(a != b),
+        (Lt, Value::Str(a), Value::Str(b)) => Value::Bool(a < b),
+        (Lte, Value::Str(a), Value::Str(b)) => Value::Bool(a <= b),
+        (Gt, Value::Str(a), Value::Str(b)) => Value::Bool(a > b),
+        (Gte, Value::Str(a), Value::Str(b)) => Value::Bool(a >= b),
+        (And, a, b) => Value::Bool(a.into() && b.into()),
+        (Or, a, b) => Value::Bool(a.into() || b.into()),
+        (op, l, r) => {
+            return error(
+                "type error",
+                format!("Cannot apply {} to {} and {}", op, quoted(&l), quoted(&r)),
+            )
+        }
+    })
+}
+
+/// Applies `op` to lists, which can be joined with `+` and compared for
+/// equality.
+fn list_binary(op: BinaryOp, l_value: Value, r_value: Value) -> RuntimeResult {
+    use BinaryOp::*;
+    Ok(match (op, l_value, r_value) {
+        (Add, Value::List(mut a), Value::List(b)) => {
+            a.extend(b);
+            Value::List(a)
+        }
+        (EqEq, a, b) => Value::Bool(equal(&a, &b)),
+        (Neq, a, b) => Value::Bool(!equal(&a, &b)),
+        (And, a, b) => Value::Bool(a.into() && b.into()),
+        (Or, a, b) => Value::Bool(a.into() || b.into()),
+        (op, l, r) => {
+            return error(
+                "type error",
+                format!("Cannot apply {} to {} and {}", op, l, r),
+            )
+        }
+    })
+}
+
+/// Writes `value` for an error message, quoting it if it's a string so that
+/// it can't be mistaken for anything else.
+fn quoted(value: &Value) -> String {
+    match value {
+        Value::Str(text) => format!("{:?}", text),
+        value => value.to_string(),
+    }
+}
+
+fn dot(a: Vec<Value>, b: Vec<Value>) -> RuntimeResult {
+    zip(a, b, |a, b| binary(BinaryOp::Mul, a, b))?
+        .into_iter()
+        .try_fold(Value::Int(0), |sum, x| binary(BinaryOp::Add, sum, x))
+}
+
+/// Applies `op` to two polynomials, or a polynomial and a number. Dividing
+/// gives the quotient and `%` the remainder.
+fn polynomial_binary(op: BinaryOp, l_value: Value, r_value: Value) -> RuntimeResult {
+    use BinaryOp::*;
+    if let (Pow, Value::Polynomial(p)) = (op, &l_value) {
+        return match r_value {
+            Value::Int(n) if n >= 0 => Ok(Value::Polynomial(p.pow(n as u32)?)),
+            n => error(
+                "type error",
+                format!(
+                    "Polynomials can only be raised to natural powers, not {}",
+                    n
+                ),
+            ),
+        };
+    }
+    let (Some(a), Some(b)) = (polynomial(&l_value), polynomial(&r_value)) else {
+        return error(
+            "type error",
+            format!("Cannot apply {} to {} and {}", op, l_value, r_value),
+        );
+    };
+    Ok(match op {
+        Add => Value::Polynomial(a.add(&b)?),
+        Sub => Value::Polynomial(a.sub(&b)?),
+        Mul => Value::Polynomial(a.mul(&b)?),
+        Div => Value::Polynomial(a.div(&b)?),
+        Rem => Value::Polynomial(a.div_rem(&b)?.1),
+        EqEq => Value::Bool(a == b),
+        Neq => Value::Bool(a != b),
+        And => Value::Bool(l_value.into() && r_value.into()),
+        Or => Value::Bool(l_value.into() || r_value.into()),
+        _ => {
+            return error(
+                "type error",
+                format!("Cannot apply {} to {} and {}", op, l_value, r_value),
+            )
+        }
+    })
+}
+
+/// Widens a number to a constant polynomial.
+pub(crate) fn polynomial(value: &Value) -> Option<Polynomial> {
+    match value {
+        Value::Polynomial(p) => Some(p.clone()),
+        value if value.is_number() => Some(Polynomial::new(vec![value.clone()])),
+        _ => None,
+    }
+}
+
+/// Applies `op` to two integers if the result is an integer or a boolean,
+/// leaving overflow and division to the general case.
+fn int_binary(op: BinaryOp, a: i32, b: i32) -> Option<Value> {
+    use BinaryOp::*;
+    Some(match op {
+        Add => Value::Int(a.checked_add(b)?),
+        Sub => Value::Int(a.checked_sub(b)?),
+        Mul => Value::Int(a.checked_mul(b)?),
+        EqEq => Value::Bool(a == b),
+        Neq => Value::Bool(a != b),
+        Lt => Value::Bool(a < b),
+        Lte => Value::Bool(a <= b),
+        Gt => Value::Bool(a > b),
+        Gte => Value::Bool(a >= b),
+        _ => return None,
+    })
+}
+
+/// Applies `op` to two numbers, at least one of them complex.
+fn complex_binary(op: BinaryOp, a: Complex, b: Complex) -> RuntimeResult {
+    use BinaryOp::*;
+    Ok(match op {
+        Add => (a + b).into(),
+        Sub => (a - b).into(),
+        Mul | Dot | Cross => (a * b).into(),
+        Div => (a / b).into(),
+        Pow => a.pow(b).into(),
+        Rem => {
+            return error(
+                "type error",
+                format!("Cannot take remainder of {} and {}", a, b),
+            )
+        }
+        EqEq => Value::Bool(a == b),
+        Neq => Value::Bool(a != b),
+        // Complex numbers have no order
+        Lt | Lte | Gt | Gte => {
+            return error("type error", format!("Cannot compare {} {} {}", a, op, b))
+        }
+        And => Value::Bool(a != Complex::ZERO && b != Complex::ZERO),
+        Or => Value::Bool(a != Complex::ZERO || b != Complex::ZERO),
+        Approx | In | NotIn | Implies => unreachable!("handled before dispatching on types"),
+    })
+}
+
+/// Applies `op` to two integers or rationals, exactly unless it raises one
+/// to a fractional power.
+fn exact(op: BinaryOp, a: Value, b: Value) -> RuntimeResult {
+    let ((a, b), (c, d)) = (fraction(a), fraction(b));
+    use BinaryOp::*;
+    Ok(match op {
+        Add => rational(a * &d + c * &b, b * d)?,
+        Sub => rational(a * &d - c * &b, b * d)?,
+        Mul | Dot | Cross => rational(a * c, b * d)?,
+        Div | Rem if c.is_zero() => return division_by_zero(),
+        Div => rational(a * d, b * c)?,
+        Rem => rational((a * &d) % (c * &b), b * d)?,
+        Pow if d.is_one() => {
+            // A negative power is a positive power of the reciprocal
+            let (a, b) = if c.is_negative() { (b, a) } else { (a, b) };
+            let bits = a.bits().max(b.bits());
+            match c.abs().to_u64() {
+                // Powers of 0, 1 and -1 stay small however large the exponent
+                Some(c) if bits <= 1 || bits.saturating_mul(c) <= MAX_BITS => {
+                    rational(num_traits::Pow::pow(a, c), num_traits::Pow::pow(b, c))?
+                }
+                _ => return overflow(),
+            }
+        }
+        Pow => powf(ratio(&a, &b), ratio(&c, &d)),
+        // Denominators are positive, so cross-multiplying keeps the order
+        EqEq => Value::Bool(a * d == c * b),
+        Neq => Value::Bool(a * d != c * b),
+        Lt => Value::Bool(a * d < c * b),
+        Lte => Value::Bool(a * d <= c * b),
+        Gt => Value::Bool(a * d > c * b),
+        Gte => Value::Bool(a * d >= c * b),
+        And => Value::Bool(!a.is_zero() && !c.is_zero()),
+        Or => Value::Bool(!a.is_zero() || !c.is_zero()),
+        Approx | In | NotIn | Implies => unreachable!("handled before dispatching on types"),
+    })
+}
+
+/// Builds the rational `n/d` in lowest terms with a positive denominator,
+/// which is an integer if the denominator is 1.
+pub(crate) fn rational(n: BigInt, d: BigInt) -> RuntimeResult {
+    if d.is_zero() {
+        return division_by_zero();
+    }
+    let divisor = gcd(&n, &d);
+    let (n, d) = if d.is_negative() {
+        (-n / &divisor, -d / divisor)
+    } else {
+        (n / &divisor, d / divisor)
+    };
+    Ok(if d.is_one() {
+        Value::from(n)
+    } else {
+        Value::Rational(n, d)
+    })
+}
+
+/// The largest integer, in bits, that arithmetic will build, so that a typo
+/// like `9^9^9` fails instead of exhausting memory.
+const MAX_BITS: u64 = 1 << 24;
+
+fn factorial(n: i32) -> RuntimeResult {
+    if let Some(x) = (1..=n).try_fold(1i32, |product, k| product.checked_mul(k)) {
+        return Ok(Value::Int(x));
+    }
+    // log₂(n!) < n log₂(n)
+    if n as u64 * (n.ilog2() as u64 + 1) > MAX_BITS {
+        return overflow();
+    }
+    Ok(Value::from((1..=n).map(BigInt::from).product::<BigInt>()))
+}
+
+/// Splits an integer or rational into its numerator and denominator.
+fn fraction(value: Value) -> (BigInt, BigInt) {
+    match value {
+        Value::Int(x) => (x.into(), BigInt::one()),
+        Value::BigInt(x) => (x, BigInt::one()),
+        Value::Rational(n, d) => (n, d),
+        _ => unreachable!("only exact numbers have fractions"),
+    }
+}
+
+/// Rounds `n/d` to the nearest integer, with halves away from zero as for
+/// floats.
+pub(crate) fn round(n: &BigInt, d: &BigInt) -> Value {
+    let rounded = (n.abs() * 2u8 + d).div_floor(&(d * 2u8));
+    Value::from(if n.is_negative() { -rounded } else { rounded })
+}
+
+/// Widens any number to a complex one.
+pub(crate) fn complex(value: &Value) -> Option<Complex> {
+    match value {
+        Value::Complex(r, i) => Some(Complex::new(*r, *i)),
+        value if value.is_number() => Some(real(value).into()),
+        _ => None,
+    }
+}
+
+/// The principal square root of a real number, which is imaginary for a
+/// negative one.
+pub(crate) fn sqrt(x: f64) -> Value {
+    if x < 0.0 {
+        Complex::from(x).sqrt().into()
+    } else {
+        Value::Float(x.sqrt())
+    }
+}
+
+/// The principal natural logarithm of a real number, which is complex for a
+/// negative one.
+pub(crate) fn ln(x: f64) -> Value {
+    if x < 0.0 {
+        Complex::from(x).ln().into()
+    } else {
+        Value::Float(x.ln())
+    }
+}
+
+/// `a` to the power of `b`, which is the principal complex value when a
+/// negative `a` has a fractional power.
+fn powf(a: f64, b: f64) -> Value {
+    if a < 0.0 && b.fract() != 0.0 {
+        Complex::from(a).pow(b.into()).into()
+    } else {
+        Value::Float(a.powf(b))
+    }
+}
+
+/// The nearest float to a real number, or `NaN` for anything else.
+pub(crate) fn real(value: &Value) -> f64 {
+    match value {
+        Value::Int(x) => *x as f64,
+        Value::BigInt(x) => float(x),
+        Value::Rational(n, d) => ratio(n, d),
+        Value::Float(x) => *x,
+        _ => f64::NAN,
+    }
+}
+
+/// The nearest float to `n/d`.
+pub(crate) fn ratio(n: &BigInt, d: &BigInt) -> f64 {
+    // Both are scaled down first so that huge fractions don't become ∞/∞
+    let shift = n
+        .bits()
+        .max(d.bits())
+        .saturating_sub(f64::MAX_EXP as u64 - 1);
+    float(&(n >> shift)) / float(&(d >> shift))
+}
+
+/// The nearest float to `x`, which is infinite if `x` is too large.
+pub(crate) fn float(x: &BigInt) -> f64 {
+    x.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Applies a fallible `f` to each item, stopping at the first error.
+pub fn map<T, U, E>(items: Vec<T>, f: impl FnMut(T) -> Result<U, E>) -> Result<Vec<U>, E> {
+    items.into_iter().map(f).collect()
+}
+
+/// Applies a fallible `f` to pairs of items, stopping at the first error.
+pub fn zip<T, U>(
+    a: Vec<T>,
+    b: Vec<T>,
+    mut f: impl FnMut(T, T) -> Result<U, SpectreError>,
+) -> Result<Vec<U>, SpectreError> {
+    a.into_iter().zip(b).map(|(a, b)| f(a, b)).collect()
+}
+
+/// Builds `constant op body`, giving the constant the body's range.
+fn constant_op(constant: Node, op: BinaryOp, body: Spanned<Node>) -> Spanned<Node> {
+    let range = body.range.clone();
+    let constant = Box::new(Spanned::new(constant, range.clone()));
+    Spanned::new(Node::Binary(constant, op, Box::new(body)), range)
+}
+
+/// Builds `body op constant`, giving the constant the body's range.
+fn op_constant(body: Spanned<Node>, op: BinaryOp, constant: Node) -> Spanned<Node> {
+    let range = body.range.clone();
+    let constant = Box::new(Spanned::new(constant, range.clone()));
+    Spanned::new(Node::Binary(Box::new(body), op, constant), range)
+}
+
+fn overflow<T>() -> Result<T, SpectreError> {
+    error(
+        "overflow",
+        "The result is too large to compute exactly".to_string(),
+    )
+}
+
+fn division_by_zero<T>() -> Result<T, SpectreError> {
+    error(
+        "division by zero",
+        "Cannot divide an integer by zero".to_string(),
+    )
+}
@@ -0,0 +1,444 @@
+use std::{f64::consts::TAU, fmt};
+
+use common::SpectreError;
+use parser::{BinaryOp, UnaryOp};
+
+use crate::{
+    error,
+    ops::{approx, binary, complex, unary},
+    Complex, RuntimeResult, Value, DEFAULT_TOLERANCE,
+};
+
+/// A polynomial in `x` with numeric coefficients. Coefficients are combined
+/// with the same arithmetic as other numbers, so exact ones stay exact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    /// The coefficients from the constant term up, with no trailing zeros, so
+    /// that the zero polynomial has none.
+    coefficients: Vec<Value>,
+}
+
+/// The largest degree arithmetic will build, so that a typo like `x^10^9`
+/// fails instead of exhausting memory.
+const MAX_DEGREE: usize = 1 << 16;
+
+/// How many rounds of Durand–Kerner to try before giving up.
+const MAX_ITERATIONS: usize = 1000;
+
+impl Polynomial {
+    /// Builds a polynomial from its coefficients, constant term first.
+    pub fn new(mut coefficients: Vec<Value>) -> Self {
+        while coefficients.last().is_some_and(is_zero) {
+            coefficients.pop();
+        }
+        Self { coefficients }
+    }
+
+    /// The polynomial `x`.
+    pub fn x() -> Self {
+        Self::new(vec![Value::Int(0), Value::Int(1)])
+    }
+
+    /// The coefficients from the constant term up.
+    pub fn coefficients(&self) -> &[Value] {
+        &self.coefficients
+    }
+
+    /// The degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    fn coefficient(&self, k: usize) -> Value {
+        self.coefficients.get(k).cloned().unwrap_or(Value::Int(0))
+    }
+
+    fn leading(&self) -> Value {
+        self.coefficients.last().cloned().unwrap_or(Value::Int(0))
+    }
+
+    pub fn add(&self, other: &Self) -> Result<Self, SpectreError> {
+        self.combine(other, BinaryOp::Add)
+    }
+
+    pub fn sub(&self, other: &Self) -> Result<Self, SpectreError> {
+        self.combine(other, BinaryOp::Sub)
+    }
+
+    /// Adds or subtracts term by term.
+    fn combine(&self, other: &Self, op: BinaryOp) -> Result<Self, SpectreError> {
+        let len = self.coefficients.len().max(other.coefficients.len());
+        let coefficients = (0..len)
+            .map(|k| binary(op, self.coefficient(k), other.coefficient(k)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(coefficients))
+    }
+
+    pub fn neg(&self) -> Result<Self, SpectreError> {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .map(|c| unary(UnaryOp::Neg, c.clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(coefficients))
+    }
+
+    pub fn mul(&self, other: &Self) -> Result<Self, SpectreError> {
+        let (Some(m), Some(n)) = (self.degree(), other.degree()) else {
+            return Ok(Self::new(vec![]));
+        };
+        if m + n > MAX_DEGREE {
+            return overflow();
+        }
+        let mut coefficients = vec![Value::Int(0); m + n + 1];
+        for (i, a) in self.coefficients.iter().enumerate() {
+            for (j, b) in other.coefficients.iter().enumerate() {
+                let product = binary(BinaryOp::Mul, a.clone(), b.clone())?;
+                coefficients[i + j] = binary(BinaryOp::Add, coefficients[i + j].clone(), product)?;
+            }
+        }
+        Ok(Self::new(coefficients))
+    }
+
+    /// Raises to a power by repeated squaring.
+    pub fn pow(&self, mut n: u32) -> Result<Self, SpectreError> {
+        if self.degree().unwrap_or(0) * n as usize > MAX_DEGREE {
+            return overflow();
+        }
+        let mut base = self.clone();
+        let mut result = Self::new(vec![Value::Int(1)]);
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Long division, giving the quotient and a remainder of lower degree
+    /// than `divisor`.
+    pub fn div_rem(&self, divisor: &Self) -> Result<(Self, Self), SpectreError> {
+        let Some(n) = divisor.degree() else {
+            return error(
+                "division by zero",
+                "Cannot divide a polynomial by zero".to_string(),
+            );
+        };
+        let leading = divisor.leading();
+        let mut remainder = self.coefficients.clone();
+        let mut quotient = vec![Value::Int(0); remainder.len().saturating_sub(n)];
+        while remainder.len() > n {
+            let shift = remainder.len() - 1 - n;
+            let factor = binary(BinaryOp::Div, remainder[shift + n].clone(), leading.clone())?;
+            for (k, c) in divisor.coefficients.iter().enumerate().take(n) {
+                let product = binary(BinaryOp::Mul, factor.clone(), c.clone())?;
+                remainder[shift + k] =
+                    binary(BinaryOp::Sub, remainder[shift + k].clone(), product)?;
+            }
+            // The leading term cancels by construction, which rounding
+            // wouldn't guarantee for floats
+            remainder.pop();
+            quotient[shift] = factor;
+        }
+        Ok((Self::new(quotient), Self::new(remainder)))
+    }
+
+    /// Divides by `divisor`, which must go into this exactly. Float
+    /// coefficients only need to leave a remainder that rounds to zero.
+    pub fn div(&self, divisor: &Self) -> Result<Self, SpectreError> {
+        let (quotient, remainder) = self.div_rem(divisor)?;
+        for c in &remainder.coefficients {
+            if !bool::from(approx(c.clone(), Value::Int(0), DEFAULT_TOLERANCE)?) {
+                return error(
+                    "not divisible",
+                    format!(
+                        "{} leaves a remainder of {} when divided by {}; divrem gives both",
+                        self, remainder, divisor
+                    ),
+                );
+            }
+        }
+        Ok(quotient)
+    }
+
+    /// The monic greatest common divisor, by Euclid's algorithm. With float
+    /// coefficients rounding usually leaves a remainder, so the result is
+    /// only meaningful for exact ones.
+    pub fn gcd(&self, other: &Self) -> Result<Self, SpectreError> {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while b.degree().is_some() {
+            let (_, remainder) = a.div_rem(&b)?;
+            a = b;
+            b = remainder;
+        }
+        a.monic()
+    }
+
+    /// Divides through by the leading coefficient.
+    fn monic(&self) -> Result<Self, SpectreError> {
+        let leading = self.leading();
+        if is_zero(&leading) {
+            return Ok(self.clone());
+        }
+        let coefficients = self
+            .coefficients
+            .iter()
+            .map(|c| binary(BinaryOp::Div, c.clone(), leading.clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(coefficients))
+    }
+
+    /// Evaluates at `x` by Horner's method. `x` can be anything that numbers
+    /// can be multiplied by, including another polynomial.
+    pub fn evaluate(&self, x: &Value) -> RuntimeResult {
+        self.coefficients
+            .iter()
+            .rev()
+            .try_fold(Value::Int(0), |result, c| {
+                binary(
+                    BinaryOp::Add,
+                    binary(BinaryOp::Mul, result, x.clone())?,
+                    c.clone(),
+                )
+            })
+    }
+
+    /// Evaluates at the one argument of a call like `p(2)`.
+    pub(crate) fn call(&self, args: &[Value]) -> RuntimeResult {
+        match args {
+            [x] => self.evaluate(x),
+            args => error(
+                "invalid arguments",
+                format!("A polynomial expects 1 argument, got {}", args.len()),
+            ),
+        }
+    }
+
+    pub fn derivative(&self) -> Result<Self, SpectreError> {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(k, c)| binary(BinaryOp::Mul, c.clone(), Value::from(k as i32)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(coefficients))
+    }
+
+    /// The antiderivative with a constant term of 0.
+    pub fn integral(&self) -> Result<Self, SpectreError> {
+        let mut coefficients = vec![Value::Int(0)];
+        for (k, c) in self.coefficients.iter().enumerate() {
+            coefficients.push(binary(BinaryOp::Div, c.clone(), Value::from(k as i32 + 1))?);
+        }
+        Ok(Self::new(coefficients))
+    }
+
+    /// Finds every complex root, repeated by multiplicity, in order of real
+    /// then imaginary part.
+    ///
+    /// Exact polynomials are first split into square-free factors, since
+    /// Durand–Kerner only converges slowly to repeated roots.
+    pub fn roots(&self, tolerance: f64) -> Result<Vec<Complex>, SpectreError> {
+        if self.degree().is_none() {
+            return error(
+                "invalid arguments",
+                "Every number is a root of the zero polynomial".to_string(),
+            );
+        }
+        let factors = if self.coefficients.iter().all(Value::is_exact) {
+            self.square_free()?
+        } else {
+            vec![(self.clone(), 1)]
+        };
+
+        let mut roots = vec![];
+        for (factor, multiplicity) in factors {
+            for root in factor.durand_kerner(tolerance)? {
+                roots.extend(std::iter::repeat_n(root, multiplicity));
+            }
+        }
+        roots.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+        Ok(roots)
+    }
+
+    /// Splits into factors with no repeated roots, paired with how many
+    /// times each divides the polynomial, by Yun's algorithm.
+    fn square_free(&self) -> Result<Vec<(Self, usize)>, SpectreError> {
+        let mut factors = vec![];
+        let mut repeated = self.gcd(&self.derivative()?)?;
+        let mut distinct = self.div_rem(&repeated)?.0;
+        let mut multiplicity = 1;
+        while repeated.degree().is_some_and(|n| n > 0) {
+            let common = distinct.gcd(&repeated)?;
+            factors.push((distinct.div_rem(&common)?.0, multiplicity));
+            repeated = repeated.div_rem(&common)?.0;
+            distinct = common;
+            multiplicity += 1;
+        }
+        factors.push((distinct, multiplicity));
+        Ok(factors)
+    }
+
+    /// Finds the roots all at once by the Durand–Kerner method.
+    fn durand_kerner(&self, tolerance: f64) -> Result<Vec<Complex>, SpectreError> {
+        let coefficients: Vec<Complex> = self
+            .coefficients
+            .iter()
+            .map(|c| complex(c).unwrap_or_else(|| unreachable!("coefficients are numbers")))
+            .collect();
+        let Some(&leading) = coefficients.last() else {
+            return Ok(vec![]);
+        };
+        let monic: Vec<Complex> = coefficients.iter().map(|&c| c / leading).collect();
+        let n = monic.len() - 1;
+        let evaluate = |z: Complex| monic.iter().rev().fold(Complex::ZERO, |p, &c| p * z + c);
+        // How large rounding alone can make p(z), which limits how closely
+        // repeated roots of float polynomials can be found
+        let rounding = |z: Complex| {
+            let r = z.abs();
+            let size = monic.iter().rev().fold(0.0, |p, c| p * r + c.abs());
+            2.0 * n as f64 * f64::EPSILON * size
+        };
+
+        // Durand–Kerner loses the last few bits to the other roots' errors,
+        // which a Newton step recovers
+        let derivative: Vec<Complex> = (1..=n)
+            .map(|k| monic[k] * Complex::new(k as f64, 0.0))
+            .collect();
+        let polish = |z: Complex| {
+            let slope = derivative
+                .iter()
+                .rev()
+                .fold(Complex::ZERO, |p, &c| p * z + c);
+            let next = z - evaluate(z) / slope;
+            if next.abs().is_finite() && evaluate(next).abs() < evaluate(z).abs() {
+                next
+            } else {
+                z
+            }
+        };
+
+        // Start spread around a circle that contains every root, off the
+        // real axis so that conjugate pairs can separate
+        let radius = 1.0 + monic[..n].iter().map(|c| c.abs()).fold(0.0, f64::max);
+        let mut roots: Vec<Complex> = (0..n)
+            .map(|k| Complex::from_polar(radius, TAU * k as f64 / n as f64 + 0.4))
+            .collect();
+
+        for _ in 0..MAX_ITERATIONS {
+            let mut converged = true;
+            for i in 0..n {
+                let z = roots[i];
+                let denominator = (0..n)
+                    .filter(|&j| j != i)
+                    .fold(Complex::ONE, |product, j| product * (z - roots[j]));
+                let value = evaluate(z);
+                if value.abs() <= rounding(z) {
+                    continue;
+                }
+                let step = value / denominator;
+                roots[i] = z - step;
+                converged &= step.abs() <= tolerance * z.abs().max(1.0);
+            }
+            if converged {
+                let real = coefficients.iter().all(|c| c.im == 0.0);
+                return Ok(roots
+                    .into_iter()
+                    .map(|z| clean(polish(z), real, tolerance))
+                    .collect());
+            }
+        }
+        error(
+            "not convergent",
+            format!(
+                "The roots of {} didn't converge to within {}",
+                self, tolerance
+            ),
+        )
+    }
+}
+
+/// Rounds away the tiny imaginary parts that real roots pick up, and tiny
+/// real parts of imaginary ones.
+fn clean(z: Complex, real: bool, tolerance: f64) -> Complex {
+    let scale = tolerance * z.abs().max(1.0);
+    let re = if z.re.abs() <= scale { 0.0 } else { z.re };
+    let im = if real && z.im.abs() <= scale {
+        0.0
+    } else {
+        z.im
+    };
+    Complex::new(re, im)
+}
+
+fn is_zero(value: &Value) -> bool {
+    match value {
+        Value::Int(x) => *x == 0,
+        Value::Float(x) => *x == 0.0,
+        Value::Complex(r, i) => *r == 0.0 && *i == 0.0,
+        // Big integers and rationals are never zero
+        _ => false,
+    }
+}
+
+fn overflow<T>() -> Result<T, SpectreError> {
+    error(
+        "overflow",
+        "The polynomial's degree is too large".to_string(),
+    )
+}
+
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// Writes the terms from the highest degree down, as in `x² - 2`.
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.coefficients.is_empty() {
+            return write!(f, "0");
+        }
+        let mut first = true;
+        for (k, c) in self.coefficients.iter().enumerate().rev() {
+            if is_zero(c) {
+                continue;
+            }
+            let negative = c.is_negative();
+            let c = if negative {
+                unary(UnaryOp::Neg, c.clone()).map_err(|_| fmt::Error)?
+            } else {
+                c.clone()
+            };
+            match (first, negative) {
+                (true, true) => write!(f, "-")?,
+                (true, false) => {}
+                (false, true) => write!(f, " - ")?,
+                (false, false) => write!(f, " + ")?,
+            }
+            first = false;
+
+            let power = match k {
+                0 => String::new(),
+                1 => "x".to_string(),
+                k => format!(
+                    "x{}",
+                    k.to_string()
+                        .chars()
+                        .map(|digit| SUPERSCRIPT_DIGITS[digit as usize - '0' as usize])
+                        .collect::<String>()
+                ),
+            };
+            match c {
+                c if k == 0 => write!(f, "{}", c)?,
+                Value::Int(1) => write!(f, "{}", power)?,
+                Value::Float(1.0) => write!(f, "{}", power)?,
+                c @ (Value::Rational(..) | Value::Complex(..)) => write!(f, "({}){}", c, power)?,
+                c => write!(f, "{}{}", c, power)?,
+            }
+        }
+        Ok(())
+    }
+}
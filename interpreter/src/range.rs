@@ -0,0 +1,166 @@
+use std::fmt;
+
+use common::SpectreError;
+use num_bigint::BigInt;
+use parser::BinaryOp;
+
+use crate::{
+    error,
+    ops::{binary, real},
+    Value,
+};
+
+/// Evenly spaced real numbers, like `1..10` or `0..=1 step 0.1`. Elements are
+/// only worked out as they're needed, so ranges can be endless, as `1..∞` is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    start: Value,
+    end: Value,
+    step: Value,
+    inclusive: bool,
+}
+
+impl Range {
+    /// Builds the range from `start` to `end`, stepping by 1 unless given a
+    /// step.
+    pub fn new(
+        start: Value,
+        end: Value,
+        step: Option<Value>,
+        inclusive: bool,
+    ) -> Result<Self, SpectreError> {
+        for bound in [&start, &end].into_iter().chain(&step) {
+            if !is_real(bound) {
+                return error(
+                    "type error",
+                    format!("Ranges must be of real numbers, got {}", bound),
+                );
+            }
+        }
+        let step = step.unwrap_or(Value::Int(1));
+        if real(&step) == 0.0 {
+            return error(
+                "invalid range",
+                "The step of a range can't be 0".to_string(),
+            );
+        }
+        Ok(Self {
+            start,
+            end,
+            step,
+            inclusive,
+        })
+    }
+
+    /// The element `i` steps from the start, if the range goes that far. It's
+    /// worked out from the start rather than the previous element so that
+    /// float steps don't accumulate rounding errors.
+    pub fn get(&self, i: usize) -> Option<Value> {
+        // Ranges of integers, as most loops go over, are worked out without
+        // big integers unless an element doesn't fit in an i32
+        if let (Value::Int(start), Value::Int(step)) = (&self.start, &self.step) {
+            let x = i64::try_from(i)
+                .ok()
+                .and_then(|i| i.checked_mul(i64::from(*step)))
+                .and_then(|offset| offset.checked_add(i64::from(*start)))
+                .and_then(|x| i32::try_from(x).ok());
+            if let Some(x) = x {
+                let x = Value::Int(x);
+                return self.before_end(&x).then_some(x);
+            }
+        }
+        let offset = binary(
+            BinaryOp::Mul,
+            Value::from(BigInt::from(i)),
+            self.step.clone(),
+        )
+        .ok()?;
+        let x = binary(BinaryOp::Add, self.start.clone(), offset).ok()?;
+        self.before_end(&x).then_some(x)
+    }
+
+    /// The number of elements, or `None` if the range is endless.
+    pub fn count(&self) -> Option<usize> {
+        let steps = (real(&self.end) - real(&self.start)) / real(&self.step);
+        if !steps.is_finite() {
+            return None;
+        }
+        // Rounding can leave the estimate off by one either way, so settle it
+        // against the elements themselves
+        let mut len = steps.max(0.0) as usize;
+        while len > 0 && self.get(len - 1).is_none() {
+            len -= 1;
+        }
+        while len < usize::MAX && self.get(len).is_some() {
+            len += 1;
+        }
+        Some(len)
+    }
+
+    /// The sum of the elements, worked out directly for ranges of integers so
+    /// that it doesn't take a step per element.
+    pub fn sum(&self) -> Option<Value> {
+        let (Value::Int(start), Value::Int(step)) = (&self.start, &self.step) else {
+            return None;
+        };
+        let n = BigInt::from(self.count()?);
+        // n terms going up evenly from start to start + (n - 1) step
+        let sum = &n * start + BigInt::from(*step) * &n * (&n - 1) / 2;
+        Some(Value::from(sum))
+    }
+
+    /// The elements in order.
+    pub fn elements(self) -> impl Iterator<Item = Value> {
+        (0..).map_while(move |i| self.get(i))
+    }
+
+    /// Whether `x` is one of the elements, worked out without going through
+    /// them so that it finishes for endless ranges.
+    pub fn contains(&self, x: &Value) -> bool {
+        if !is_real(x) {
+            return false;
+        }
+        let steps = (real(x) - real(&self.start)) / real(&self.step);
+        if !(steps >= 0.0 && steps.is_finite()) {
+            return false;
+        }
+        self.get(steps.round() as usize)
+            .and_then(|element| binary(BinaryOp::EqEq, element, x.clone()).ok())
+            .is_some_and(bool::from)
+    }
+
+    /// Whether `x` hasn't gone past the end, in the direction of the step.
+    fn before_end(&self, x: &Value) -> bool {
+        let ascending = real(&self.step) > 0.0;
+        if let (Value::Int(x), Value::Int(end)) = (x, &self.end) {
+            return match (ascending, self.inclusive) {
+                (true, false) => x < end,
+                (true, true) => x <= end,
+                (false, false) => x > end,
+                (false, true) => x >= end,
+            };
+        }
+        let op = match (ascending, self.inclusive) {
+            (true, false) => BinaryOp::Lt,
+            (true, true) => BinaryOp::Lte,
+            (false, false) => BinaryOp::Gt,
+            (false, true) => BinaryOp::Gte,
+        };
+        binary(op, x.clone(), self.end.clone()).is_ok_and(bool::from)
+    }
+}
+
+fn is_real(value: &Value) -> bool {
+    value.is_number() && !matches!(value, Value::Complex(..))
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dots = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{}{}", self.start, dots, self.end)?;
+        if self.step != Value::Int(1) {
+            write!(f, " step {}", self.step)?;
+        }
+        Ok(())
+    }
+}
@@ -1,422 +1,379 @@
-use std::rc::Rc;
+use std::{cell::RefCell, rc::Rc};
 
-use parser::{BinaryOp, Node, UnaryOp};
+use common::SpectreError;
+use parser::{BinaryOp, Node, Spanned, UnaryOp};
 
-use crate::{Scope, Value};
+use crate::{
+    builtins::calculus::{integrate, real_number},
+    ops::{approx, binary, index, interpolate, iterate, iterate_all, map, range, unary},
+    Function, Scope, Value,
+};
+
+pub type RuntimeResult = Result<Value, SpectreError>;
+
+/// Why evaluation stopped before finishing a node: an error, or a `break`,
+/// `continue` or `return` making its way out to the loop or function it
+/// leaves.
+pub(crate) enum Unwind {
+    Error(SpectreError),
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// The result of evaluating a node, which may unwind out of it.
+pub(crate) type Flow = Result<Value, Unwind>;
+
+impl From<SpectreError> for Unwind {
+    fn from(e: SpectreError) -> Self {
+        Unwind::Error(e)
+    }
+}
+
+impl Unwind {
+    /// The error unwinding, or an error for a `break`, `continue` or `return`
+    /// that reached somewhere it can't leave. The parser only allows them
+    /// inside what they leave, so that shouldn't happen.
+    pub(crate) fn error(self) -> SpectreError {
+        let keyword = match self {
+            Unwind::Error(e) => return e,
+            Unwind::Break => "break",
+            Unwind::Continue => "continue",
+            Unwind::Return(_) => "return",
+        };
+        SpectreError {
+            msg: "invalid control flow".to_string(),
+            reason: format!("'{}' has nothing here to leave", keyword),
+            range: Default::default(),
+        }
+    }
+}
+
+/// Takes the value a function returns from what its body evaluated to.
+pub(crate) fn returned(flow: Flow) -> RuntimeResult {
+    match flow {
+        Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+        Err(unwind) => Err(unwind.error()),
+    }
+}
+
+/// Handles what an iteration of a loop's body evaluated to, keeping its value
+/// if it finished, and returns whether to keep looping.
+pub(crate) fn keep_looping(flow: Flow, rtn_value: &mut Value) -> Result<bool, Unwind> {
+    match flow {
+        Ok(value) => *rtn_value = value,
+        Err(Unwind::Break) => return Ok(false),
+        Err(Unwind::Continue) => {}
+        Err(unwind) => return Err(unwind),
+    }
+    Ok(true)
+}
+
+/// Builds a runtime error. The range is left empty since values don't know
+/// where in the source they came from; [`Interpreter::visit`] fills it in with
+/// the range of the node being evaluated.
+pub(crate) fn error<T>(msg: &str, reason: String) -> Result<T, SpectreError> {
+    Err(SpectreError {
+        msg: msg.to_string(),
+        reason,
+        range: Default::default(),
+    })
+}
+
+/// The tolerance interpreters start with, and that `≈` uses outside of one.
+pub const DEFAULT_TOLERANCE: f64 = 1e-10;
 
 pub struct Interpreter {
-    pub scope: Scope,
+    pub scope: Rc<RefCell<Scope>>,
+    /// How close numeric methods, such as integration, must get to the exact
+    /// answer, and how close values must be for `≈`.
+    pub tolerance: f64,
 }
 
 impl Default for Interpreter {
     fn default() -> Self {
         let mut interpreter = Self {
-            scope: Scope::default(),
+            scope: Rc::new(RefCell::new(Scope::default())),
+            tolerance: DEFAULT_TOLERANCE,
         };
         interpreter.math();
+        interpreter.linalg();
+        interpreter.algebra();
+        interpreter.calculus();
+        interpreter.solver();
+        interpreter.strings();
+        interpreter.lists();
         interpreter.io();
         interpreter
     }
 }
 
 impl Interpreter {
-    pub fn run(&mut self, ast: Node) -> Value {
-        self.visit(ast)
+    /// Runs `ast` by walking the tree directly. This is much slower than
+    /// [`Interpreter::run`] but simple enough to check it against.
+    pub fn walk(&mut self, ast: Spanned<Node>) -> RuntimeResult {
+        self.visit(ast).map_err(Unwind::error)
     }
 
     pub fn add_var(&mut self, name: &str, value: Value) {
-        self.scope.set(name.into(), value);
+        self.scope.borrow_mut().define(name.into(), value);
     }
 
-    fn visit(&mut self, node: Node) -> Value {
-        match node {
+    /// Runs `f` inside `scope`, restoring the current scope afterwards.
+    pub(crate) fn with_scope<T>(&mut self, scope: Scope, f: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.scope, Rc::new(RefCell::new(scope)));
+        let result = f(self);
+        self.scope = outer;
+        result
+    }
+
+    /// Folds `body` over `index = start..=end` with `op`, as in `∑` and `∏`.
+    /// Folds `body` over `index` taking each of `elements` with `op`.
+    fn fold(
+        &mut self,
+        op: BinaryOp,
+        index: Rc<str>,
+        elements: impl Iterator<Item = Value>,
+        body: Spanned<Node>,
+    ) -> Flow {
+        let identity = match op {
+            BinaryOp::Mul => Value::Int(1),
+            _ => Value::Int(0),
+        };
+
+        let scope = Scope::new(Rc::clone(&self.scope));
+        self.with_scope(scope, |interpreter| {
+            // Start from the first term rather than the identity so sums of
+            // vectors and matrices work
+            let mut result: Option<Value> = None;
+            for element in elements {
+                interpreter
+                    .scope
+                    .borrow_mut()
+                    .define(Rc::clone(&index), element);
+                let term = interpreter.visit(body.clone())?;
+                result = Some(match result {
+                    Some(result) => binary(op, result, term)?,
+                    None => term,
+                });
+            }
+            Ok(result.unwrap_or(identity))
+        })
+    }
+
+    /// Evaluates the function of a call. A subscript on the name of a
+    /// function that isn't a variable itself, as in `log₂(8)`, is also
+    /// returned so it can be passed as the last argument.
+    fn callee(&mut self, node: Spanned<Node>) -> Result<(Value, Option<Value>), Unwind> {
+        if let Node::Subscript(name, base, subscript) = &node.node {
+            if !self.scope.borrow().contains(name) {
+                let function = self.scope.borrow().get(base)?;
+                if let Value::Function(_) | Value::NativeFunction(_) = function {
+                    let subscript = self.visit((**subscript).clone())?;
+                    return Ok((function, Some(subscript)));
+                }
+            }
+        }
+        Ok((self.visit(node)?, None))
+    }
+
+    fn visit(&mut self, node: Spanned<Node>) -> Flow {
+        let range = node.range;
+        self.evaluate(node.node).map_err(|unwind| match unwind {
+            // The innermost node gets the first chance to claim an error
+            Unwind::Error(mut e) if e.range.is_empty() => {
+                e.range = range;
+                Unwind::Error(e)
+            }
+            unwind => unwind,
+        })
+    }
+
+    fn evaluate(&mut self, node: Node) -> Flow {
+        Ok(match node {
             Node::Int(x) => Value::Int(x),
+            Node::BigInt(x) => Value::BigInt(x),
             Node::Float(x) => Value::Float(x),
-            Node::Identifier(name) => self.scope.get(&name),
+            Node::Str(text) => Value::Str(text),
+            Node::Interpolation(parts) => interpolate(map(parts, |part| self.visit(part))?),
+            Node::Identifier(name) => self.scope.borrow().get(&name)?,
+            Node::Subscript(name, base, subscript) => {
+                if self.scope.borrow().contains(&name) {
+                    self.scope.borrow().get(&name)?
+                } else {
+                    let value = self.scope.borrow().get(&base)?;
+                    index(value, self.visit(*subscript)?)?
+                }
+            }
+            Node::Index(node, subscript) => {
+                let value = self.visit(*node)?;
+                index(value, self.visit(*subscript)?)?
+            }
+            Node::Vector(nodes) => Value::Vector(map(nodes, |node| self.visit(node))?),
+            Node::List(nodes) => Value::List(map(nodes, |node| self.visit(node))?),
+            Node::Matrix(rows) => {
+                let rows = map(rows, |row| map(row, |node| self.visit(node)))?;
+                if rows.iter().any(|row| row.len() != rows[0].len()) {
+                    return error(
+                        "type error",
+                        "All rows of a matrix must have the same length".to_string(),
+                    )
+                    .map_err(Unwind::from);
+                }
+                Value::Matrix(rows)
+            }
             Node::Assignment(name, node) => {
-                let value = self.visit(*node);
-                self.scope.set(name, value.clone());
+                let value = self.visit(*node)?;
+                self.scope.borrow_mut().set(name, value.clone());
                 value
             }
             Node::Unary(op, node) => {
-                let value = self.visit(*node);
-
-                use UnaryOp::*;
+                let value = self.visit(*node)?;
                 match op {
-                    Pos => value,
-                    Neg => match value {
-                        Value::Int(x) => Value::Int(-x),
-                        Value::Float(x) => Value::Float(-x),
-                        Value::Complex(r, i) => Value::Complex(-r, -i),
-                        _ => unimplemented!(),
-                    },
-                    Abs => match value {
-                        Value::Int(x) => Value::Int(x.abs()),
-                        Value::Float(x) => Value::Float(x.abs()),
-                        Value::Complex(r, i) => Value::Float(r.hypot(i)),
-                        _ => unimplemented!(),
-                    },
-                    Floor => match value {
-                        Value::Int(x) => Value::Int(x),
-                        Value::Float(x) => Value::Float(x.floor()),
-                        _ => unimplemented!(),
-                    },
-                    Ceil => match value {
-                        Value::Int(x) => Value::Int(x),
-                        Value::Float(x) => Value::Float(x.ceil()),
-                        _ => unimplemented!(),
-                    },
-                    Round => match value {
-                        Value::Int(x) => Value::Int(x),
-                        Value::Float(x) => Value::Float(x.round()),
-                        _ => unimplemented!(),
-                    },
-                    Degree => match value {
-                        Value::Int(x) => Value::Float((x as f64).to_radians()),
-                        Value::Float(x) => Value::Float(x.to_radians()),
-                        _ => unimplemented!(),
-                    },
-                    Fact => match value {
-                        Value::Int(x) => Value::Int((1..=x).product()),
-                        Value::Float(x) => Value::Int((1..=(x as i32)).product()),
-                        _ => unimplemented!(),
-                    },
-                    Sqrt => match value {
-                        Value::Int(x) => Value::Float((x as f64).sqrt()),
-                        Value::Float(x) => Value::Float(x.sqrt()),
-                        _ => unimplemented!(),
-                    },
-                    Cbrt => match value {
-                        Value::Int(x) => Value::Int((x as f64).cbrt() as i32),
-                        Value::Float(x) => Value::Float(x.cbrt()),
-                        _ => unimplemented!(),
-                    },
-                    Fort => match value {
-                        Value::Int(x) => Value::Int((x as f64).sqrt().sqrt() as i32),
-                        Value::Float(x) => Value::Float(x.sqrt().sqrt()),
-                        _ => unimplemented!(),
-                    },
-                    Not => Value::Bool(!(bool::from(value))),
+                    UnaryOp::Prime => self.derivative(value)?,
+                    op => unary(op, value)?,
                 }
             }
-            Node::Binary(left, op, right) => {
-                let l_value = self.visit(*left);
-                let r_value = self.visit(*right);
-
-                macro_rules! simple_binary_op {
-                    ($op:tt) => {
-                        match (l_value, r_value) {
-                            (Value::Int(a), Value::Int(b)) => Value::Int(a $op b),
-                            (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) $op b),
-                            (Value::Int(a), Value::Complex(r, i)) => Value::Complex((a as f64) $op r, i),
-                            (Value::Float(a), Value::Int(b)) => Value::Float(a $op (b as f64)),
-                            (Value::Float(a), Value::Float(b)) => Value::Float(a $op b),
-                            (Value::Float(a), Value::Complex(r, i)) => Value::Complex(a $op r, i),
-                            (Value::Complex(r, i), Value::Int(x)) => Value::Complex(r $op (x as f64), i),
-                            (Value::Complex(r, i), Value::Float(x)) => Value::Complex(r $op x, i),
-                            (Value::Complex(r, i), Value::Complex(r2, i2)) => Value::Complex(r $op r2, i $op i2),
-                            _ => unimplemented!(),
-                        }
-                    };
+            Node::Binary(left, op @ (BinaryOp::And | BinaryOp::Or | BinaryOp::Implies), right) => {
+                // The right side is only evaluated if the left doesn't settle
+                // the result
+                let left = bool::from(self.visit(*left)?);
+                if left == (op == BinaryOp::Or) {
+                    Value::Bool(op != BinaryOp::And)
+                } else {
+                    Value::Bool(self.visit(*right)?.into())
                 }
-
-                use BinaryOp::*;
+            }
+            Node::Binary(left, op, right) => {
+                let l_value = self.visit(*left)?;
+                let r_value = self.visit(*right)?;
                 match op {
-                    Add => simple_binary_op!(+),
-                    Sub => simple_binary_op!(-),
-                    Mul => match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => Value::Int(a * b),
-                        (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) * b),
-                        (Value::Int(a), Value::Complex(r, i)) => {
-                            Value::Complex((a as f64) * r, (a as f64) * i)
-                        }
-                        (Value::Int(a), Value::Function(name, arg_names, body)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(Box::new(Node::Int(a)), Mul, body)),
-                        ),
-                        (Value::Float(a), Value::Int(b)) => Value::Float(a * (b as f64)),
-                        (Value::Float(a), Value::Float(b)) => Value::Float(a * b),
-                        (Value::Float(a), Value::Complex(r, i)) => Value::Complex(a * r, a * i),
-                        (Value::Float(a), Value::Function(name, arg_names, body)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(Box::new(Node::Float(a)), Mul, body)),
-                            )
-                        }
-                        (Value::Complex(r, i), Value::Int(x)) => Value::Complex(r * (x as f64), i),
-                        (Value::Complex(r, i), Value::Float(x)) => Value::Complex(r * x, i),
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => {
-                            let ii = i * i2;
-                            Value::Complex(r * r2 - ii, r * i2 + ii)
-                        }
-                        (Value::Function(name, arg_names, body), Value::Int(a)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(body, Mul, Box::new(Node::Int(a)))),
-                        ),
-                        (Value::Function(name, arg_names, body), Value::Float(a)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(body, Mul, Box::new(Node::Float(a)))),
-                            )
-                        }
-                        (l, r) => panic!("Cannot multiply {} by {}", l, r),
-                    },
-                    Div => match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => Value::Int(a / b),
-                        (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) / b),
-                        (Value::Int(a), Value::Complex(r, i)) => {
-                            Value::Complex((a as f64) / r, (a as f64) / i)
-                        }
-                        (Value::Int(a), Value::Function(name, arg_names, body)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(Box::new(Node::Int(a)), Div, body)),
-                        ),
-                        (Value::Float(a), Value::Int(b)) => Value::Float(a / (b as f64)),
-                        (Value::Float(a), Value::Float(b)) => Value::Float(a / b),
-                        (Value::Float(a), Value::Complex(r, i)) => Value::Complex(a / r, a / i),
-                        (Value::Float(a), Value::Function(name, arg_names, body)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(Box::new(Node::Float(a)), Mul, body)),
-                            )
-                        }
-                        (Value::Complex(r, i), Value::Int(x)) => Value::Complex(r / (x as f64), i),
-                        (Value::Complex(r, i), Value::Float(x)) => Value::Complex(r / x, i),
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => Value::Complex(
-                            (r * r2 + i * i2) / (r2 * r2 + i2 * i2),
-                            (i * r2 - r * i2) / (r2 * r2 + i2 * i2),
-                        ),
-                        (Value::Function(name, arg_names, body), Value::Int(a)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(body, Div, Box::new(Node::Int(a)))),
-                        ),
-                        (Value::Function(name, arg_names, body), Value::Float(a)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(body, Div, Box::new(Node::Float(a)))),
-                            )
-                        }
-                        (l, r) => panic!("Cannot divide {} by {}", l, r),
-                    },
-                    Rem => match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => Value::Int(a % b),
-                        (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) % b),
-                        (Value::Int(a), Value::Function(name, arg_names, body)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(Box::new(Node::Int(a)), Div, body)),
-                        ),
-                        (Value::Float(a), Value::Int(b)) => Value::Float(a % (b as f64)),
-                        (Value::Float(a), Value::Float(b)) => Value::Float(a % b),
-                        (Value::Float(a), Value::Function(name, arg_names, body)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(Box::new(Node::Float(a)), Rem, body)),
-                            )
-                        }
-                        (Value::Function(name, arg_names, body), Value::Int(a)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(body, Rem, Box::new(Node::Int(a)))),
-                        ),
-                        (Value::Function(name, arg_names, body), Value::Float(a)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(body, Rem, Box::new(Node::Float(a)))),
-                            )
-                        }
-                        (l, r) => panic!("Cannot take remainder of {} and {}", l, r),
-                    },
-                    Pow => match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => Value::Int(a.pow(b as u32)),
-                        (Value::Int(a), Value::Float(b)) => Value::Float((a as f64).powf(b)),
-                        (Value::Int(a), Value::Complex(r, i)) => {
-                            let r = (a as f64).powf(r);
-                            let i = (a as f64).powf(i);
-                            Value::Complex(r * i.cos(), r * i.sin())
-                        }
-                        (Value::Int(a), Value::Function(name, arg_names, body)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(Box::new(Node::Int(a)), Pow, body)),
-                        ),
-                        (Value::Float(a), Value::Int(b)) => Value::Float(a.powi(b)),
-                        (Value::Float(a), Value::Float(b)) => Value::Float(a.powf(b)),
-                        (Value::Float(a), Value::Complex(r, i)) => {
-                            let r = a.powf(r);
-                            let i = a.powf(i);
-                            Value::Complex(r * i.cos(), r * i.sin())
-                        }
-                        (Value::Float(a), Value::Function(name, arg_names, body)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(Box::new(Node::Float(a)), Pow, body)),
-                            )
-                        }
-                        (Value::Complex(r, i), Value::Int(x)) => {
-                            let r = r.powi(x);
-                            let i = i.powi(x);
-                            Value::Complex(r * i.cos(), r * i.sin())
-                        }
-                        (Value::Complex(r, i), Value::Float(x)) => {
-                            let r = r.powf(x);
-                            let i = i.powf(x);
-                            Value::Complex(r * i.cos(), r * i.sin())
-                        }
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => {
-                            let r = r.hypot(i);
-                            let i = i.atan2(r);
-                            let r2 = r2.hypot(i2);
-                            let i2 = i2.atan2(r2);
-                            let r = r.powf(r2);
-                            let i = i.powf(i2);
-                            Value::Complex(r * i.cos(), r * i.sin())
-                        }
-                        (Value::Function(name, arg_names, body), Value::Int(a)) => Value::Function(
-                            name,
-                            arg_names,
-                            Box::new(Node::Binary(body, Pow, Box::new(Node::Int(a)))),
-                        ),
-                        (Value::Function(name, arg_names, body), Value::Float(a)) => {
-                            Value::Function(
-                                name,
-                                arg_names,
-                                Box::new(Node::Binary(body, Pow, Box::new(Node::Float(a)))),
-                            )
-                        }
-                        (l, r) => panic!("Cannot raise {} to the power of {}", l, r),
-                    },
-                    EqEq => Value::Bool(l_value == r_value),
-                    Neq => Value::Bool(l_value != r_value),
-                    Lt => Value::Bool(match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => a < b,
-                        (Value::Int(a), Value::Float(b)) => (a as f64) < b,
-                        (Value::Int(x), Value::Complex(r, i)) => ((x * x) as f64) < (r * r + i * i),
-                        (Value::Float(a), Value::Int(b)) => a < (b as f64),
-                        (Value::Float(a), Value::Float(b)) => a < b,
-                        (Value::Float(x), Value::Complex(r, i)) => (x * x) < (r * r + i * i),
-                        (Value::Complex(r, i), Value::Int(x)) => (r * r + i * i) < ((x * x) as f64),
-                        (Value::Complex(r, i), Value::Float(x)) => (r * r + i * i) < (x * x),
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => {
-                            (r * r + i * i) < (r2 * r2 + i2 * i2)
-                        }
-                        (l, r) => panic!("Cannot compare {} {} {}", l, Lt, r),
-                    }),
-                    Lte => Value::Bool(match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => a <= b,
-                        (Value::Int(a), Value::Float(b)) => (a as f64) <= b,
-                        (Value::Int(x), Value::Complex(r, i)) => {
-                            ((x * x) as f64) <= (r * r + i * i)
-                        }
-                        (Value::Float(a), Value::Int(b)) => a <= (b as f64),
-                        (Value::Float(a), Value::Float(b)) => a <= b,
-                        (Value::Float(x), Value::Complex(r, i)) => (x * x) <= (r * r + i * i),
-                        (Value::Complex(r, i), Value::Int(x)) => {
-                            (r * r + i * i) <= ((x * x) as f64)
-                        }
-                        (Value::Complex(r, i), Value::Float(x)) => (r * r + i * i) <= (x * x),
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => {
-                            (r * r + i * i) <= (r2 * r2 + i2 * i2)
-                        }
-                        (l, r) => panic!("Cannot compare {} {} {}", l, Lte, r),
-                    }),
-                    Gt => Value::Bool(match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => a > b,
-                        (Value::Int(a), Value::Float(b)) => (a as f64) > b,
-                        (Value::Int(x), Value::Complex(r, i)) => ((x * x) as f64) > (r * r + i * i),
-                        (Value::Float(a), Value::Int(b)) => a > (b as f64),
-                        (Value::Float(a), Value::Float(b)) => a > b,
-                        (Value::Float(x), Value::Complex(r, i)) => (x * x) > (r * r + i * i),
-                        (Value::Complex(r, i), Value::Int(x)) => (r * r + i * i) > ((x * x) as f64),
-                        (Value::Complex(r, i), Value::Float(x)) => (r * r + i * i) > (x * x),
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => {
-                            (r * r + i * i) > (r2 * r2 + i2 * i2)
-                        }
-                        (l, r) => panic!("Cannot compare {} {} {}", l, Gt, r),
-                    }),
-                    Gte => Value::Bool(match (l_value, r_value) {
-                        (Value::Int(a), Value::Int(b)) => a >= b,
-                        (Value::Int(a), Value::Float(b)) => (a as f64) >= b,
-                        (Value::Int(x), Value::Complex(r, i)) => {
-                            ((x * x) as f64) >= (r * r + i * i)
-                        }
-                        (Value::Float(a), Value::Int(b)) => a >= (b as f64),
-                        (Value::Float(a), Value::Float(b)) => a >= b,
-                        (Value::Float(x), Value::Complex(r, i)) => (x * x) >= (r * r + i * i),
-                        (Value::Complex(r, i), Value::Int(x)) => {
-                            (r * r + i * i) >= ((x * x) as f64)
-                        }
-                        (Value::Complex(r, i), Value::Float(x)) => (r * r + i * i) >= (x * x),
-                        (Value::Complex(r, i), Value::Complex(r2, i2)) => {
-                            (r * r + i * i) >= (r2 * r2 + i2 * i2)
-                        }
-                        (l, r) => panic!("Cannot compare {} {} {}", l, Gte, r),
-                    }),
-                    And => Value::Bool(l_value.into() && r_value.into()),
-                    Or => Value::Bool(l_value.into() || r_value.into()),
+                    BinaryOp::Approx => approx(l_value, r_value, self.tolerance)?,
+                    op => binary(op, l_value, r_value)?,
                 }
             }
             Node::If(cond, then, else_case) => {
-                let cond = self.visit(*cond);
+                let cond = self.visit(*cond)?;
                 if bool::from(cond) {
-                    self.visit(*then)
+                    self.visit(*then)?
                 } else {
                     match else_case {
-                        Some(else_case) => self.visit(*else_case),
+                        Some(else_case) => self.visit(*else_case)?,
                         None => Value::Int(0),
                     }
                 }
             }
             Node::While(cond, body) => {
                 let mut rtn_value = Value::Int(0);
-                while bool::from(self.visit(*cond.clone())) {
-                    rtn_value = self.visit(*body.clone());
+                while bool::from(self.visit(*cond.clone())?) {
+                    if !keep_looping(self.visit(*body.clone()), &mut rtn_value)? {
+                        break;
+                    }
                 }
                 rtn_value
             }
-            Node::FnDef(name, arg_names, node) => {
+            Node::For(var, iterable, body) => {
+                let elements = iterate(self.visit(*iterable)?)?;
+                let scope = Scope::new(Rc::clone(&self.scope));
+                self.with_scope(scope, |interpreter| -> Flow {
+                    let mut rtn_value = Value::Int(0);
+                    for element in elements {
+                        interpreter
+                            .scope
+                            .borrow_mut()
+                            .define(Rc::clone(&var), element);
+                        let flow = interpreter.visit(*body.clone());
+                        if !keep_looping(flow, &mut rtn_value)? {
+                            break;
+                        }
+                    }
+                    Ok(rtn_value)
+                })?
+            }
+            Node::Range(start, end, step, inclusive) => {
+                let start = self.visit(*start)?;
+                let end = self.visit(*end)?;
+                let step = match step {
+                    Some(step) => Some(self.visit(*step)?),
+                    None => None,
+                };
+                range(start, end, step, inclusive)?
+            }
+            Node::Break => return Err(Unwind::Break),
+            Node::Continue => return Err(Unwind::Continue),
+            Node::Return(value) => {
+                let value = match value {
+                    Some(value) => self.visit(*value)?,
+                    None => Value::Int(0),
+                };
+                return Err(Unwind::Return(value));
+            }
+            Node::FnDef(name, arg_names, body) => {
+                let function =
+                    Function::new(Rc::clone(&name), arg_names, *body, Rc::clone(&self.scope));
                 self.scope
-                    .set(Rc::clone(&name), Value::Function(name, arg_names, node));
+                    .borrow_mut()
+                    .define(name, Value::Function(Rc::new(function)));
                 Value::Int(0)
             }
-            Node::Call(name, args) => {
-                let arg_values = args
-                    .into_iter()
-                    .map(|arg| self.visit(arg))
-                    .collect::<Vec<Value>>();
+            Node::Lambda(arg_names, body) => Value::Function(Rc::new(Function::new(
+                "λ".into(),
+                arg_names,
+                *body,
+                Rc::clone(&self.scope),
+            ))),
+            Node::Call(function, args) => {
+                let (function, subscript) = self.callee(*function)?;
+                let mut arg_values = map(args, |arg| self.visit(arg))?;
+                arg_values.extend(subscript);
 
-                let function = self.scope.get(&name);
                 match function {
-                    Value::Function(_, arg_names, body) => {
-                        let mut interpreter = Interpreter::default();
-                        for (name, value) in arg_names.iter().zip(arg_values) {
-                            interpreter.add_var(name, value.clone());
+                    Value::Function(function) => {
+                        function.check_args(&arg_values)?;
+                        let mut frame = Scope::new(Rc::clone(&function.scope));
+                        for (name, value) in function.params.iter().zip(arg_values) {
+                            frame.define(Rc::clone(name), value);
                         }
-                        interpreter.visit(*body)
+                        let body = function.body.clone();
+                        returned(self.with_scope(frame, |interpreter| interpreter.visit(body)))?
+                    }
+                    Value::NativeFunction(function) => function(self, &arg_values)?,
+                    Value::Polynomial(polynomial) => polynomial.call(&arg_values)?,
+                    function => {
+                        return error("type error", format!("{} is not a function", function))
+                            .map_err(Unwind::from)
                     }
-                    Value::NativeFunction(function) => function(&arg_values),
-                    _ => panic!("{} is not a function", name),
                 }
             }
+            Node::BigOperator(op, index, iterable, body) => {
+                let elements = iterate_all(self.visit(*iterable)?)?;
+                self.fold(op, index, elements, *body)?
+            }
+            Node::Integral(var, start, end, body) => {
+                let start = real_number(&self.visit(*start)?)?;
+                let end = real_number(&self.visit(*end)?)?;
+                let tolerance = self.tolerance;
+                let scope = Scope::new(Rc::clone(&self.scope));
+                let value = self.with_scope(scope, |interpreter| {
+                    let integrand = |x| {
+                        interpreter
+                            .scope
+                            .borrow_mut()
+                            .define(Rc::clone(&var), Value::Float(x));
+                        real_number(&interpreter.visit((*body).clone()).map_err(Unwind::error)?)
+                    };
+                    integrate(integrand, start, end, tolerance)
+                })?;
+                Value::Float(value)
+            }
             Node::Statements(nodes) => {
                 let mut rtn_value = Value::Int(0);
                 for node in nodes {
-                    rtn_value = self.visit(node);
+                    rtn_value = self.visit(node)?;
                 }
                 rtn_value
             }
             Node::Eof => Value::Int(0),
-        }
+        })
     }
 }
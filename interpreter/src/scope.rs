@@ -1,21 +1,217 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-use crate::Value;
+use crate::{error, RuntimeResult, Value};
 
+/// Maps the names bound in a scope to their slots. Compiled code shares one
+/// layout between every call of a function.
+pub type Layout = Rc<HashMap<Rc<str>, usize>>;
+
+/// A single environment in the lexical scope chain. Lookups that miss in
+/// this scope fall through to `parent`, ending at the globals.
+///
+/// Variables live in numbered slots so that compiled code can address them
+/// directly; the tree-walker finds slots by name through the layout. A slot
+/// is `None` until its variable is first assigned.
 #[derive(Default)]
 pub struct Scope {
-    pub variables: HashMap<Rc<str>, Value>,
+    layout: Layout,
+    slots: Vec<Option<Value>>,
+    pub parent: Option<Rc<RefCell<Scope>>>,
 }
 
 impl Scope {
-    pub fn get(&self, name: &str) -> Value {
-        match self.variables.get(name) {
-            Some(value) => value.clone(),
-            None => panic!("{} is not defined", name),
+    pub fn new(parent: Rc<RefCell<Scope>>) -> Self {
+        Self {
+            parent: Some(parent),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a scope with a slot for each name in `layout`, all unset.
+    pub fn with_layout(layout: Layout, parent: Option<Rc<RefCell<Scope>>>) -> Self {
+        Self {
+            slots: vec![None; layout.len()],
+            layout,
+            parent,
         }
     }
 
+    pub fn get(&self, name: &str) -> RuntimeResult {
+        match self
+            .layout
+            .get(name)
+            .and_then(|&slot| self.slots[slot].as_ref())
+        {
+            Some(value) => Ok(value.clone()),
+            None => match &self.parent {
+                Some(parent) => parent.borrow().get(name),
+                None => error("undefined variable", format!("{} is not defined", name)),
+            },
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.layout
+            .get(name)
+            .is_some_and(|&slot| self.slots[slot].is_some())
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.borrow().contains(name))
+    }
+
+    /// Assigns to the nearest existing binding of `name`, or defines it in
+    /// this scope if no enclosing scope has it.
     pub fn set(&mut self, name: Rc<str>, value: Value) {
-        self.variables.insert(name, value);
+        let here = self
+            .layout
+            .get(&name)
+            .is_some_and(|&slot| self.slots[slot].is_some());
+        if !here {
+            if let Some(parent) = &self.parent {
+                if parent.borrow().contains(&name) {
+                    parent.borrow_mut().set(name, value);
+                    return;
+                }
+            }
+        }
+        self.define(name, value);
+    }
+
+    /// Binds `name` in this scope, shadowing any enclosing binding.
+    pub fn define(&mut self, name: Rc<str>, value: Value) {
+        let slot = self.declare(name);
+        self.slots[slot] = Some(value);
+    }
+
+    /// Returns the slot of `name` in this scope, adding an unset one if it
+    /// has none.
+    pub fn declare(&mut self, name: Rc<str>) -> usize {
+        if let Some(&slot) = self.layout.get(&name) {
+            return slot;
+        }
+        let slot = self.slots.len();
+        Rc::make_mut(&mut self.layout).insert(name, slot);
+        self.slots.push(None);
+        slot
+    }
+
+    /// The names bound to `value` here and in every enclosing scope, as for
+    /// finding what a built-in function is called.
+    pub fn names_of(&self, value: &Value) -> Vec<Rc<str>> {
+        let mut names: Vec<Rc<str>> = self
+            .layout
+            .iter()
+            .filter(|(_, &slot)| self.slots[slot].as_ref() == Some(value))
+            .map(|(name, _)| Rc::clone(name))
+            .collect();
+        if let Some(parent) = &self.parent {
+            names.extend(parent.borrow().names_of(value));
+        }
+        names
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Reads slot `slot` of the scope `depth` levels up the chain. Compiled
+    /// code only knows which scope may hold a variable, so if that slot is
+    /// still unset this looks the name up as [`Scope::get`] does.
+    pub fn load(&self, depth: usize, slot: usize) -> RuntimeResult {
+        match self.slot(depth, slot) {
+            Ok(value) => Ok(value),
+            Err(name) => self.get(&name),
+        }
+    }
+
+    /// Assigns to slot `slot` of the scope `depth` levels up the chain, or,
+    /// if that slot is still unset, to wherever [`Scope::set`] would.
+    pub fn assign(&mut self, depth: usize, slot: usize, value: Value) {
+        if depth == 0 {
+            if self.slots[slot].is_some() {
+                self.slots[slot] = Some(value);
+                return;
+            }
+        } else if let Some(parent) = &self.parent {
+            if parent.borrow_mut().reassign(depth - 1, slot, &value) {
+                return;
+            }
+        }
+        let name = self.name(depth, slot);
+        self.set(name, value);
+    }
+
+    /// Writes slot `slot` of the scope `depth` levels up the chain, whether
+    /// or not it is set, as binding arguments does.
+    pub fn store(&mut self, depth: usize, slot: usize, value: Value) {
+        if depth > 0 {
+            match &self.parent {
+                Some(parent) => parent.borrow_mut().store(depth - 1, slot, value),
+                None => unreachable!("scope chain is shallower than compiled code expects"),
+            }
+            return;
+        }
+        self.slots[slot] = Some(value);
+    }
+
+    /// The value in slot `slot` of the scope `depth` levels up the chain, or
+    /// the name of its variable if it is unset.
+    fn slot(&self, depth: usize, slot: usize) -> Result<Value, Rc<str>> {
+        if depth > 0 {
+            return match &self.parent {
+                Some(parent) => parent.borrow().slot(depth - 1, slot),
+                None => unreachable!("scope chain is shallower than compiled code expects"),
+            };
+        }
+        match &self.slots[slot] {
+            Some(value) => Ok(value.clone()),
+            None => Err(self.name(0, slot)),
+        }
+    }
+
+    /// Overwrites slot `slot` of the scope `depth` levels up the chain if it
+    /// is set, returning whether it was.
+    fn reassign(&mut self, depth: usize, slot: usize, value: &Value) -> bool {
+        if depth > 0 {
+            return match &self.parent {
+                Some(parent) => parent.borrow_mut().reassign(depth - 1, slot, value),
+                None => unreachable!("scope chain is shallower than compiled code expects"),
+            };
+        }
+        match &mut self.slots[slot] {
+            Some(old) => {
+                *old = value.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The name of the variable in slot `slot` of the scope `depth` levels up
+    /// the chain.
+    fn name(&self, depth: usize, slot: usize) -> Rc<str> {
+        if depth > 0 {
+            return match &self.parent {
+                Some(parent) => parent.borrow().name(depth - 1, slot),
+                None => unreachable!("scope chain is shallower than compiled code expects"),
+            };
+        }
+        self.layout
+            .iter()
+            .find(|(_, &s)| s == slot)
+            .map(|(name, _)| Rc::clone(name))
+            .expect("every slot has a name")
+    }
+}
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Functions hold their defining scope, so printing values here could
+        // recurse forever.
+        f.debug_struct("Scope")
+            .field("variables", &self.layout.keys().collect::<Vec<_>>())
+            .field("parent", &self.parent.is_some())
+            .finish()
     }
 }
@@ -0,0 +1,392 @@
+use std::ops::Range;
+
+use parser::{BinaryOp, Node, Spanned, UnaryOp};
+
+use crate::{
+    ops::{binary, unary},
+    RuntimeResult, Value,
+};
+
+/// Rewrites `node` into a smaller equivalent by folding constants, dropping
+/// identities like `x*1` and `x^1`, collecting like terms and merging powers
+/// of the same base.
+///
+/// Multiplication is treated as commutative, and `x/x` as 1, as is usual for
+/// algebra on numbers.
+pub fn simplify(node: &Spanned<Node>) -> Spanned<Node> {
+    let range = &node.range;
+    let boxed = |node: &Spanned<Node>| Box::new(simplify(node));
+    let node = Spanned::new(
+        match &node.node {
+            Node::Vector(nodes) => Node::Vector(nodes.iter().map(simplify).collect()),
+            Node::List(nodes) => Node::List(nodes.iter().map(simplify).collect()),
+            Node::Interpolation(parts) => Node::Interpolation(parts.iter().map(simplify).collect()),
+            Node::Matrix(rows) => Node::Matrix(
+                rows.iter()
+                    .map(|row| row.iter().map(simplify).collect())
+                    .collect(),
+            ),
+            Node::Assignment(name, value) => Node::Assignment(name.clone(), boxed(value)),
+            Node::Unary(op, node) => Node::Unary(*op, boxed(node)),
+            Node::Binary(left, op, right) => Node::Binary(boxed(left), *op, boxed(right)),
+            Node::If(cond, then, else_case) => {
+                Node::If(boxed(cond), boxed(then), else_case.as_deref().map(boxed))
+            }
+            Node::While(cond, body) => Node::While(boxed(cond), boxed(body)),
+            Node::Return(value) => Node::Return(value.as_deref().map(boxed)),
+            Node::FnDef(name, params, body) => {
+                Node::FnDef(name.clone(), params.clone(), boxed(body))
+            }
+            Node::Lambda(params, body) => Node::Lambda(params.clone(), boxed(body)),
+            Node::Call(function, args) => {
+                Node::Call(boxed(function), args.iter().map(simplify).collect())
+            }
+            Node::Subscript(name, base, index) => {
+                Node::Subscript(name.clone(), base.clone(), boxed(index))
+            }
+            Node::Index(node, index) => Node::Index(boxed(node), boxed(index)),
+            Node::Integral(var, start, end, body) => {
+                Node::Integral(var.clone(), boxed(start), boxed(end), boxed(body))
+            }
+            Node::BigOperator(op, index, iterable, body) => {
+                Node::BigOperator(*op, index.clone(), boxed(iterable), boxed(body))
+            }
+            Node::For(var, iterable, body) => Node::For(var.clone(), boxed(iterable), boxed(body)),
+            Node::Range(start, end, step, inclusive) => Node::Range(
+                boxed(start),
+                boxed(end),
+                step.as_deref().map(boxed),
+                *inclusive,
+            ),
+            Node::Statements(nodes) => Node::Statements(nodes.iter().map(simplify).collect()),
+            node => node.clone(),
+        },
+        range.clone(),
+    );
+
+    match &node.node {
+        Node::Unary(UnaryOp::Pos | UnaryOp::Neg, _)
+        | Node::Binary(
+            _,
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow,
+            _,
+        ) => {
+            let mut terms = vec![];
+            collect_terms(&node, false, &mut terms);
+            sum(combine(terms), range)
+        }
+        Node::Unary(..) | Node::Binary(..) => constant(&node.node)
+            .and_then(|value| literal(&value, range))
+            .unwrap_or(node),
+        Node::Call(function, args) if logarithm_of_base(&function.node, args) => {
+            Spanned::new(Node::Int(1), range.clone())
+        }
+        _ => node,
+    }
+}
+
+/// Whether `function(args)` is the logarithm of its own base, as `ln(e)`,
+/// `log(10)`, `log₂(2)` and `log(b, b)` are.
+fn logarithm_of_base(function: &Node, args: &[Spanned<Node>]) -> bool {
+    match (function, args) {
+        (Node::Identifier(name), [x]) if &**name == "ln" => {
+            matches!(&x.node, Node::Identifier(name) if &**name == "e")
+        }
+        (Node::Identifier(name), [x]) if &**name == "log" => is(&x.node, 10),
+        (Node::Identifier(name), [x, base]) if &**name == "log" => {
+            x.to_string() == base.to_string()
+        }
+        (Node::Subscript(_, name, subscript), [x]) if &**name == "log" => {
+            x.to_string() == subscript.to_string()
+        }
+        _ => false,
+    }
+}
+
+/// A product of a number and powers of other expressions.
+struct Term {
+    coefficient: Value,
+    /// Bases with their exponents, in the order they first appeared.
+    factors: Vec<(Spanned<Node>, Spanned<Node>)>,
+}
+
+impl Term {
+    /// Identifies the expressions the coefficient multiplies, regardless of
+    /// their order, so that like terms can be found.
+    fn key(&self) -> String {
+        let mut factors: Vec<String> = self
+            .factors
+            .iter()
+            .map(|(base, exponent)| format!("{}^{}", base, exponent))
+            .collect();
+        factors.sort();
+        factors.join(" ")
+    }
+}
+
+/// Splits a sum into its terms, negating them if `negate` is set.
+fn collect_terms(node: &Spanned<Node>, negate: bool, terms: &mut Vec<Term>) {
+    match &node.node {
+        Node::Binary(left, BinaryOp::Add, right) => {
+            collect_terms(left, negate, terms);
+            collect_terms(right, negate, terms);
+        }
+        Node::Binary(left, BinaryOp::Sub, right) => {
+            collect_terms(left, negate, terms);
+            collect_terms(right, !negate, terms);
+        }
+        Node::Unary(UnaryOp::Neg, node) => collect_terms(node, !negate, terms),
+        Node::Unary(UnaryOp::Pos, node) => collect_terms(node, negate, terms),
+        _ => {
+            let mut term = Term {
+                coefficient: Value::Int(if negate { -1 } else { 1 }),
+                factors: vec![],
+            };
+            collect_factors(node, false, &mut term);
+            merge_powers(&mut term);
+            terms.push(term);
+        }
+    }
+}
+
+/// Multiplies `term` by `node`, or divides it if `invert` is set.
+fn collect_factors(node: &Spanned<Node>, invert: bool, term: &mut Term) {
+    if let Some(value) = constant(&node.node) {
+        let op = if invert { BinaryOp::Div } else { BinaryOp::Mul };
+        if let Ok(coefficient) = binary(op, term.coefficient.clone(), value) {
+            if literal(&coefficient, &node.range).is_some() {
+                term.coefficient = coefficient;
+                return;
+            }
+        }
+    }
+
+    match &node.node {
+        Node::Binary(left, BinaryOp::Mul, right) => {
+            collect_factors(left, invert, term);
+            collect_factors(right, invert, term);
+        }
+        Node::Binary(left, BinaryOp::Div, right) => {
+            collect_factors(left, invert, term);
+            collect_factors(right, !invert, term);
+        }
+        Node::Unary(UnaryOp::Neg, node) => {
+            term.coefficient = number(unary(UnaryOp::Neg, term.coefficient.clone()));
+            collect_factors(node, invert, term);
+        }
+        // 1ˣ = 1
+        Node::Binary(base, BinaryOp::Pow, _) if is(&base.node, 1) => {}
+        Node::Binary(base, BinaryOp::Pow, exponent) => {
+            // (bᵉ)ⁿ = bᵉⁿ only holds for whole n
+            let (base, exponent) = match (&base.node, constant(&exponent.node)) {
+                (Node::Binary(inner, BinaryOp::Pow, inner_exponent), Some(Value::Int(_))) => (
+                    (**inner).clone(),
+                    simplify(&binary_node(
+                        (**inner_exponent).clone(),
+                        BinaryOp::Mul,
+                        (**exponent).clone(),
+                    )),
+                ),
+                _ => ((**base).clone(), (**exponent).clone()),
+            };
+            let exponent = if invert { negate(exponent) } else { exponent };
+            term.factors.push((base, exponent));
+        }
+        _ => {
+            let exponent = Spanned::new(Node::Int(if invert { -1 } else { 1 }), node.range.clone());
+            term.factors.push((node.clone(), exponent));
+        }
+    }
+}
+
+/// Combines factors with the same base by adding their exponents.
+fn merge_powers(term: &mut Term) {
+    let mut merged: Vec<(Spanned<Node>, Spanned<Node>)> = vec![];
+    for (base, exponent) in term.factors.drain(..) {
+        let key = base.to_string();
+        match merged
+            .iter_mut()
+            .find(|(other, _)| other.to_string() == key)
+        {
+            Some((_, total)) => {
+                *total = simplify(&binary_node(total.clone(), BinaryOp::Add, exponent));
+            }
+            None => merged.push((base, exponent)),
+        }
+    }
+    merged.retain(|(_, exponent)| !is(&exponent.node, 0));
+    term.factors = merged;
+}
+
+/// Adds up the coefficients of like terms, dropping any that cancel out.
+fn combine(terms: Vec<Term>) -> Vec<Term> {
+    let mut combined: Vec<(String, Term)> = vec![];
+    for term in terms {
+        let key = term.key();
+        match combined.iter_mut().find(|(other, _)| *other == key) {
+            Some((_, like)) => {
+                like.coefficient = number(binary(
+                    BinaryOp::Add,
+                    like.coefficient.clone(),
+                    term.coefficient,
+                ));
+            }
+            None => combined.push((key, term)),
+        }
+    }
+    combined
+        .into_iter()
+        .map(|(_, term)| term)
+        .filter(|term| !is_value(&term.coefficient, 0))
+        .collect()
+}
+
+/// Rebuilds a sum of terms, subtracting the negative ones.
+fn sum(terms: Vec<Term>, range: &Range<usize>) -> Spanned<Node> {
+    let mut result: Option<Spanned<Node>> = None;
+    for term in terms {
+        let (negative, node) = product(term, range);
+        result = Some(match (result, negative) {
+            (None, false) => node,
+            (None, true) => Spanned::new(Node::Unary(UnaryOp::Neg, Box::new(node)), range.clone()),
+            (Some(sum), false) => binary_node(sum, BinaryOp::Add, node),
+            (Some(sum), true) => binary_node(sum, BinaryOp::Sub, node),
+        });
+    }
+    result.unwrap_or_else(|| Spanned::new(Node::Int(0), range.clone()))
+}
+
+/// Rebuilds a term as a fraction, returning its magnitude and whether it's
+/// negative.
+fn product(term: Term, range: &Range<usize>) -> (bool, Spanned<Node>) {
+    let negative = term.coefficient.is_negative();
+    let coefficient = if negative {
+        number(unary(UnaryOp::Neg, term.coefficient))
+    } else {
+        term.coefficient
+    };
+
+    let mut numerator = vec![];
+    let mut denominator = vec![];
+    let (n, d) = match coefficient {
+        Value::Rational(n, d) => (Value::from(n), Some(Value::from(d))),
+        coefficient => (coefficient, None),
+    };
+    if !is_value(&n, 1) || term.factors.is_empty() {
+        numerator.extend(literal(&n, range));
+    }
+    if let Some(d) = d {
+        denominator.extend(literal(&d, range));
+    }
+    for (base, exponent) in term.factors {
+        let inverse = match constant(&exponent.node) {
+            Some(Value::Int(x)) if x < 0 => Some(Value::Int(-x)),
+            _ => None,
+        };
+        match inverse {
+            Some(exponent) => denominator.push(power(base, literal(&exponent, range).unwrap())),
+            None => numerator.push(power(base, exponent)),
+        }
+    }
+
+    let multiply = |factors: Vec<Spanned<Node>>| {
+        factors
+            .into_iter()
+            .reduce(|product, factor| binary_node(product, BinaryOp::Mul, factor))
+    };
+    let numerator =
+        multiply(numerator).unwrap_or_else(|| Spanned::new(Node::Int(1), range.clone()));
+    let node = match multiply(denominator) {
+        Some(denominator) => binary_node(numerator, BinaryOp::Div, denominator),
+        None => numerator,
+    };
+    (negative, node)
+}
+
+fn power(base: Spanned<Node>, exponent: Spanned<Node>) -> Spanned<Node> {
+    if is(&exponent.node, 1) {
+        base
+    } else {
+        binary_node(base, BinaryOp::Pow, exponent)
+    }
+}
+
+fn negate(node: Spanned<Node>) -> Spanned<Node> {
+    let range = node.range.clone();
+    match constant(&node.node)
+        .and_then(|value| unary(UnaryOp::Neg, value).ok())
+        .and_then(|value| literal(&value, &range))
+    {
+        Some(node) => node,
+        None => Spanned::new(Node::Unary(UnaryOp::Neg, Box::new(node)), range),
+    }
+}
+
+fn binary_node(left: Spanned<Node>, op: BinaryOp, right: Spanned<Node>) -> Spanned<Node> {
+    let range = left.range.start.min(right.range.start)..left.range.end.max(right.range.end);
+    Spanned::new(Node::Binary(Box::new(left), op, Box::new(right)), range)
+}
+
+/// Evaluates `node` if it's arithmetic on numbers alone. Results are only
+/// kept if they're exact or came from floats, so that `√2` isn't replaced by
+/// an approximation.
+fn constant(node: &Node) -> Option<Value> {
+    let (value, inexact) = match node {
+        Node::Int(x) => return Some(Value::Int(*x)),
+        Node::BigInt(x) => return Some(Value::BigInt(x.clone())),
+        Node::Float(x) => return Some(Value::Float(*x)),
+        Node::Unary(op, node) => {
+            let value = constant(&node.node)?;
+            let inexact = matches!(value, Value::Float(_));
+            (unary(*op, value).ok()?, inexact)
+        }
+        Node::Binary(left, op, right) => {
+            let (left, right) = (constant(&left.node)?, constant(&right.node)?);
+            let inexact = matches!(left, Value::Float(_)) || matches!(right, Value::Float(_));
+            (binary(*op, left, right).ok()?, inexact)
+        }
+        _ => return None,
+    };
+    match value {
+        Value::Float(x) if inexact && x.is_finite() => Some(value),
+        value if value.is_exact() => Some(value),
+        _ => None,
+    }
+}
+
+/// Writes a real number as an expression.
+fn literal(value: &Value, range: &Range<usize>) -> Option<Spanned<Node>> {
+    let node = match value {
+        Value::Int(x) => Node::Int(*x),
+        Value::BigInt(x) => Node::BigInt(x.clone()),
+        Value::Float(x) if x.is_finite() => Node::Float(*x),
+        Value::Rational(n, d) => {
+            return Some(binary_node(
+                literal(&Value::from(n.clone()), range)?,
+                BinaryOp::Div,
+                literal(&Value::from(d.clone()), range)?,
+            ))
+        }
+        _ => return None,
+    };
+    Some(Spanned::new(node, range.clone()))
+}
+
+/// Unwraps arithmetic on coefficients, which can't fail since they're always
+/// real numbers.
+fn number(result: RuntimeResult) -> Value {
+    result.unwrap_or_else(|_| unreachable!("coefficients are real numbers"))
+}
+
+/// Whether `node` is the number `x`.
+fn is(node: &Node, x: i32) -> bool {
+    constant(node).is_some_and(|value| is_value(&value, x))
+}
+
+fn is_value(value: &Value, x: i32) -> bool {
+    match value {
+        Value::Int(value) => *value == x,
+        Value::Float(value) => *value == x as f64,
+        _ => false,
+    }
+}
@@ -1,31 +1,184 @@
-use std::{fmt, rc::Rc};
+use std::{
+    cell::{OnceCell, RefCell},
+    fmt,
+    rc::Rc,
+};
 
-use parser::Node;
+use common::SpectreError;
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+use parser::{Node, Spanned};
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{compile::Code, error, simplify, Complex, Interpreter, Polynomial, Range, Scope};
+
+#[derive(Debug, Clone)]
 pub enum Value {
     Int(i32),
+    /// An integer outside the range of `Int`, which integer arithmetic
+    /// promotes to instead of overflowing.
+    BigInt(BigInt),
+    /// A fraction in lowest terms whose denominator is greater than 1, as
+    /// made by dividing integers.
+    Rational(BigInt, BigInt),
     Float(f64),
     Bool(bool),
+    Str(Rc<str>),
     Complex(f64, f64),
-    Function(Rc<str>, Vec<Rc<str>>, Box<Node>),
-    NativeFunction(fn(&Vec<Value>) -> Value),
+    Vector(Vec<Value>),
+    /// Values of any types, which unlike a vector's elements needn't be
+    /// numbers and aren't added up elementwise.
+    List(Vec<Value>),
+    Matrix(Vec<Vec<Value>>),
+    Polynomial(Polynomial),
+    Range(Rc<Range>),
+    Function(Rc<Function>),
+    /// A built-in function, which gets the interpreter so that it can call
+    /// the functions it's given.
+    NativeFunction(fn(&mut Interpreter, &[Value]) -> Result<Value, SpectreError>),
+}
+
+/// A user-defined function or lambda, closing over the scope it was created in.
+#[derive(Debug)]
+pub struct Function {
+    pub name: Rc<str>,
+    pub params: Vec<Rc<str>>,
+    pub body: Spanned<Node>,
+    pub scope: Rc<RefCell<Scope>>,
+    /// The body compiled for the VM, filled in on the first call if it wasn't
+    /// compiled along with the code that created the function.
+    pub(crate) code: OnceCell<Rc<Code>>,
+}
+
+impl Function {
+    pub fn new(
+        name: Rc<str>,
+        params: Vec<Rc<str>>,
+        body: Spanned<Node>,
+        scope: Rc<RefCell<Scope>>,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            body,
+            scope,
+            code: OnceCell::new(),
+        }
+    }
+
+    /// Returns a function with the same parameters and scope whose body is
+    /// `f` applied to this one's, simplified so that repeated arithmetic on
+    /// functions doesn't keep growing their bodies.
+    pub fn map_body(&self, f: impl FnOnce(Spanned<Node>) -> Spanned<Node>) -> Value {
+        Value::Function(Rc::new(Function::new(
+            Rc::clone(&self.name),
+            self.params.clone(),
+            simplify(&f(self.body.clone())),
+            Rc::clone(&self.scope),
+        )))
+    }
+
+    /// Checks that a call passes one argument for each parameter.
+    pub(crate) fn check_args(&self, args: &[Value]) -> Result<(), SpectreError> {
+        if args.len() == self.params.len() {
+            return Ok(());
+        }
+        let plural = if self.params.len() == 1 { "" } else { "s" };
+        error(
+            "invalid arguments",
+            format!(
+                "{} expects {} argument{}, got {}",
+                self.name,
+                self.params.len(),
+                plural,
+                args.len()
+            ),
+        )
+    }
 }
 
 use Value::*;
 
+impl Value {
+    pub fn is_number(&self) -> bool {
+        matches!(
+            self,
+            Int(_) | Value::BigInt(_) | Rational(..) | Float(_) | Complex(_, _)
+        )
+    }
+
+    /// Whether this is an integer or rational, which arithmetic keeps exact.
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Int(_) | Value::BigInt(_) | Rational(..))
+    }
+
+    /// Whether this is a real number less than zero.
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Int(x) => *x < 0,
+            Value::BigInt(x) => x.is_negative(),
+            Rational(n, _) => n.is_negative(),
+            Float(x) => *x < 0.0,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Int(a), Int(b)) => a == b,
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (Rational(n, d), Rational(n2, d2)) => n == n2 && d == d2,
+            (Float(a), Float(b)) => a == b,
+            (Bool(a), Bool(b)) => a == b,
+            (Str(a), Str(b)) => a == b,
+            (Complex(r, i), Complex(r2, i2)) => r == r2 && i == i2,
+            (Vector(a), Vector(b)) => a == b,
+            (List(a), List(b)) => a == b,
+            (Matrix(a), Matrix(b)) => a == b,
+            (Value::Polynomial(a), Value::Polynomial(b)) => a == b,
+            (Value::Range(a), Value::Range(b)) => a == b,
+            (Function(a), Function(b)) => {
+                a.name == b.name
+                    && a.params == b.params
+                    && a.body == b.body
+                    && Rc::ptr_eq(&a.scope, &b.scope)
+            }
+            (NativeFunction(a), NativeFunction(b)) => std::ptr::fn_addr_eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
 impl From<i32> for Value {
     fn from(value: i32) -> Self {
         Int(value)
     }
 }
 
+/// Demotes `value` to an `Int` if it fits, so every integer has one
+/// representation.
+impl From<BigInt> for Value {
+    fn from(value: BigInt) -> Self {
+        match i32::try_from(&value) {
+            Ok(value) => Int(value),
+            Err(_) => Value::BigInt(value),
+        }
+    }
+}
+
 impl From<f64> for Value {
     fn from(value: f64) -> Self {
         Float(value)
     }
 }
 
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Str(value.into())
+    }
+}
+
 impl From<bool> for Value {
     fn from(value: bool) -> Self {
         Bool(value)
@@ -38,14 +191,27 @@ impl From<(f64, f64)> for Value {
     }
 }
 
+impl From<Complex> for Value {
+    fn from(value: Complex) -> Self {
+        Value::Complex(value.re, value.im)
+    }
+}
+
 impl From<Value> for bool {
     fn from(val: Value) -> Self {
         match val {
             Int(x) => x != 0,
+            Value::BigInt(x) => !x.is_zero(),
+            Rational(n, _) => !n.is_zero(),
             Float(x) => x != 0.0,
             Bool(x) => x,
-            Complex(r, i) => r != 0.0 && i != 0.0,
-            Function(_, _, _) | NativeFunction(_) => true,
+            Str(text) => !text.is_empty(),
+            Complex(r, i) => r != 0.0 || i != 0.0,
+            Vector(v) | List(v) => !v.is_empty(),
+            Matrix(rows) => !rows.is_empty(),
+            Value::Polynomial(p) => p.degree().is_some(),
+            Value::Range(range) => range.get(0).is_some(),
+            Function(..) | NativeFunction(_) => true,
         }
     }
 }
@@ -54,10 +220,77 @@ impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Int(value) => write!(f, "{}", value),
+            Value::BigInt(value) => write!(f, "{}", value),
+            Rational(n, d) => write!(f, "{}/{}", n, d),
             Float(value) => write!(f, "{}", value),
             Bool(value) => write!(f, "{}", value),
-            Complex(r, i) => write!(f, "{} + {}i", r, i),
-            Function(name, _, _) => write!(f, "<fn {}>", name),
+            Str(text) => write!(f, "{}", text),
+            Complex(r, i) => write!(f, "{}", crate::Complex::new(*r, *i)),
+            Vector(v) => write!(
+                f,
+                "⟨{}⟩",
+                v.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            List(items) => write!(
+                f,
+                "[{}]",
+                items
+                    .iter()
+                    .map(|x| match x {
+                        Str(text) => format!("{:?}", text),
+                        x => x.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Matrix(rows) => {
+                let cells: Vec<Vec<String>> = rows
+                    .iter()
+                    .map(|row| row.iter().map(|x| x.to_string()).collect())
+                    .collect();
+                let widths: Vec<usize> = (0..cells.first().map_or(0, |row| row.len()))
+                    .map(|j| {
+                        cells
+                            .iter()
+                            .map(|row| row[j].chars().count())
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                for (i, row) in cells.iter().enumerate() {
+                    let (left, right) = match (i, cells.len()) {
+                        (_, 1) => ('[', ']'),
+                        (0, _) => ('⎡', '⎤'),
+                        (i, n) if i == n - 1 => ('⎣', '⎦'),
+                        _ => ('⎢', '⎥'),
+                    };
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    let row = row
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, &width)| format!("{:>width$}", cell, width = width))
+                        .collect::<Vec<_>>()
+                        .join("  ");
+                    write!(f, "{}{}{}", left, row, right)?;
+                }
+                Ok(())
+            }
+            Value::Polynomial(p) => write!(f, "{}", p),
+            Value::Range(range) => write!(f, "{}", range),
+            Function(function) => {
+                let params = function.params.join(", ");
+                let body = simplify(&function.body);
+                if &*function.name == "λ" {
+                    write!(f, "({}) ↦ {}", params, body)
+                } else {
+                    write!(f, "{}({}) = {}", function.name, params, body)
+                }
+            }
             NativeFunction(_) => write!(f, "<native fn>"),
         }
     }
@@ -0,0 +1,309 @@
+use std::{cell::OnceCell, rc::Rc};
+
+use parser::{BinaryOp, Node, Spanned, UnaryOp};
+
+use crate::{
+    builtins::calculus::{integrate, real_number},
+    compile::{Chunk, Code, Compiler, Instruction},
+    error, keep_looping,
+    ops::{approx, binary, index, interpolate, iterate, iterate_all, range, unary},
+    returned, Flow, Function, Interpreter, RuntimeResult, Scope, Unwind, Value,
+};
+
+impl Interpreter {
+    /// Compiles `ast` to bytecode and runs it.
+    pub fn run(&mut self, ast: Spanned<Node>) -> RuntimeResult {
+        let chunk = Compiler::new(Rc::clone(&self.scope)).compile(&ast);
+        self.execute(&chunk, &mut vec![]).map_err(Unwind::error)
+    }
+
+    /// Runs `chunk` on top of `stack`, giving the value it leaves and
+    /// putting the stack back as it was. Loop bodies and the like share their
+    /// caller's stack rather than allocating one each time they run.
+    fn execute(&mut self, chunk: &Chunk, stack: &mut Vec<Value>) -> Flow {
+        let base = stack.len();
+        let flow = self.steps(chunk, stack);
+        let value = (stack.len() > base).then(|| pop(stack));
+        stack.truncate(base);
+        flow.map(|()| value.unwrap_or(Value::Int(0)))
+    }
+
+    fn steps(&mut self, chunk: &Chunk, stack: &mut Vec<Value>) -> Result<(), Unwind> {
+        let mut ip = 0;
+
+        while let Some(instruction) = chunk.code.get(ip) {
+            ip += 1;
+            let result = match instruction {
+                Instruction::Jump(target) => {
+                    ip = *target;
+                    continue;
+                }
+                Instruction::JumpIfFalse(target) => {
+                    if !bool::from(pop(stack)) {
+                        ip = *target;
+                    }
+                    continue;
+                }
+                Instruction::LoadOrJump(depth, slot, target) => {
+                    if let Ok(value) = self.scope.borrow().load(*depth, *slot) {
+                        stack.push(value);
+                        ip = *target;
+                    }
+                    continue;
+                }
+                instruction => self.step(instruction, stack),
+            };
+            match result {
+                Err(Unwind::Error(mut e)) if e.range.is_empty() => {
+                    e.range = chunk.ranges[ip - 1].clone();
+                    return Err(Unwind::Error(e));
+                }
+                Err(unwind) => return Err(unwind),
+                Ok(()) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn step(&mut self, instruction: &Instruction, stack: &mut Vec<Value>) -> Result<(), Unwind> {
+        match instruction {
+            Instruction::Constant(value) => stack.push(value.clone()),
+            Instruction::Load(depth, slot) => {
+                let value = self.scope.borrow().load(*depth, *slot)?;
+                stack.push(value);
+            }
+            Instruction::Store(depth, slot) => {
+                let value = stack.last().cloned().unwrap_or(Value::Int(0));
+                self.scope.borrow_mut().assign(*depth, *slot, value);
+            }
+            Instruction::Define(slot) => {
+                let value = pop(stack);
+                self.scope.borrow_mut().store(0, *slot, value);
+            }
+            Instruction::Pop => {
+                stack.pop();
+            }
+            Instruction::Unary(op) => {
+                let value = pop(stack);
+                stack.push(match op {
+                    UnaryOp::Prime => self.derivative(value)?,
+                    op => unary(*op, value)?,
+                });
+            }
+            Instruction::Binary(op) => {
+                let r_value = pop(stack);
+                let l_value = pop(stack);
+                stack.push(match op {
+                    BinaryOp::Approx => approx(l_value, r_value, self.tolerance)?,
+                    op => binary(*op, l_value, r_value)?,
+                });
+            }
+            Instruction::Interpolate(n) => {
+                let parts = stack.split_off(stack.len() - n);
+                stack.push(interpolate(parts));
+            }
+            Instruction::Vector(n) => {
+                let values = stack.split_off(stack.len() - n);
+                stack.push(Value::Vector(values));
+            }
+            Instruction::List(n) => {
+                let values = stack.split_off(stack.len() - n);
+                stack.push(Value::List(values));
+            }
+            Instruction::Matrix(lengths) => {
+                let mut values = stack.split_off(stack.len() - lengths.iter().sum::<usize>());
+                if lengths.iter().any(|&length| length != lengths[0]) {
+                    return error(
+                        "type error",
+                        "All rows of a matrix must have the same length".to_string(),
+                    )
+                    .map_err(Unwind::from);
+                }
+                let mut rows = Vec::with_capacity(lengths.len());
+                for &length in lengths.iter().rev() {
+                    rows.push(values.split_off(values.len() - length));
+                }
+                rows.reverse();
+                stack.push(Value::Matrix(rows));
+            }
+            Instruction::Jump(_) | Instruction::JumpIfFalse(_) | Instruction::LoadOrJump(..) => {
+                unreachable!("jumps are handled by execute")
+            }
+            Instruction::While(cond, body) => {
+                let mut rtn_value = Value::Int(0);
+                while bool::from(self.execute(cond, stack)?) {
+                    if !keep_looping(self.execute(body, stack), &mut rtn_value)? {
+                        break;
+                    }
+                }
+                stack.push(rtn_value);
+            }
+            Instruction::Break => return Err(Unwind::Break),
+            Instruction::Continue => return Err(Unwind::Continue),
+            Instruction::Return => return Err(Unwind::Return(pop(stack))),
+            Instruction::Call(n) => {
+                let args = stack.split_off(stack.len() - n);
+                let function = pop(stack);
+                let value = self.call_on(function, args, stack)?;
+                stack.push(value);
+            }
+            Instruction::CallSubscripted(n) => {
+                let mut args = stack.split_off(stack.len() - n);
+                let subscript = pop(stack);
+                let function = match pop(stack) {
+                    function @ (Value::Function(_) | Value::NativeFunction(_)) => {
+                        args.push(subscript);
+                        function
+                    }
+                    value => index(value, subscript)?,
+                };
+                let value = self.call_on(function, args, stack)?;
+                stack.push(value);
+            }
+            Instruction::Index => {
+                let subscript = pop(stack);
+                let value = pop(stack);
+                stack.push(index(value, subscript)?);
+            }
+            Instruction::Function(prototype) => {
+                let function = Function {
+                    name: Rc::clone(&prototype.name),
+                    params: prototype.params.clone(),
+                    body: prototype.body.clone(),
+                    scope: Rc::clone(&self.scope),
+                    code: OnceCell::from(Rc::clone(&prototype.code)),
+                };
+                stack.push(Value::Function(Rc::new(function)));
+            }
+            Instruction::Fold(op, code) => {
+                let elements = iterate_all(pop(stack))?;
+                let value = self.fold_code(*op, code, elements, stack)?;
+                stack.push(value);
+            }
+            Instruction::For(code) => {
+                let elements = iterate(pop(stack))?;
+                let scope =
+                    Scope::with_layout(Rc::clone(&code.layout), Some(Rc::clone(&self.scope)));
+                let value = self.with_scope(scope, |interpreter| -> Flow {
+                    let mut rtn_value = Value::Int(0);
+                    for element in elements {
+                        interpreter
+                            .scope
+                            .borrow_mut()
+                            .store(0, code.params[0], element);
+                        let flow = interpreter.execute(&code.chunk, stack);
+                        if !keep_looping(flow, &mut rtn_value)? {
+                            break;
+                        }
+                    }
+                    Ok(rtn_value)
+                })?;
+                stack.push(value);
+            }
+            Instruction::Range(has_step, inclusive) => {
+                let step = has_step.then(|| pop(stack));
+                let end = pop(stack);
+                let start = pop(stack);
+                stack.push(range(start, end, step, *inclusive)?);
+            }
+            Instruction::Integral(code) => {
+                let end = real_number(&pop(stack))?;
+                let start = real_number(&pop(stack))?;
+                let tolerance = self.tolerance;
+
+                let scope =
+                    Scope::with_layout(Rc::clone(&code.layout), Some(Rc::clone(&self.scope)));
+                let value = self.with_scope(scope, |interpreter| {
+                    let integrand = |x| {
+                        interpreter
+                            .scope
+                            .borrow_mut()
+                            .store(0, code.params[0], Value::Float(x));
+                        let value = interpreter.execute(&code.chunk, stack);
+                        real_number(&value.map_err(Unwind::error)?)
+                    };
+                    integrate(integrand, start, end, tolerance)
+                })?;
+                stack.push(Value::Float(value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Folds the body in `code` over each of `elements` with `op`, as in `∑`
+    /// and `∏`.
+    fn fold_code(
+        &mut self,
+        op: BinaryOp,
+        code: &Code,
+        elements: impl Iterator<Item = Value>,
+        stack: &mut Vec<Value>,
+    ) -> Flow {
+        let identity = match op {
+            BinaryOp::Mul => Value::Int(1),
+            _ => Value::Int(0),
+        };
+
+        let scope = Scope::with_layout(Rc::clone(&code.layout), Some(Rc::clone(&self.scope)));
+        self.with_scope(scope, |interpreter| {
+            // Start from the first term rather than the identity so sums of
+            // vectors and matrices work
+            let mut result: Option<Value> = None;
+            for element in elements {
+                interpreter
+                    .scope
+                    .borrow_mut()
+                    .store(0, code.params[0], element);
+                let term = interpreter.execute(&code.chunk, stack)?;
+                result = Some(match result {
+                    Some(result) => binary(op, result, term)?,
+                    None => term,
+                });
+            }
+            Ok(result.unwrap_or(identity))
+        })
+    }
+
+    /// Calls `function` with `args`, as built-in functions do with the
+    /// functions they're given.
+    pub fn call(&mut self, function: Value, args: Vec<Value>) -> RuntimeResult {
+        self.call_on(function, args, &mut vec![])
+    }
+
+    /// Calls `function` as [`Interpreter::call`] does, running its body on
+    /// top of `stack`.
+    fn call_on(
+        &mut self,
+        function: Value,
+        args: Vec<Value>,
+        stack: &mut Vec<Value>,
+    ) -> RuntimeResult {
+        match function {
+            Value::Function(function) => {
+                function.check_args(&args)?;
+                let code = Rc::clone(
+                    function
+                        .code
+                        .get_or_init(|| Rc::new(Compiler::function(&function))),
+                );
+                let mut frame =
+                    Scope::with_layout(Rc::clone(&code.layout), Some(Rc::clone(&function.scope)));
+                for (&slot, value) in code.params.iter().zip(args) {
+                    frame.store(0, slot, value);
+                }
+                returned(
+                    self.with_scope(frame, |interpreter| interpreter.execute(&code.chunk, stack)),
+                )
+            }
+            Value::NativeFunction(function) => function(self, &args),
+            Value::Polynomial(polynomial) => polynomial.call(&args),
+            function => error("type error", format!("{} is not a function", function)),
+        }
+    }
+}
+
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack
+        .pop()
+        .expect("compiled code should not underflow the stack")
+}
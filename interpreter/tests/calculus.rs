@@ -0,0 +1,97 @@
+//! Checks derivatives against values worked out by hand.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn polynomials() {
+    assert_eq!(eval("f(x) = x³ + 2x; f′(2)"), "14");
+    assert_eq!(eval("f(x) = x³ + 2x; f''(2)"), "12");
+    assert_eq!(eval("f(x) = 5; f'(1)"), "0");
+}
+
+#[test]
+fn product_quotient_and_chain_rules() {
+    assert_eq!(eval("f(x) = x² (x + 1); f'(2)"), "16");
+    assert_eq!(eval("f(x) = 1 / x; f'(2)"), "-1/4");
+    assert_eq!(eval("f(x) = (x + 1) / (x - 1); f'(3)"), "-1/2");
+    assert_eq!(eval("g(x) = x² + 1; f(x) = g(x)³; f'(1)"), "24");
+}
+
+#[test]
+fn builtin_functions() {
+    assert_eq!(eval("f(x) = sin(x); f'(0)"), "1");
+    assert_eq!(eval("f(x) = cos(x)^2 + sin(x)^2; f'(1)"), "0");
+    assert_eq!(eval("f(x) = ln(x); f'(4)"), "1/4");
+    assert_eq!(eval("f(x) = √x; f'(4)"), "0.25");
+    assert_eq!(eval("f(x) = exp(2x); f'(0)"), "2");
+}
+
+#[test]
+fn builtin_functions_by_name() {
+    assert_eq!(eval("sin'(0)"), "1");
+    assert_eq!(eval("deriv(sin)(0)"), "1");
+    assert_eq!(eval("ln′(4)"), "1/4");
+    assert_eq!(eval("cos''(0)"), "-1");
+    assert_eq!(eval("g = exp; g'(0)"), "1");
+    assert_eq!(
+        eval("len'(1)"),
+        "not differentiable: This built-in function has no known derivative"
+    );
+}
+
+#[test]
+fn exponentials() {
+    assert_eq!(eval("f(x) = 2^x; f'(0) == ln(2)"), "true");
+    assert_eq!(eval("f(x) = x^x; f'(1)"), "1");
+}
+
+#[test]
+fn deriv_builtin() {
+    assert_eq!(eval("deriv(x ↦ x²)(3)"), "6");
+    assert_eq!(eval("f(x, a) = a x²; deriv(f)(1, 3)"), "6");
+    assert_eq!(eval("deriv(x ↦ ⟨x, x²⟩)(3)"), "⟨1, 6⟩");
+    assert_eq!(
+        eval("f(x) = if x < 0 { -x } else { x² }; f'(-1) + f'(2)"),
+        "3"
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        eval("f(x) = x!; f'(1)"),
+        "not differentiable: Cannot differentiate (x!) with respect to x"
+    );
+    assert_eq!(eval("deriv(3)"), "type error: Cannot differentiate 3");
+}
+
+#[test]
+fn definite_integrals() {
+    assert_eq!(eval("∫₀^π sin(x) dx"), "2");
+    assert_eq!(eval("∫(0, 1, x², x)"), "0.3333333333333333");
+    assert_eq!(eval("∫₁^0 x dx"), "-0.5");
+    assert_eq!(eval("integrate(sin, 0, π)"), "2");
+    assert_eq!(eval("f(x) = x³; integrate(f, 0, 2)"), "4");
+    assert_eq!(eval("g(a) = ∫₀¹ a t² dt; g'(3)"), "0.3333333333333333");
+    assert_eq!(
+        eval("∫₀₌₁¹ x dx"),
+        "expected token: expected the end of the lower bound"
+    );
+    assert_eq!(
+        eval("∫₀¹⁼² x dx"),
+        "expected token: expected the end of the upper bound"
+    );
+}
+
+#[test]
+fn improper_integrals() {
+    assert_eq!(eval("∫(-∞, ∞, 1/(1 + x²), x) - π < 0.000000001"), "true");
+    assert_eq!(eval("∫₀¹ 1/√x dx - 2 < 0.000000001"), "true");
+    assert_eq!(eval("∫(0, ∞, e^(-x), x)"), "1");
+    assert_eq!(
+        eval("∫(1, ∞, 1/x, x)"),
+        "not convergent: The integral didn't converge to within 0.0000000001"
+    );
+}
@@ -0,0 +1,18 @@
+//! Helpers shared by the integration tests.
+
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+
+/// Runs `source` and prints its value, or the error lexing, parsing or
+/// running it.
+pub fn eval(source: &str) -> String {
+    let result = Lexer::new(source.to_string())
+        .lex()
+        .and_then(|tokens| Parser::new(tokens).parse())
+        .and_then(|ast| Interpreter::default().run(ast));
+    match result {
+        Ok(value) => value.to_string(),
+        Err(e) => format!("{}: {}", e.msg, e.reason),
+    }
+}
@@ -0,0 +1,147 @@
+//! Checks the complex functions against identities they must satisfy.
+
+use std::f64::consts::{E, PI};
+
+use interpreter::Complex;
+use proptest::prelude::*;
+
+mod common;
+
+use common::eval;
+
+fn close(a: Complex, b: Complex) -> bool {
+    (a - b).abs() <= 1e-9 * (1.0 + a.abs().max(b.abs()))
+}
+
+fn complex(range: f64) -> impl Strategy<Value = Complex> {
+    (-range..range, -range..range).prop_map(|(re, im)| Complex::new(re, im))
+}
+
+fn nonzero(range: f64) -> impl Strategy<Value = Complex> {
+    complex(range).prop_filter("nonzero", |z| z.abs() > 1e-3)
+}
+
+#[test]
+fn known_values() {
+    assert_eq!(Complex::I * Complex::I, Complex::new(-1.0, 0.0));
+    assert!(close(
+        Complex::from(E).pow(Complex::I * PI.into()),
+        (-1.0).into()
+    ));
+    assert!(close(Complex::I.pow(Complex::I), (-PI / 2.0).exp().into()));
+    assert_eq!(Complex::from(-4.0).sqrt(), Complex::new(0.0, 2.0));
+    assert!(close(Complex::from(-1.0).ln(), Complex::new(0.0, PI)));
+    assert_eq!(
+        Complex::new(1.0, 2.0) * Complex::new(3.0, 4.0),
+        Complex::new(-5.0, 10.0)
+    );
+    assert_eq!(
+        Complex::from(2.0) / Complex::new(1.0, 1.0),
+        Complex::new(1.0, -1.0)
+    );
+}
+
+#[test]
+fn negative_reals_have_complex_roots_and_logarithms() {
+    assert_eq!(eval("sqrt(-4)"), "0 + 2i");
+    assert_eq!(eval("√(-4)"), "0 + 2i");
+    assert_eq!(eval("sqrt(-4)² ≈ -4"), "true");
+    assert_eq!(eval("ln(-1) ≈ iπ"), "true");
+    assert_eq!(eval("log(-100) ≈ 2 + iπ/ln(10)"), "true");
+    assert_eq!(eval("(-8)^(1/3) ≈ 1 + i√3"), "true");
+    assert_eq!(eval("(-2.0)^0.5 ≈ i√2"), "true");
+    assert_eq!(eval("(-2)^3"), "-8");
+    assert_eq!(eval("(-2.0)^2.0"), "4");
+}
+
+#[test]
+fn complex_numbers_have_no_order() {
+    assert_eq!(eval("2i == 2i"), "true");
+    assert_eq!(eval("2i < 3"), "type error: Cannot compare 0 + 2i < 3 + 0i");
+    assert_eq!(
+        eval("1 >= i"),
+        "type error: Cannot compare 1 + 0i >= 0 + 1i"
+    );
+}
+
+proptest! {
+    #[test]
+    fn multiplication_commutes(a in complex(1e3), b in complex(1e3)) {
+        prop_assert_eq!(a * b, b * a);
+    }
+
+    #[test]
+    fn division_undoes_multiplication(a in complex(1e3), b in nonzero(1e3)) {
+        prop_assert!(close(a * b / b, a));
+    }
+
+    #[test]
+    fn magnitudes_multiply(a in complex(1e3), b in complex(1e3)) {
+        prop_assert!(((a * b).abs() - a.abs() * b.abs()).abs() <= 1e-9 * (1.0 + (a * b).abs()));
+    }
+
+    #[test]
+    fn conjugation_distributes(a in complex(1e3), b in complex(1e3)) {
+        prop_assert!(close((a * b).conj(), a.conj() * b.conj()));
+    }
+
+    #[test]
+    fn exp_inverts_ln(z in nonzero(1e3)) {
+        prop_assert!(close(z.ln().exp(), z));
+    }
+
+    #[test]
+    fn ln_inverts_exp_on_the_principal_strip(re in -10.0..10.0f64, im in -3.0..3.0f64) {
+        let z = Complex::new(re, im);
+        prop_assert!(close(z.exp().ln(), z));
+    }
+
+    #[test]
+    fn exp_turns_sums_into_products(a in complex(10.0), b in complex(10.0)) {
+        prop_assert!(close((a + b).exp(), a.exp() * b.exp()));
+    }
+
+    #[test]
+    fn sqrt_is_principal(z in complex(1e3)) {
+        let root = z.sqrt();
+        prop_assert!(close(root * root, z));
+        prop_assert!(root.re >= 0.0);
+    }
+
+    #[test]
+    fn integer_powers_are_repeated_products(z in complex(1e3)) {
+        prop_assert_eq!(z.pow(2.0.into()), z * z);
+        prop_assert!(close(z.pow(3.0.into()), z * z * z));
+    }
+
+    #[test]
+    fn powers_agree_with_exp_and_ln(z in nonzero(10.0), w in complex(2.0)) {
+        prop_assert!(close(z.pow(w), (w * z.ln()).exp()));
+    }
+
+    #[test]
+    fn pythagorean_identity(z in complex(5.0)) {
+        let (sin, cos) = (z.sin(), z.cos());
+        prop_assert!(close(sin * sin + cos * cos, Complex::ONE));
+    }
+
+    #[test]
+    fn hyperbolic_identity(z in complex(5.0)) {
+        let (sinh, cosh) = (z.sinh(), z.cosh());
+        prop_assert!(close(cosh * cosh - sinh * sinh, Complex::ONE));
+    }
+
+    #[test]
+    fn trig_and_hyperbolic_functions_are_rotations(z in complex(5.0)) {
+        let iz = Complex::I * z;
+        prop_assert!(close(iz.sin(), Complex::I * z.sinh()));
+        prop_assert!(close(iz.cos(), z.cosh()));
+        prop_assert!(close(iz.tan(), Complex::I * z.tanh()));
+    }
+
+    #[test]
+    fn eulers_formula(x in -10.0..10.0f64) {
+        let z = (Complex::I * Complex::from(x)).exp();
+        prop_assert!(close(z, Complex::new(x.cos(), x.sin())));
+    }
+}
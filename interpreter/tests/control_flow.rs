@@ -0,0 +1,105 @@
+//! Checks `break`, `continue` and `return`, and the logical operators that
+//! skip their right side.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn logical_operators_short_circuit() {
+    assert_eq!(eval("0 and 1/0"), "false");
+    assert_eq!(eval("1 or 1/0"), "true");
+    assert_eq!(eval("1 > 2 → 1/0"), "true");
+    assert_eq!(eval("2 and 3"), "true");
+    assert_eq!(eval("0 or []"), "false");
+    assert_eq!(eval("1 < 2 → 0"), "false");
+    assert_eq!(
+        eval("n = 0\nbump() = { n = n + 1 }\n1 or bump()\n0 and bump()\nn"),
+        "0"
+    );
+    assert_eq!(
+        eval("1 and 1/0"),
+        "division by zero: Cannot divide an integer by zero"
+    );
+}
+
+#[test]
+fn break_leaves_the_innermost_loop() {
+    assert_eq!(
+        eval("t = 0\nfor k in 1..∞ {\n  if k > 4 { break }\n  t = t + k\n}\nt"),
+        "10"
+    );
+    assert_eq!(
+        eval("n = 0\nfor i in 1..=3 {\n  for j in 1..=3 {\n    if j > i { break }\n    n = n + 1\n  }\n}\nn"),
+        "6"
+    );
+    assert_eq!(
+        eval("i = 0\nwhile 1 { i = i + 1\nif i == 5 { break } }\ni"),
+        "5"
+    );
+}
+
+#[test]
+fn loops_keep_the_value_of_their_last_full_iteration() {
+    assert_eq!(eval("for k in 1..10 { if k == 4 { break }\nk² }"), "9");
+    assert_eq!(eval("for k in 1..=4 { if k == 4 { continue }\nk² }"), "9");
+}
+
+#[test]
+fn continue_skips_to_the_next_iteration() {
+    assert_eq!(
+        eval("t = 0\nfor k in 1..=10 {\n  if k % 2 == 0 { continue }\n  t = t + k\n}\nt"),
+        "25"
+    );
+    assert_eq!(
+        eval(
+            "i = 0\nt = 0\nwhile i < 5 {\n  i = i + 1\n  if i == 2 { continue }\n  t = t + i\n}\nt"
+        ),
+        "13"
+    );
+}
+
+#[test]
+fn return_leaves_the_innermost_function() {
+    let source = "
+        first_square_over(n) = {
+            for k in 1..∞ {
+                if k² > n { return k }
+            }
+        }
+        first_square_over(50)";
+    assert_eq!(eval(source), "8");
+    assert_eq!(eval("f(x) = { return x + 1\nx + 2 }\nf(1)"), "2");
+    assert_eq!(eval("f(x) = { if x < 0 { return }\n√x }\nf(-4)"), "0");
+    assert_eq!(eval("f = x ↦ { while 1 { return 2x } }\nf(3)"), "6");
+}
+
+#[test]
+fn return_can_leave_mid_expression() {
+    let source = "
+        f(x) = 1 + (if x > 0 { x } else { return -1 })
+        ⟨f(2), f(-2)⟩";
+    assert_eq!(eval(source), "⟨3, -1⟩");
+}
+
+#[test]
+fn return_only_leaves_the_called_function() {
+    let source = "
+        inner(x) = { return x * 10 }
+        outer(x) = inner(x) + 1
+        outer(2)";
+    assert_eq!(eval(source), "21");
+    assert_eq!(eval("g(f) = f(1) + f(2)\ng(x ↦ { return x² })"), "5");
+}
+
+#[test]
+fn solve_equations_cant_leave_what_is_around_them() {
+    assert_eq!(
+        eval("for i in 1..3 { solve(x - break = 0, x) }"),
+        "invalid control flow: 'break' can only be used inside a loop"
+    );
+    assert_eq!(
+        eval("f() = solve(x - return 1 = 0, x)\nf()"),
+        "invalid control flow: 'return' can only be used inside a function"
+    );
+}
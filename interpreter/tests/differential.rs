@@ -0,0 +1,463 @@
+//! Runs programs through both the bytecode VM and the tree-walker and checks
+//! that they agree.
+
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+
+/// Runs `source` a line at a time, as the REPL does, printing each result or
+/// error so the two engines' transcripts can be compared.
+fn transcript(source: &str, walk: bool) -> Vec<String> {
+    let mut interpreter = Interpreter::default();
+    let mut offset = 0;
+    source
+        .lines()
+        .map(|line| {
+            let result = outcome(&mut interpreter, line, offset, walk);
+            offset += line.chars().count() + 1;
+            result
+        })
+        .collect()
+}
+
+/// Runs `source`, starting at `offset`, and prints its result or error.
+fn outcome(interpreter: &mut Interpreter, source: &str, offset: usize, walk: bool) -> String {
+    let tokens = Lexer::with_offset(source.to_string(), offset)
+        .lex()
+        .unwrap_or_else(|e| panic!("failed to lex {:?}: {}", source, e.reason));
+    let ast = Parser::new(tokens)
+        .parse()
+        .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", source, e.reason));
+    let result = if walk {
+        interpreter.walk(ast)
+    } else {
+        interpreter.run(ast)
+    };
+    match result {
+        Ok(value) => value.to_string(),
+        Err(e) => format!("{}: {} at {:?}", e.msg, e.reason, e.range),
+    }
+}
+
+fn check(source: &str) {
+    assert_eq!(transcript(source, false), transcript(source, true));
+}
+
+/// Checks `source` run all at once, as a file is, rather than a line at a
+/// time.
+fn check_program(source: &str) {
+    let vm = outcome(&mut Interpreter::default(), source, 0, false);
+    let walker = outcome(&mut Interpreter::default(), source, 0, true);
+    assert_eq!(vm, walker, "for {:?}", source);
+}
+
+#[test]
+fn arithmetic() {
+    check(
+        "1 + 2 * 3
+        10 - 3 - 2
+        2 ^ 3 ^ 2
+        7 % 3
+        1 / 4.0
+        2x
+        x = 3
+        2x²
+        -x!
+        √16 + ∛27
+        |-3|
+        ⌊2.5⌋ + ⌈2.5⌉
+        90°
+        1 < 2 and 2 <= 2 or not 3 > 4
+        1 ≠ 2 ∧ 2 ≤ 2 ∨ ¬(3 ≥ 4)
+        0.1 + 0.2 ≈ 0.3
+        ⟨1, 2⟩ ≈ ⟨1, 2.001⟩
+        2 ∈ ⟨1, 2, 3⟩
+        2 ∉ [1, 2; 3, 4]
+        1 ∈ 3
+        1 > 2 → 1 / 0 == 0",
+    );
+}
+
+#[test]
+fn variables_and_assignment() {
+    check(
+        "a = b = 2
+        a + b
+        a = a + 1
+        a
+        ℏ = 2
+        2ℏ + 𝜑 - 𝜙
+        cafe\u{301} = 3
+        caf\u{e9}
+        undefined + 1",
+    );
+}
+
+#[test]
+fn control_flow() {
+    check(
+        "x = 0
+        s = 0
+        while x < 10 { s = s + x; x = x + 1 }
+        s
+        if s > 40 { 1 } else { 2 }
+        if s < 40 { 1 }
+        if s < 40 { 1 } else if s < 50 { 2 } else { 3 }
+        while 0 { 1 }
+        0 and 1/0
+        1 and 2
+        1 or 1/0
+        0 or \"\"
+        0 → 1/0
+        1 → 0
+        1 and 1/0",
+    );
+}
+
+#[test]
+fn functions_and_recursion() {
+    check(
+        "fact(n) = if n <= 1 { 1 } else { n fact(n - 1) }
+        fact(10)
+        fib(n) = if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+        fib(15)
+        even(n) = if n == 0 { 1 } else { odd(n - 1) }
+        odd(n) = if n == 0 { 0 } else { even(n - 1) }
+        even(7)
+        f(x, y) = x - y
+        f(5, 3)
+        f
+        f(1)
+        3(4)",
+    );
+}
+
+#[test]
+fn closures() {
+    check(
+        "adder(n) = x ↦ x + n
+        add5 = adder(5)
+        add5(3)
+        compose(f, g) = x ↦ f(g(x))
+        compose(add5, (x) ↦ 2x)(1)
+        counter = 0
+        bump() = counter = counter + 1
+        bump()
+        bump()
+        counter
+        make() = if 1 { total = 0; (x) => total = total + x }
+        acc = make()
+        acc(2)
+        acc(3)
+        total",
+    );
+}
+
+#[test]
+fn function_arithmetic() {
+    check(
+        "sq(x) = x²
+        g = 2 * sq
+        g(3)
+        h = sq / 2
+        h(4)
+        g / 4
+        simplify(x ↦ x x + 2x - x)",
+    );
+}
+
+#[test]
+fn vectors_and_matrices() {
+    check(
+        "v = ⟨1, 2, 3⟩
+        v + v
+        v ∙ v
+        v × ⟨0, 1, 0⟩
+        |⟨3, 4⟩|
+        A = [1, 2; 3, 4]
+        A * A
+        A * ⟨1, 1⟩
+        Aᵀ
+        det(A)
+        A⁻¹
+        [1, 2; 3]
+        A * v",
+    );
+}
+
+#[test]
+fn big_operators() {
+    check(
+        "∑(k = 1, 10, k²)
+        ∏(k = 1, 5, k)
+        ∑(i = 1, 3, ∑(j = 1, i, i j))
+        ∑(k = 1, 0, k)
+        ∏(k = 1, 0, k)
+        ∑(k = 1, 3, ⟨k, 1⟩)
+        n = 4
+        ∑(k = 1, n, k n)
+        ∑(k = 1, 2.5, k)
+        ∑ₖ₌₁ⁿ k² + ∏ₖ₌₁³ k
+        k",
+    );
+}
+
+#[test]
+fn big_integers() {
+    check(
+        "2147483647 + 1
+        -2147483648 - 1
+        n = 123456789012345678901234567890
+        n * n - n
+        n / 7
+        n % 7
+        2^100 > n
+        2^100 + 0.5
+        25!
+        gcd(2^64, 6^30)
+        lcm(2^40, 3^30)
+        det([2^40, 1; 1, 2^40])
+        9^9^9",
+    );
+}
+
+#[test]
+fn rationals() {
+    check(
+        "1 / 3
+        1/3 + 1/6
+        (2/3)^-2
+        ⌊-7/2⌋ + round(5/2)
+        numerator(6/8) / denominator(6/8)
+        float(1/3)
+        ⟨1, 2⟩ / 3
+        1/3 + 0.5
+        ∑(k = 1, 10, 1/(k*(k+1)))
+        0^-1",
+    );
+}
+
+#[test]
+fn complex_numbers() {
+    check(
+        "z = 1 + 2i
+        z * (3 + 4i)
+        z / (1 - i)
+        2z
+        z^2
+        e^(iπ)
+        i^i
+        sin(z) + cos(z)
+        exp(z) - cosh(z)
+        √(-4 + 0i)
+        z < 3
+        z % 2",
+    );
+}
+
+#[test]
+fn derivatives() {
+    check(
+        "f(x) = x³ + 2x
+        f'(2)
+        f''(2)
+        g(t) = sin(t) f(t)
+        g'(1)
+        deriv(x ↦ e^x)(1)
+        h = 2 * f
+        h'(1)
+        deriv(sin)
+        sin'(0)
+        cos''(π)
+        deriv(len)
+        z(x) = x!
+        z'(1)",
+    );
+}
+
+#[test]
+fn integrals() {
+    check(
+        "∫₀^π sin(x) dx
+        ∫(0, 1, x², x)
+        ∫(-∞, ∞, e^(-x²), x)^2
+        f(x) = 1/x
+        integrate(f, 1, e)
+        ∫₀¹ ⟨x, 1⟩ dx
+        ∫(1, ∞, 1/x, x)
+        ∫₀^i x dx
+        a = 2
+        ∫₀¹ a x dx
+        x",
+    );
+}
+
+#[test]
+fn solving() {
+    check(
+        "solve(x² - 2 = 0, x)
+        solve(x² = 2, x, 1)
+        f(x) = x³ - 6x² + 11x - 6
+        solve(f, 0, 4)
+        solve(sin, 3)
+        bisect(cos, 0, 3)
+        bisect(cos, 0, 1)
+        solve(e^x = 0, x, 0)
+        solve(x² = ⟨1, 2⟩, x, 1)",
+    );
+}
+
+#[test]
+fn polynomials() {
+    check(
+        "p = poly(1, 0, -2)
+        q = poly(x ↦ (x - 1)²(x + 2))
+        p q
+        q / poly(1, -1)
+        q % p
+        p(3)
+        p'
+        integrate(p, 0, 3)
+        gcd(q, poly(1, 0, -1))
+        roots(q)
+        p^-1
+        p / 0",
+    );
+}
+
+#[test]
+fn subscripts() {
+    check(
+        "x₁ = 3
+        x₁ + x₂
+        v = ⟨4, 5, 6⟩
+        n = 1
+        v₀ + vₙ₊₁
+        v₃
+        v₁ = 7
+        v₁
+        f₁(x) = x²
+        f₁(3)
+        g(x) = vₓ
+        g(2)
+        log₂(8)
+        log₂ = x ↦ 0
+        log₂(8)
+        m = [1, 2; 3, 4]
+        m₁",
+    );
+}
+
+#[test]
+fn strings() {
+    check(
+        r#"name = "world"
+        "hello, " + name
+        "{name} has {len(name)} letters"
+        "{1/2 + 1}\t{⟨1, 2⟩}"
+        name₀ + slice(name, 1, 3)
+        "or" ∈ name
+        name < "x"
+        name - 1
+        g(n) = "n = {n}"
+        g(3)"#,
+    );
+}
+
+#[test]
+fn ranges_and_loops() {
+    check(
+        "1..=10 step 2
+        t = 0
+        for k in 1..=10 { t = t + k² }
+        t
+        k
+        for x ∈ 0..=1 step 0.25 { t = t + x }
+        t
+        f(n) = ∑(k ∈ 1..=n, k)
+        f(100)
+        ∏(k ∈ ⟨1, 2, 3⟩, k + 1)
+        g(x) = ∑(k ∈ 0..3, xᵏ)
+        g'(2)
+        3 ∈ 1..n
+        for c in \"hi\" { c }
+        1..2 step 0
+        for x in 3 { x }",
+    );
+}
+
+#[test]
+fn break_continue_and_return() {
+    check(
+        "t = 0
+        for k in 1..∞ { if k > 6 { break }; if k % 3 == 0 { continue }; t = t + k }
+        t
+        i = 0
+        while i < 10 { i = i + 1; if i % 2 { continue }; if i > 6 { break }; i² }
+        f(n) = { for k in 1..n { if k² > n { return k } }; -1 }
+        f(50)
+        f(1)
+        g(x) = 1 + (if x > 0 { x } else { return \"negative\" })
+        g(2)
+        g(-2)
+        h(x) = { while 1 { return x + f(x) } }
+        h(10)
+        ⟨1, 2⟩ + (x ↦ { return ⟨x, x⟩ })(3)",
+    );
+}
+
+#[test]
+fn lists() {
+    check(
+        "xs = [5, \"two\", 3.5]
+        ys = [4, 1, 3, 2]
+        [xs[1], xs[0..2], len(xs), xs + ys]
+        sorted = sort(ys)
+        sorted[len(sorted) - 1]
+        map(ys, y ↦ y²)
+        filter(ys, y ↦ y > 1)
+        reduce(ys, (a, b) ↦ a b)
+        zip(ys, reverse(ys))
+        [sum(ys), prod(ys), min(ys), max(ys)]
+        t = 0; for y in push(ys, 10) { t = t + y }; t
+        [1, 2][5]
+        sort([1, [2]])",
+    );
+}
+
+#[test]
+fn runtime_errors() {
+    check(
+        "1 / 0
+        f(x) = x / 0
+        f(1)
+        g(x) = x + nothing
+        g(1)
+        f(1, 2)
+        f₂(1)
+        (x ↦ x)()
+        sqrt(1, 2)
+        ⟨1, 2⟩ + ⟨1, 2, 3⟩
+        13!",
+    );
+}
+
+#[test]
+fn scopes_of_whole_programs() {
+    check_program("f() = { y = 5 }\ny = 1\nf()\ny");
+    check_program("f() = { if 0 { t = 1 }\nt }\nt = 7\nf()");
+    check_program("y = 100\nf(x, y) = x + y\nf(1)");
+    check_program("g() = y\nf() = { y = 5 }\nf()\ng()");
+    check_program("f(x) = 2x\ng() = { f(x) = 3x\nf(1) }\n⟨g(), f(1)⟩");
+    check_program("n = 0\nadd() = { n = n + 1 }\nfor i in 1..=3 { add() }\nn");
+    check_program("k = 5\nf() = { for k in 1..3 { 0 }\nk }\nf()");
+    check(
+        "f() = { z = 5 }
+        z = 1
+        f()
+        z
+        g() = { if 0 { u = 1 }; u }
+        u = 7
+        g()",
+    );
+}
@@ -0,0 +1,63 @@
+//! Checks that runtime errors are returned, pointing at the code that failed,
+//! rather than ending the program.
+
+use std::ops::Range;
+
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::{Node, Parser, Spanned};
+
+fn parse(source: &str) -> Spanned<Node> {
+    let tokens = match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    };
+    match Parser::new(tokens).parse() {
+        Ok(ast) => ast,
+        Err(e) => panic!("failed to parse {:?}: {}", source, e.reason),
+    }
+}
+
+/// Runs `source`, which should fail, and returns the reason and range.
+fn error(source: &str) -> (String, Range<usize>) {
+    match Interpreter::default().run(parse(source)) {
+        Ok(value) => panic!("expected {:?} to fail, got {}", source, value),
+        Err(e) => (e.reason, e.range),
+    }
+}
+
+#[test]
+fn errors_span_the_code_that_failed() {
+    assert_eq!(error("1 + y"), ("y is not defined".to_string(), 4..5));
+    assert_eq!(
+        error("x = 2\nx + undefined"),
+        ("undefined is not defined".to_string(), 10..19)
+    );
+    assert_eq!(
+        error("3 + ⟨1⟩"),
+        ("Cannot apply + to 3 and ⟨1⟩".to_string(), 0..7)
+    );
+    assert_eq!(
+        error("abs(1, 2)"),
+        ("abs expects 1 argument, got 2".to_string(), 0..9)
+    );
+}
+
+#[test]
+fn errors_in_functions_span_the_body() {
+    assert_eq!(
+        error("f(x) = x / 0\n1 + f(2)"),
+        ("Cannot divide an integer by zero".to_string(), 7..12)
+    );
+}
+
+#[test]
+fn the_interpreter_carries_on_after_an_error() {
+    let mut interpreter = Interpreter::default();
+    assert!(interpreter.run(parse("x = 5")).is_ok());
+    assert!(interpreter.run(parse("x ⟨1, 2⟩ ⟨3⟩")).is_err());
+    match interpreter.run(parse("x + 1")) {
+        Ok(value) => assert_eq!(value.to_string(), "6"),
+        Err(e) => panic!("failed to run after an error: {}", e.reason),
+    }
+}
@@ -0,0 +1,86 @@
+//! Checks calling user functions and the scopes their bodies run in.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn calls_need_an_argument_for_each_parameter() {
+    assert_eq!(
+        eval("f(x) = x\nf(1, 2)"),
+        "invalid arguments: f expects 1 argument, got 2"
+    );
+    assert_eq!(
+        eval("y = 100\nf(x, y) = x + y\nf(1)"),
+        "invalid arguments: f expects 2 arguments, got 1"
+    );
+    assert_eq!(
+        eval("f(x) = x\nf₂(5)"),
+        "invalid arguments: f expects 1 argument, got 2"
+    );
+    assert_eq!(
+        eval("map([1, 2], (a, b) ↦ a + b)"),
+        "invalid arguments: λ expects 2 arguments, got 1"
+    );
+    assert_eq!(eval("f(x, n) = x^n\nf₂(5)"), "25");
+}
+
+#[test]
+fn functions_can_call_themselves_and_each_other() {
+    assert_eq!(
+        eval("fact(n) = if n <= 1 { 1 } else { n fact(n - 1) }\nfact(20)"),
+        "2432902008176640000"
+    );
+    assert_eq!(
+        eval(
+            "even(n) = if n < 1 { 1 } else { odd(n - 1) }
+odd(n) = if n < 1 { 0 } else { even(n - 1) }
+even(10) + odd(7)"
+        ),
+        "2"
+    );
+    assert_eq!(
+        eval("sq(x) = x²\nnorm(a, b) = sq(a) + sq(b)\nnorm(3, 4)"),
+        "25"
+    );
+    // Functions may call ones defined after them
+    assert_eq!(eval("f(x) = g(x) + 1\ng(x) = 2x\nf(3)"), "7");
+}
+
+#[test]
+fn bodies_see_globals_but_keep_their_own_variables() {
+    assert_eq!(eval("g = 9.81\nh(t) = g t² / 2\nh(2)"), "19.62");
+    assert_eq!(eval("x = 1\nf(x) = x + 1\nf(10) + x"), "12");
+    assert_eq!(
+        eval("f(a) = c = a * 2\nf(1)\nc"),
+        "undefined variable: c is not defined"
+    );
+    assert_eq!(eval("n = 0\nbump() = n = n + 1\nbump()\nbump()\nn"), "2");
+}
+
+#[test]
+fn lambdas_are_values() {
+    assert_eq!(eval("inc = x ↦ x + 1\ninc(4)"), "5");
+    assert_eq!(eval("mul = (x, y) => x y\nmul(3, 4)"), "12");
+    assert_eq!(eval("apply(f, x) = f(x)\napply(x ↦ 3x, 4)"), "12");
+    assert_eq!(eval("map([1, 2, 3], x ↦ x²)"), "[1, 4, 9]");
+    assert_eq!(eval("x ↦ x + 1"), "(x) ↦ (x + 1)");
+}
+
+#[test]
+fn closures_capture_where_they_were_made() {
+    assert_eq!(
+        eval("compose(f, g) = x ↦ f(g(x))\nh = compose(x ↦ x², x ↦ x + 1)\nh(2)"),
+        "9"
+    );
+    assert_eq!(
+        eval("adder(n) = x ↦ x + n\nadd5 = adder(5)\nadd2 = adder(2)\nadd5(1) + add2(10)"),
+        "18"
+    );
+    assert_eq!(
+        eval("counter() = { c = 0; () ↦ { c = c + 1 } }\nnext = counter()\nnext()\nnext()"),
+        "2"
+    );
+    // Variables are looked up when the closure runs, not when it's made
+    assert_eq!(eval("k = 2\nf = x ↦ k x\nk = 10\nf(1)"), "10");
+}
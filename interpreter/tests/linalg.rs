@@ -0,0 +1,68 @@
+//! Checks matrices and the linear algebra builtins.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn inverses_of_exact_matrices_are_exact() {
+    assert_eq!(eval("inv([1, 2; 3, 4])"), "⎡ -2     1⎤\n⎣3/2  -1/2⎦");
+    assert_eq!(eval("inv([1/2, 0; 0, 4])"), "⎡2    0⎤\n⎣0  1/4⎦");
+    assert_eq!(eval("[2, 1; 1, 1]^-2"), "⎡ 2  -3⎤\n⎣-3   5⎦");
+    assert_eq!(eval("[1, 2; 3, 4]^0"), "⎡1  0⎤\n⎣0  1⎦");
+    assert_eq!(eval("([1, 1; 1, 0]^90)[0][1]"), "2880067194370816120");
+    assert_eq!(
+        eval("[1, 1; 0, 1]^1000000000"),
+        "⎡1  1000000000⎤\n⎣0           1⎦"
+    );
+    assert_eq!(eval("m = [0, 1; 1, 1]\nm inv(m) == [1, 0; 0, 1]"), "true");
+    assert_eq!(
+        eval("inv([1, 2; 2, 4])"),
+        "singular matrix: Cannot invert a singular matrix"
+    );
+    assert_eq!(eval("inv([2.0, 0; 0, 4]) ≈ [0.5, 0; 0, 0.25]"), "true");
+}
+
+#[test]
+fn determinants_ranks_traces_and_inverses() {
+    assert_eq!(eval("det([1, 2; 3, 4])"), "-2");
+    assert_eq!(eval("det([2, 0, 1; 1, 3, 2; 1, 1, 1])"), "0");
+    assert_eq!(eval("det([0.5, 1; 2, 3])"), "-0.5");
+    assert_eq!(eval("rank([1, 2; 2, 4])"), "1");
+    assert_eq!(eval("rank([1, 0; 0, 1])"), "2");
+    assert_eq!(eval("trace([1, 2; 3, 4])"), "5");
+    assert_eq!(eval("inv([2.0, 0; 0, 4])"), "⎡0.5     0⎤\n⎣  0  0.25⎦");
+    assert_eq!(
+        eval("det([1, 2, 3; 4, 5, 6])"),
+        "type error: det expects a square matrix"
+    );
+    assert_eq!(
+        eval("trace([1, 2, 3; 4, 5, 6])"),
+        "type error: trace expects a square matrix"
+    );
+}
+
+#[test]
+fn products_and_transposes() {
+    assert_eq!(eval("[1, 2; 3, 4] [5, 6; 7, 8]"), "⎡19  22⎤\n⎣43  50⎦");
+    assert_eq!(eval("[1, 2; 3, 4] ⟨1, 1⟩"), "⟨3, 7⟩");
+    assert_eq!(
+        eval("transpose([1, 2, 3; 4, 5, 6])"),
+        "⎡1  4⎤\n⎢2  5⎥\n⎣3  6⎦"
+    );
+    assert_eq!(eval("[1, 2; 3, 4]ᵀ"), "⎡1  3⎤\n⎣2  4⎦");
+    assert_eq!(
+        eval("[1, 2; 3, 4] [1, 2, 3; 4, 5, 6; 7, 8, 9]"),
+        "type error: Cannot multiply a 2×2 matrix by a 3×3 matrix"
+    );
+}
+
+#[test]
+fn rows_are_aligned_and_must_match() {
+    assert_eq!(eval("[1, 10; 100, 1000]"), "⎡  1    10⎤\n⎣100  1000⎦");
+    assert_eq!(eval("[1, 2\n3, 4]"), "⎡1  2⎤\n⎣3  4⎦");
+    assert_eq!(
+        eval("[1, 2; 3]"),
+        "type error: All rows of a matrix must have the same length"
+    );
+}
@@ -0,0 +1,129 @@
+//! Checks lists and the builtins for working with them.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn lists_hold_values_of_any_types() {
+    assert_eq!(eval("[1, \"two\", 3.5, ⟨4⟩]"), "[1, \"two\", 3.5, ⟨4⟩]");
+    assert_eq!(eval("[]"), "[]");
+    assert_eq!(eval("[1, [2, 3]] == [1, [2, 3]]"), "true");
+    assert_eq!(eval("[1, 2] + [3]"), "[1, 2, 3]");
+    assert_eq!(eval("2 ∈ [1, 2]"), "true");
+    assert_eq!(
+        eval("[1, 2] * 2"),
+        "type error: Cannot apply * to [1, 2] and 2"
+    );
+}
+
+#[test]
+fn lists_can_span_lines() {
+    assert_eq!(eval("xs = [\n  1, 2, 3\n]\nxs"), "[1, 2, 3]");
+    assert_eq!(eval("[1, 2;]"), "[1, 2]");
+    assert_eq!(eval("[\n  1, 2\n  3, 4\n]"), "⎡1  2⎤\n⎣3  4⎦");
+    assert_eq!(eval("[1; 2]ᵀ == [1, 2]"), "false");
+}
+
+#[test]
+fn indexing_takes_elements_and_ranges_slice() {
+    assert_eq!(eval("xs = [10, 20, 30, 40]\nxs[1]"), "20");
+    assert_eq!(eval("xs = [10, 20, 30, 40]\nxs[1..3]"), "[20, 30]");
+    assert_eq!(
+        eval("xs = [10, 20, 30, 40]\nxs[3..=0 step -1]"),
+        "[40, 30, 20, 10]"
+    );
+    assert_eq!(eval("⟨1, 2, 3⟩[0..2]"), "⟨1, 2⟩");
+    assert_eq!(eval("\"spectre\"[1..4]"), "pec");
+    assert_eq!(eval("[1, 2; 3, 4; 5, 6][1..3]"), "⎡3  4⎤\n⎣5  6⎦");
+    assert_eq!(
+        eval("[1, 2][2]"),
+        "index out of range: Index 2 is out of range for length 2"
+    );
+    assert_eq!(
+        eval("[1, 2][1..∞]"),
+        "index out of range: Index 2 is out of range for length 2"
+    );
+}
+
+#[test]
+fn builtins_transform_lists() {
+    assert_eq!(eval("len([1, 2, 3])"), "3");
+    assert_eq!(eval("len(1..5)"), "4");
+    assert_eq!(eval("len(1..=5)"), "5");
+    assert_eq!(eval("len(10..1 step -3)"), "3");
+    assert_eq!(eval("len(0..=1 step 0.1)"), "11");
+    assert_eq!(eval("len(5..1)"), "0");
+    assert_eq!(eval("len(0..3000000000)"), "3000000000");
+    assert_eq!(eval("len(1..∞)"), "type error: 1..inf is endless");
+    assert_eq!(eval("len([1, 2, 3; 4, 5, 6])"), "2");
+    assert_eq!(eval("slice([1, 2, 3], 1)"), "[2, 3]");
+    assert_eq!(
+        eval("xs = [1]\nys = push(xs, 2)\n[xs, ys]"),
+        "[[1], [1, 2]]"
+    );
+    assert_eq!(eval("map([1, 2, 3], x ↦ x²)"), "[1, 4, 9]");
+    assert_eq!(eval("map(⟨1, 2⟩, x ↦ 2x)"), "⟨2, 4⟩");
+    assert_eq!(eval("filter(1..10, n ↦ n % 3 == 0)"), "[3, 6, 9]");
+    assert_eq!(eval("reduce([1, 2, 3, 4], (a, b) ↦ 10a + b)"), "1234");
+    assert_eq!(eval("reduce([], (a, b) ↦ a + b, 0)"), "0");
+    assert_eq!(eval("reverse([1, \"a\", 2])"), "[2, \"a\", 1]");
+    assert_eq!(eval("reverse(\"abc\")"), "cba");
+    assert_eq!(eval("zip([1, 2, 3], \"ab\")"), "[[1, \"a\"], [2, \"b\"]]");
+}
+
+#[test]
+fn sort_orders_numbers_and_strings() {
+    assert_eq!(eval("sort([3, 1/2, 2.5, -1, 2])"), "[-1, 1/2, 2, 2.5, 3]");
+    assert_eq!(
+        eval("sort([\"pear\", \"apple\", \"fig\"])"),
+        "[\"apple\", \"fig\", \"pear\"]"
+    );
+    assert_eq!(eval("sort([2, 1, 2, 1])"), "[1, 1, 2, 2]");
+    assert_eq!(
+        eval("sort([1, \"a\"])"),
+        "type error: Cannot apply < to \"a\" and 1"
+    );
+}
+
+#[test]
+fn totals_and_extremes_take_lists() {
+    assert_eq!(eval("sum([1, 2, 3])"), "6");
+    assert_eq!(eval("sum(1..=100)"), "5050");
+    assert_eq!(eval("sum(1..=1000000000)"), "500000000500000000");
+    assert_eq!(eval("sum(10..0 step -3)"), "22");
+    assert_eq!(eval("sum(1..1)"), "0");
+    assert_eq!(eval("sum(0..1 step 1/4)"), "3/2");
+    assert_eq!(eval("sum([⟨1, 2⟩, ⟨3, 4⟩])"), "⟨4, 6⟩");
+    assert_eq!(eval("prod([1/2, 4])"), "2");
+    assert_eq!(eval("⟨sum([]), prod([])⟩"), "⟨0, 1⟩");
+    assert_eq!(eval("min(3, 1, 2)"), "1");
+    assert_eq!(eval("max([3, 7.5, 2])"), "7.5");
+    assert_eq!(eval("min(1/3, 0.3)"), "0.3");
+    assert_eq!(eval("max(0..10 step 3)"), "9");
+    assert_eq!(
+        eval("min([])"),
+        "invalid arguments: min expects at least 1 value"
+    );
+}
+
+#[test]
+fn endless_ranges_cannot_be_used_up() {
+    for call in [
+        "map(1..∞, x ↦ x)",
+        "filter(1..∞, x ↦ true)",
+        "reduce(1..∞, (a, b) ↦ a + b)",
+        "sort(1..∞)",
+        "reverse(1..∞)",
+        "sum(1..∞)",
+        "prod(1..∞)",
+        "max(1..∞)",
+    ] {
+        assert_eq!(eval(call), "type error: 1..inf is endless", "{}", call);
+    }
+    assert_eq!(
+        eval("zip(1..∞, 0..∞)"),
+        "type error: Cannot zip only endless ranges"
+    );
+    assert_eq!(eval("zip(1..∞, \"ab\")"), "[[1, \"a\"], [2, \"b\"]]");
+}
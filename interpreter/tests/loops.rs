@@ -0,0 +1,119 @@
+//! Checks ranges and the loops and big operators that go over them.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn for_loops_go_over_ranges_and_collections() {
+    assert_eq!(eval("t = 0\nfor k in 1..5 { t = t + k }\nt"), "10");
+    assert_eq!(eval("t = 0\nfor k ∈ 1..=5 { t = t + k }\nt"), "15");
+    assert_eq!(
+        eval("t = 0\nfor k in 10..0 step -3 { t = 10t + k }\nt"),
+        "10741"
+    );
+    assert_eq!(eval("t = 1\nfor x in ⟨2, 3, 4⟩ { t = t x }\nt"), "24");
+    assert_eq!(eval("s = \"\"\nfor c in \"abc\" { s = c + s }\ns"), "cba");
+    assert_eq!(eval("for x in 1..1 { x }"), "0");
+}
+
+#[test]
+fn loop_variables_are_scoped_to_the_body() {
+    assert_eq!(
+        eval("for k in 1..3 { k }\nk"),
+        "undefined variable: k is not defined"
+    );
+    assert_eq!(eval("k = 7\nfor k in 1..3 { k }\nk"), "7");
+}
+
+#[test]
+fn integer_ranges_reach_past_i32() {
+    assert_eq!(
+        eval("r = 2147483646..=2147483648\n⟨r₀, r₁, r₂⟩"),
+        "⟨2147483646, 2147483647, 2147483648⟩"
+    );
+    assert_eq!(eval("len(0..=4000000000 step 2000000000)"), "3");
+    assert_eq!(eval("2147483648 ∈ 0..∞"), "true");
+    assert_eq!(eval("-2147483648 ∈ 0..-∞ step -1"), "true");
+}
+
+#[test]
+fn float_steps_do_not_drift() {
+    assert_eq!(
+        eval("n = 0\nfor x in 0..=1 step 0.1 { n = n + 1 }\nn"),
+        "11"
+    );
+    assert_eq!(eval("r = 0..=1 step 0.1\nr₁₀"), "1");
+    assert_eq!(eval("0.5 ∈ 0..1 step 0.1"), "true");
+}
+
+#[test]
+fn ranges_are_lazy() {
+    assert_eq!(eval("10^6 ∈ 1..∞"), "true");
+    assert_eq!(eval("1/2 ∈ 1..∞"), "false");
+    assert_eq!(eval("r = 1..∞\nr₉₉₉₉₉"), "100000");
+    assert_eq!(eval("0..1 step 1/4"), "0..1 step 1/4");
+}
+
+#[test]
+fn big_operators_with_bounds() {
+    assert_eq!(eval("∑(k = 1, 10, k²)"), "385");
+    assert_eq!(eval("∏(k = 1, 5, k)"), "120");
+    assert_eq!(eval("n = 4\n∑(k = 1, n, k)"), "10");
+    assert_eq!(eval("∑(k = 1, 3, 1/k)"), "11/6");
+    assert_eq!(eval("∑(i = 1, 3, ∑(j = 1, i, j))"), "10");
+    assert_eq!(eval("f(n) = ∏(k = 1, n, k)\nf(6)"), "720");
+    assert_eq!(eval("⟨∑(k = 1, 0, k), ∏(k = 1, 0, k)⟩"), "⟨0, 1⟩");
+    assert_eq!(eval("∑(k = 1, 2.5, k)"), "3");
+    assert_eq!(eval("∑(k = 2^40, 2^40 + 2, k)"), "3298534883331");
+    assert_eq!(eval("∑(k = 2^31 - 1, 2^31, k)"), "4294967295");
+    assert_eq!(eval("∑(k = 1, ∞, k)"), "type error: 1..=inf is endless");
+    assert_eq!(eval("∏(k ∈ 1..∞, k)"), "type error: 1..inf is endless");
+}
+
+#[test]
+fn big_operator_indices_are_scoped_to_the_body() {
+    assert_eq!(eval("k = 7\n∑(k = 1, 3, k)\nk"), "7");
+    assert_eq!(
+        eval("∑(k = 1, 3, k)\nk"),
+        "undefined variable: k is not defined"
+    );
+}
+
+#[test]
+fn big_operators_go_over_ranges_and_collections() {
+    assert_eq!(eval("∑(k ∈ 1..=100, k)"), "5050");
+    assert_eq!(eval("∏(k ∈ 1..=5, k)"), "120");
+    assert_eq!(eval("∑(k ∈ 0..1 step 1/4, k)"), "3/2");
+    assert_eq!(eval("∑(v ∈ [1, 2; 3, 4], v)"), "⟨4, 6⟩");
+    assert_eq!(eval("∑(k ∈ 1..1, k)"), "0");
+}
+
+#[test]
+fn big_operators_can_be_bounded_by_scripts() {
+    assert_eq!(eval("∑ₖ₌₁¹⁰ k²"), "385");
+    assert_eq!(eval("∏ₖ₌₁⁵ k"), "120");
+    assert_eq!(eval("n = 4\n∑ₖ₌₁ⁿ k + 1"), "11");
+    assert_eq!(eval("∑ₖ₌₁₀¹² k"), "33");
+    assert_eq!(eval("∑ₖ₌₁^(2 + 1) 1/k"), "11/6");
+    assert_eq!(
+        eval("∑ₖ₌₁⁵⁼ k"),
+        "expected token: expected the end of the upper bound"
+    );
+}
+
+#[test]
+fn bad_ranges_are_errors() {
+    assert_eq!(
+        eval("1..2 step 0"),
+        "invalid range: The step of a range can't be 0"
+    );
+    assert_eq!(
+        eval("1..i"),
+        "type error: Ranges must be of real numbers, got 0 + 1i"
+    );
+    assert_eq!(
+        eval("for x in 5 { x }"),
+        "type error: Cannot iterate over 5"
+    );
+}
@@ -0,0 +1,171 @@
+//! Checks that arithmetic produces exactly the right values.
+
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn integers_promote_instead_of_overflowing() {
+    assert_eq!(eval("2147483647 + 1"), "2147483648");
+    assert_eq!(eval("-2147483648 - 1"), "-2147483649");
+    assert_eq!(eval("65536 * 65536"), "4294967296");
+    assert_eq!(eval("2^100"), "1267650600228229401496703205376");
+    assert_eq!(eval("25!"), "15511210043330985984000000");
+    assert_eq!(eval("-(-2147483648)"), "2147483648");
+    assert_eq!(eval("|-2147483648|"), "2147483648");
+}
+
+#[test]
+fn big_integers_shrink_back_when_they_fit() {
+    assert_eq!(eval("2^100 / 2^98"), "4");
+    assert_eq!(eval("2^100 - 2^100 == 0"), "true");
+    assert_eq!(eval("2^100 % 1000"), "376");
+}
+
+#[test]
+fn literals_of_any_length() {
+    assert_eq!(
+        eval("123456789012345678901234567890 + 1"),
+        "123456789012345678901234567891"
+    );
+}
+
+#[test]
+fn gcd_and_lcm_of_big_integers() {
+    assert_eq!(eval("gcd(2^100, 6^50)"), "1125899906842624");
+    assert_eq!(eval("lcm(2^40, 3^30)"), "226379693794030958489370624");
+}
+
+#[test]
+fn exact_determinants() {
+    assert_eq!(eval("det([2^40, 1; 1, 2^40])"), "1208925819614629174706175");
+}
+
+#[test]
+fn absurdly_large_results_are_errors() {
+    assert_eq!(
+        eval("9^9^9"),
+        "overflow: The result is too large to compute exactly"
+    );
+}
+
+#[test]
+fn integer_division_is_exact() {
+    assert_eq!(eval("1 / 3"), "1/3");
+    assert_eq!(eval("6 / 3"), "2");
+    assert_eq!(eval("-2 / 4"), "-1/2");
+    assert_eq!(eval("2 / -4"), "-1/2");
+    assert_eq!(eval("1 / 2^100"), "1/1267650600228229401496703205376");
+}
+
+#[test]
+fn rationals_stay_exact() {
+    assert_eq!(eval("1/3 + 1/6"), "1/2");
+    assert_eq!(eval("1/3 * 3"), "1");
+    assert_eq!(eval("(2/3)^2"), "4/9");
+    assert_eq!(eval("(2/3)^-2"), "9/4");
+    assert_eq!(eval("2^-3"), "1/8");
+    assert_eq!(eval("(7/2) % (1/3)"), "1/6");
+    assert_eq!(eval("∑(k = 1, 10, 1/(k*(k+1)))"), "10/11");
+    assert_eq!(eval("1/3 == 2/6"), "true");
+    assert_eq!(eval("1/3 < 1/2"), "true");
+}
+
+#[test]
+fn rationals_round_to_integers() {
+    assert_eq!(eval("⌊-7/2⌋"), "-4");
+    assert_eq!(eval("⌈-7/2⌉"), "-3");
+    assert_eq!(eval("round(-7/2)"), "-4");
+    assert_eq!(eval("trunc(-7/2)"), "-3");
+}
+
+#[test]
+fn rational_builtins() {
+    assert_eq!(eval("numerator(6/8)"), "3");
+    assert_eq!(eval("denominator(6/8)"), "4");
+    assert_eq!(eval("denominator(5)"), "1");
+    assert_eq!(eval("float(1/4)"), "0.25");
+    assert_eq!(eval("1/4 + 0.5"), "0.75");
+}
+
+#[test]
+fn zero_denominators_are_errors() {
+    assert_eq!(
+        eval("0^-1"),
+        "division by zero: Cannot divide an integer by zero"
+    );
+}
+
+#[test]
+fn complex_arithmetic() {
+    assert_eq!(eval("i * i"), "-1 + 0i");
+    assert_eq!(eval("(1 + 2i) * (3 + 4i)"), "-5 + 10i");
+    assert_eq!(eval("(1 + i) * 2"), "2 + 2i");
+    assert_eq!(eval("2 / (1 + i)"), "1 - 1i");
+    assert_eq!(eval("(1 + i)^2"), "0 + 2i");
+    assert_eq!(eval("√(-4 + 0i)"), "0 + 2i");
+    assert_eq!(eval("1/2 + i"), "0.5 + 1i");
+    assert_eq!(eval("|3 + 4i|"), "5");
+}
+
+#[test]
+fn eulers_identity() {
+    assert_eq!(eval("|e^(iπ) + 1| < 0.000000000000001"), "true");
+}
+
+#[test]
+fn approximate_equality_uses_the_tolerance() {
+    assert_eq!(eval("0.1 + 0.2 ≈ 0.3"), "true");
+    assert_eq!(eval("1 ≈ 1.001"), "false");
+    // Large values are compared relative to their size
+    assert_eq!(eval("10^20 ≈ 10^20 + 1000"), "true");
+    assert_eq!(eval("2^0.5 ≈ √2 + 0i"), "true");
+
+    let mut interpreter = Interpreter {
+        tolerance: 0.01,
+        ..Default::default()
+    };
+    let tokens = Lexer::new("1 ≈ 1.001".to_string())
+        .lex()
+        .unwrap_or_else(|e| panic!("failed to lex: {}", e.reason));
+    let ast = Parser::new(tokens)
+        .parse()
+        .unwrap_or_else(|e| panic!("failed to parse: {}", e.reason));
+    assert!(matches!(interpreter.run(ast), Ok(value) if value.to_string() == "true"));
+}
+
+#[test]
+fn numbers_are_equal_by_value_whatever_their_types() {
+    assert_eq!(eval("1 == 1.0"), "true");
+    assert_eq!(eval("0.5 * 2 == 1"), "true");
+    assert_eq!(eval("1/2 == 0.5"), "true");
+    assert_eq!(eval("2^100 == 2.0^100"), "true");
+    assert_eq!(eval("1 ≠ 1.0"), "false");
+    assert_eq!(eval("2 ∈ [2.0]"), "true");
+    assert_eq!(eval("⟨1, 2⟩ == ⟨1.0, 2⟩"), "true");
+    assert_eq!(eval("[1, [2]] == [1.0, [2.0]]"), "true");
+    assert_eq!(eval("1/3 == 0.3333"), "false");
+}
+
+#[test]
+fn factorials_are_only_of_natural_numbers() {
+    assert_eq!(eval("0!"), "1");
+    assert_eq!(eval("5.0!"), "120");
+    assert_eq!(
+        eval("(-1)!"),
+        "type error: Cannot take the factorial of -1, which isn't a natural number"
+    );
+    assert_eq!(
+        eval("2.5!"),
+        "type error: Cannot take the factorial of 2.5, which isn't a natural number"
+    );
+    assert_eq!(
+        eval("(1/2)!"),
+        "type error: Cannot take the factorial of 1/2, which isn't a natural number"
+    );
+    assert_eq!(eval("(-2^100)!").split(':').next(), Some("type error"));
+}
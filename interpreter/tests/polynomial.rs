@@ -0,0 +1,97 @@
+//! Checks polynomial arithmetic, which should be exact for exact
+//! coefficients, and root finding.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn construction_and_display() {
+    assert_eq!(eval("poly(1, 0, -2)"), "x² - 2");
+    assert_eq!(eval("poly(x ↦ (x - 1)²(x + 2))"), "x³ - 3x + 2");
+    assert_eq!(eval("poly(-1/2, 3, 1.5)"), "-(1/2)x² + 3x + 1.5");
+    assert_eq!(eval("poly(1 + 2i, 3)"), "(1 + 2i)x + 3");
+    assert_eq!(eval("poly(x ↦ x^12)"), "x¹²");
+    assert_eq!(eval("poly(0, 0)"), "0");
+    assert_eq!(
+        eval("poly(⟨1, 2⟩)"),
+        "type error: Coefficients must be numbers, got ⟨1, 2⟩"
+    );
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(eval("p = poly(1, 0, -2); p + 1"), "x² - 1");
+    assert_eq!(
+        eval("p = poly(1, 0, -2); p * poly(1, 1)"),
+        "x³ + x² - 2x - 2"
+    );
+    assert_eq!(eval("p = poly(1, 0, -2); p^3"), "x⁶ - 6x⁴ + 12x² - 8");
+    assert_eq!(eval("p = poly(1, 0, -2); p / 2"), "(1/2)x² - 1");
+    assert_eq!(eval("p = poly(1, 0, -2); -p"), "-x² + 2");
+    assert_eq!(eval("p = poly(1, 0, -2); p(3)"), "7");
+    assert_eq!(eval("p = poly(1, 0, -2); p(poly(1, 1))"), "x² + 2x - 1");
+    assert_eq!(eval("poly(1, 0, -2) == poly(1, 0, -1) - 1"), "true");
+    assert_eq!(
+        eval("poly(1, 0) ^ -1"),
+        "type error: Polynomials can only be raised to natural powers, not -1"
+    );
+}
+
+#[test]
+fn long_division() {
+    assert_eq!(eval("poly(1, 0, -3, 2) / poly(1, -1)"), "x² + x - 2");
+    assert_eq!(eval("poly(1, 0, -3, 2) % poly(1, 0, 1)"), "-4x + 2");
+    assert_eq!(
+        eval("divrem(poly(2, 0, 1), poly(3, 1))"),
+        "[(2/3)x - 2/9, 11/9]"
+    );
+    assert_eq!(eval("poly(2, 0, 1) % poly(3, 1)"), "11/9");
+    assert_eq!(
+        eval("poly(2, 0, 1) / poly(3, 1)"),
+        "not divisible: 2x² + 1 leaves a remainder of 11/9 when divided by 3x + 1; divrem gives both"
+    );
+    assert_eq!(
+        eval("2 / poly(1, 0)"),
+        "not divisible: 2 leaves a remainder of 2 when divided by x; divrem gives both"
+    );
+    assert_eq!(eval("poly(1.5, 3) / poly(0.5, 1)"), "3");
+    assert_eq!(
+        eval("poly(1, 0) / 0"),
+        "division by zero: Cannot divide a polynomial by zero"
+    );
+    assert_eq!(eval("gcd(poly(1, 0, -3, 2), poly(2, 0, -2))"), "x - 1");
+    assert_eq!(eval("gcd(poly(1, 0, 1), poly(1, 1))"), "1");
+}
+
+#[test]
+fn calculus() {
+    assert_eq!(eval("poly(1, 0, -2)'"), "2x");
+    assert_eq!(eval("deriv(poly(1, 2, 3, 4))"), "3x² + 4x + 3");
+    assert_eq!(eval("integrate(poly(1, 0, -2))"), "(1/3)x³ - 2x");
+    assert_eq!(eval("integrate(poly(1, 0, -2), 0, 3)"), "3");
+    assert_eq!(eval("integrate(poly(1, 0), 0, 1/2)"), "1/8");
+}
+
+#[test]
+fn roots() {
+    assert_eq!(
+        eval("roots(poly(1, 0, -2))"),
+        "⟨-1.414213562373095 + 0i, 1.4142135623730951 + 0i⟩"
+    );
+    assert_eq!(eval("roots(poly(1, 0, 1))"), "⟨0 - 1i, 0 + 1i⟩");
+    // Repeated roots of exact polynomials are found exactly
+    assert_eq!(
+        eval("roots(poly(x ↦ (x - 1)³(x + 2)))"),
+        "⟨-2 + 0i, 1 + 0i, 1 + 0i, 1 + 0i⟩"
+    );
+    assert_eq!(eval("roots(poly(5))"), "⟨⟩");
+    assert_eq!(
+        eval("roots(poly(0))"),
+        "invalid arguments: Every number is a root of the zero polynomial"
+    );
+    assert_eq!(
+        eval("roots(poly(1, 0, 0, 0, -1))"),
+        "⟨-1 + 0i, 0 - 1i, 0 + 1i, 1 + 0i⟩"
+    );
+}
@@ -0,0 +1,57 @@
+//! Checks that simplified functions come out in their expected forms.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn identities() {
+    assert_eq!(eval("simplify(x ↦ x*1 + 0)"), "(x) ↦ x");
+    assert_eq!(eval("simplify(x ↦ x^1 - 0)"), "(x) ↦ x");
+    assert_eq!(eval("simplify(x ↦ 0 * sin(x) + x^0)"), "(x) ↦ 1");
+    assert_eq!(eval("simplify(x ↦ -(-x))"), "(x) ↦ x");
+    assert_eq!(eval("simplify(x ↦ 1^x)"), "(x) ↦ 1");
+    assert_eq!(eval("simplify(x ↦ x ln(e))"), "(x) ↦ x");
+    assert_eq!(
+        eval("simplify((x, b) ↦ log(10) + log₂(2) + log(b, b))"),
+        "(x, b) ↦ 3"
+    );
+    assert_eq!(eval("deriv(x ↦ eˣ)"), "λ′(x) = (e ^ x)");
+}
+
+#[test]
+fn constant_folding() {
+    assert_eq!(eval("simplify(x ↦ (2 + 3) x)"), "(x) ↦ (5 * x)");
+    assert_eq!(eval("simplify(x ↦ x + 1/3 + 1/6)"), "(x) ↦ (x + (1 / 2))");
+    assert_eq!(eval("simplify(x ↦ 1.5 + 1)"), "(x) ↦ 2.5");
+    // Irrational results are left as they are
+    assert_eq!(eval("simplify(x ↦ √2 x)"), "(x) ↦ ((√2) * x)");
+}
+
+#[test]
+fn like_terms() {
+    assert_eq!(eval("simplify(x ↦ 2x + 3x)"), "(x) ↦ (5 * x)");
+    assert_eq!(eval("simplify(x ↦ x - x)"), "(x) ↦ 0");
+    assert_eq!(eval("simplify(x ↦ x/2 + x/2)"), "(x) ↦ x");
+    assert_eq!(
+        eval("simplify((x, y) ↦ x y + y x - x)"),
+        "(x, y) ↦ (((2 * x) * y) - x)"
+    );
+}
+
+#[test]
+fn powers() {
+    assert_eq!(eval("simplify(x ↦ x x x)"), "(x) ↦ (x ^ 3)");
+    assert_eq!(eval("simplify(x ↦ x³ / x)"), "(x) ↦ (x ^ 2)");
+    assert_eq!(eval("simplify(x ↦ (x²)³)"), "(x) ↦ (x ^ 6)");
+    assert_eq!(eval("simplify(x ↦ 3 / x²)"), "(x) ↦ (3 / (x ^ 2))");
+    assert_eq!(eval("simplify((x, n) ↦ xⁿ x)"), "(x, n) ↦ (x ^ (n + 1))");
+}
+
+#[test]
+fn functions_print_simplified() {
+    assert_eq!(eval("f(x) = x + x; f"), "f(x) = (2 * x)");
+    assert_eq!(eval("f(x) = x³; 2 * f / 4"), "f(x) = ((x ^ 3) / 2)");
+    assert_eq!(eval("f(x) = x³ + 2x; f′"), "f′(x) = ((3 * (x ^ 2)) + 2)");
+    assert_eq!(eval("simplify(3)"), "3");
+}
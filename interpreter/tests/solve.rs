@@ -0,0 +1,49 @@
+//! Checks the root finders against roots known in closed form.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn equations() {
+    assert_eq!(
+        eval("solve(x² - 2 = 0, x)"),
+        "⟨-1.414213562373095, 1.414213562373095⟩"
+    );
+    assert_eq!(eval("solve(x² = 2, x, 1)"), "1.4142135623730951");
+    assert_eq!(eval("solve(ln(x) = 1, x, 1, 10)"), "⟨2.7182818284590455⟩");
+}
+
+#[test]
+fn all_roots_in_an_interval() {
+    assert_eq!(eval("f(x) = x³ - 6x² + 11x - 6; solve(f)"), "⟨1, 2, 3⟩");
+    assert_eq!(
+        eval("solve(cos, 0, 10)"),
+        "⟨1.5707963267948966, 4.71238898038469, 7.853981633974483⟩"
+    );
+    // Poles aren't roots, even though the sign changes across them
+    assert_eq!(eval("solve(x ↦ 1/x)"), "⟨⟩");
+}
+
+#[test]
+fn newton_and_secant() {
+    assert_eq!(
+        eval("f(x) = x³ - 6x² + 11x - 6; |solve(f, 2.4) - 2| < 0.000000001"),
+        "true"
+    );
+    assert_eq!(eval("solve(sin, 3)"), "3.141592653589793");
+    assert_eq!(
+        eval("solve(x² + 1 = 0, x, 0)"),
+        "not convergent: Newton's method didn't converge from 0"
+    );
+}
+
+#[test]
+fn bisection() {
+    assert_eq!(eval("bisect(cos, 0, 3)"), "1.5707963267948966");
+    assert_eq!(
+        eval("bisect(cos, 0, 1)"),
+        "invalid arguments: The function must change sign between 0 and 1"
+    );
+    assert_eq!(eval("solve(3)"), "type error: Cannot solve 3");
+}
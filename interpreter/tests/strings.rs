@@ -0,0 +1,74 @@
+//! Checks string literals, interpolation and the functions on strings.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn interpolation_writes_values_as_printed() {
+    assert_eq!(
+        eval(
+            r#"x = 3
+"x = {x}""#
+        ),
+        "x = 3"
+    );
+    assert_eq!(eval(r#""{1/3} and {⟨1, 2⟩}""#), "1/3 and ⟨1, 2⟩");
+    assert_eq!(
+        eval(
+            r#"f(x) = x²
+"f(4) = {f(4)}""#
+        ),
+        "f(4) = 16"
+    );
+    assert_eq!(eval(r#""\{not interpolated\}""#), "{not interpolated}");
+    assert_eq!(eval(r#""a{"b"}c""#), "abc");
+    assert_eq!(eval(r#""<{"{1 + 1}\}" + "!"}>""#), "<2}!>");
+}
+
+#[test]
+fn interpolations_must_be_whole_expressions() {
+    for source in [r#""{1 + }""#, r#""{3 *}""#] {
+        assert!(
+            eval(source).starts_with("expected token: expected int"),
+            "{} evaluated to {}",
+            source,
+            eval(source)
+        );
+    }
+}
+
+#[test]
+fn concatenation_and_comparison() {
+    assert_eq!(eval(r#""spec" + "tre""#), "spectre");
+    assert_eq!(eval(r#""a" + str(1)"#), "a1");
+    assert_eq!(eval(r#""abc" == "abc""#), "true");
+    assert_eq!(eval(r#""abc" == 3"#), "false");
+    assert_eq!(eval(r#""abc" < "abd""#), "true");
+    assert_eq!(eval(r#""ell" ∈ "hello""#), "true");
+    assert_eq!(
+        eval(r#""a" + 1"#),
+        "type error: Cannot apply + to \"a\" and 1"
+    );
+    assert_eq!(eval(r#"⌊"a"⌋"#), "type error: Cannot apply ⌊⌋ to a");
+}
+
+#[test]
+fn length_indexing_and_slicing() {
+    assert_eq!(eval(r#"len("héllo")"#), "5");
+    assert_eq!(eval(r#"len(⟨1, 2, 3⟩)"#), "3");
+    assert_eq!(
+        eval(
+            r#"s = "héllo"
+s₁"#
+        ),
+        "é"
+    );
+    assert_eq!(eval(r#"slice("héllo", 1, 3)"#), "él");
+    assert_eq!(eval(r#"slice("héllo", 2)"#), "llo");
+    assert_eq!(eval(r#"slice(⟨1, 2, 3⟩, 0, 2)"#), "⟨1, 2⟩");
+    assert_eq!(
+        eval(r#"slice("abc", 2, 5)"#),
+        "index out of range: Cannot slice from 2 to 5 with length 3"
+    );
+}
@@ -0,0 +1,49 @@
+//! Checks that subscripts name variables of their own, index vectors and
+//! matrices, and pass bases to functions like `log`.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn subscripted_names_are_variables() {
+    assert_eq!(eval("x₁ = 3\nx₂ = 4\nx₁ x₂"), "12");
+    assert_eq!(eval("a₂₃ = 5\na₂₃ + 1"), "6");
+    assert_eq!(eval("f₁(x) = x + 1\nf₁(2)"), "3");
+    assert_eq!(eval("f(x₁, x₂) = x₁ - x₂\nf(5, 3)"), "2");
+}
+
+#[test]
+fn subscripts_index_from_zero() {
+    assert_eq!(eval("v = ⟨4, 5, 6⟩\nv₀ + v₂"), "10");
+    assert_eq!(eval("v = ⟨4, 5, 6⟩\nn = 1\nvₙ vₙ₊₁"), "30");
+    assert_eq!(eval("m = [1, 2; 3, 4]\nm₁"), "⟨3, 4⟩");
+    assert_eq!(eval("v = ⟨4, 5, 6⟩\nv₁ = 0\nv₁"), "0");
+}
+
+#[test]
+fn bad_indices_are_errors() {
+    assert_eq!(
+        eval("v = ⟨1, 2⟩\nv₂"),
+        "index out of range: Index 2 is out of range for length 2"
+    );
+    assert_eq!(eval("n = 3\nnₙ"), "type error: Cannot index 3");
+    assert_eq!(
+        eval("v = ⟨1, 2⟩\nv₁₌₂"),
+        "expected token: expected the end of the subscript"
+    );
+    assert_eq!(
+        eval("v = ⟨1, 2⟩\nk = 1/2\nvₖ"),
+        "type error: Indices must be integers, got 1/2"
+    );
+}
+
+#[test]
+fn subscripts_of_functions_are_their_last_argument() {
+    assert_eq!(eval("log₂(8)"), "3");
+    assert_eq!(eval("log₁₀(1000)"), "3");
+    assert_eq!(eval("log(100)"), "2");
+    assert_eq!(eval("log₃(81)"), "4");
+    assert_eq!(eval("log₂ = x ↦ 0\nlog₂(8)"), "0");
+    assert_eq!(eval("p(x, n) = xⁿ\np₃(2)"), "8");
+}
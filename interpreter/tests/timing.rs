@@ -0,0 +1,52 @@
+//! Times loops through the bytecode VM and the tree-walker. These are ignored
+//! by default since they only mean something in release builds:
+//!
+//! ```text
+//! cargo test --release -p interpreter --test timing -- --ignored --nocapture
+//! ```
+
+use std::time::Instant;
+
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+
+/// Runs `source` through both engines, printing how long each took.
+fn time(source: &str) {
+    for walk in [false, true] {
+        let Ok(ast) = Lexer::new(source.to_string())
+            .lex()
+            .and_then(|tokens| Parser::new(tokens).parse())
+        else {
+            panic!("failed to parse {:?}", source);
+        };
+        let mut interpreter = Interpreter::default();
+        let start = Instant::now();
+        let result = if walk {
+            interpreter.walk(ast)
+        } else {
+            interpreter.run(ast)
+        };
+        let engine = if walk { "walker" } else { "vm" };
+        assert!(result.is_ok(), "{} failed to run {:?}", engine, source);
+        println!("{:>6}: {:?} for {:?}", engine, start.elapsed(), source);
+    }
+}
+
+#[test]
+#[ignore]
+fn while_loop() {
+    time("i = 0\nwhile i < 3000000 { i = i + 1 }\ni");
+}
+
+#[test]
+#[ignore]
+fn for_loop() {
+    time("s = 0\nfor i in 0..3000000 { s = s + 1 }\ns");
+}
+
+#[test]
+#[ignore]
+fn function_calls() {
+    time("f(x) = x + 1\ns = 0\nfor i in 0..1000000 { s = f(s) }\ns");
+}
@@ -0,0 +1,43 @@
+//! Checks vector arithmetic and the dot and cross products.
+
+mod common;
+
+use common::eval;
+
+#[test]
+fn dot_and_cross_products() {
+    assert_eq!(eval("⟨1, 2, 3⟩ ∙ ⟨4, 5, 6⟩"), "32");
+    assert_eq!(eval("⟨1, 0, 0⟩ × ⟨0, 1, 0⟩"), "⟨0, 0, 1⟩");
+    // The cross product is perpendicular to both its factors
+    assert_eq!(
+        eval("a = ⟨2, 3, 4⟩\nb = ⟨5, 6, 7⟩\n⟨(a × b) ∙ a, (a × b) ∙ b⟩"),
+        "⟨0, 0⟩"
+    );
+}
+
+#[test]
+fn elementwise_arithmetic_scaling_and_magnitude() {
+    assert_eq!(eval("⟨1, 2⟩ + ⟨3, 4⟩"), "⟨4, 6⟩");
+    assert_eq!(eval("⟨1, 2⟩ - ⟨3, 4⟩"), "⟨-2, -2⟩");
+    assert_eq!(eval("-⟨1, 2⟩"), "⟨-1, -2⟩");
+    assert_eq!(eval("2⟨1, 2⟩"), "⟨2, 4⟩");
+    assert_eq!(eval("⟨1, 2⟩ / 2"), "⟨1/2, 1⟩");
+    assert_eq!(eval("|⟨3, 4⟩|"), "5");
+    assert_eq!(eval("v = ⟨1, 2, 3⟩\nv₁"), "2");
+}
+
+#[test]
+fn lengths_must_match() {
+    assert_eq!(
+        eval("⟨1, 2⟩ + ⟨1, 2, 3⟩"),
+        "type error: Cannot apply + to vectors of lengths 2 and 3"
+    );
+    assert_eq!(
+        eval("⟨1, 2⟩ ∙ ⟨1, 2, 3⟩"),
+        "type error: Cannot take the dot product of vectors of lengths 2 and 3"
+    );
+    assert_eq!(
+        eval("⟨1, 2⟩ × ⟨3, 4⟩"),
+        "type error: The cross product is only defined for 3-dimensional vectors"
+    );
+}
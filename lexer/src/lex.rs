@@ -1,16 +1,103 @@
-use crate::{Token, TokenType};
+use crate::{StrPart, Token, TokenType};
 use common::SpectreError;
+use unicode_ident::{is_xid_continue, is_xid_start};
+use unicode_normalization::UnicodeNormalization;
 use TokenType::*;
 
 const SUPERSCRIPT: &str = "ᵃᵇᶜᵈᵉᶠᵍʰⁱʲᵏˡᵐⁿᵒᵖʳˢᵗᵘᵛʷˣʸᶻᴬᴮᶜᴰᴱᶠᴳᴴᴵᴶᴷᴸᴹᴺᴼᴾᴿˢᵀᵁⱽᵂˣʸᶻ⁰¹²³⁴⁵⁶⁷⁸⁹⁺⁻⁼⁽⁾";
 const NORMALSCRIPT: &str = "abcdefghijklmnoprstuvwxyzABCDEFGHIJKLMNOPRSTUVWXYZ0123456789+-=()";
+const SUBSCRIPT: &str = "ₐₑₕᵢⱼₖₗₘₙₒₚᵣₛₜᵤᵥₓ₀₁₂₃₄₅₆₇₈₉₊₋₌₍₎";
+const NORMALSUBSCRIPT: &str = "aehijklmnoprstuvx0123456789+-=()";
+/// Symbols that are always names on their own, never part of a longer one.
+const CONSTANTS: &str = "πτ∞";
 
 type LexResult = Result<Token, SpectreError>;
 
+/// The digits of exponents written like `×10²³`.
+const SUPERSCRIPT_DIGITS: &str = "⁰¹²³⁴⁵⁶⁷⁸⁹";
+
+/// The token for an integer literal, which is only big if it has to be.
+fn integer(value: num_bigint::BigInt) -> TokenType {
+    match i32::try_from(&value) {
+        Ok(value) => Int(value),
+        Err(_) => BigInt(value),
+    }
+}
+
+/// Writes `text` in subscript characters where it can, as the inverse of
+/// lexing a subscript.
+pub fn subscript(text: &str) -> String {
+    text.chars()
+        .map(
+            |ch| match NORMALSUBSCRIPT.chars().position(|normal| normal == ch) {
+                Some(index) => SUBSCRIPT.chars().nth(index).unwrap_or(ch),
+                None => ch,
+            },
+        )
+        .collect()
+}
+
+/// Whether `ch` can start a name, by Unicode's identifier rules or as a
+/// mathematical letter.
+fn is_name_start(ch: char) -> bool {
+    !is_script(ch) && (ch == '_' || is_xid_start(ch) || is_math_letter(ch))
+}
+
+/// Whether `ch` can continue a name.
+fn is_name_continue(ch: char) -> bool {
+    !is_script(ch) && (is_xid_continue(ch) || is_math_letter(ch))
+}
+
+/// Symbols used as letters in mathematics that Unicode doesn't class as
+/// letters, like `ℏ` and `𝜑`.
+fn is_math_letter(ch: char) -> bool {
+    matches!(
+        ch,
+        // Letterlike Symbols
+        '\u{2100}'..='\u{214F}'
+        // Mathematical Alphanumeric Symbols
+        | '\u{1D400}'..='\u{1D7FF}'
+        | 'ϵ'
+        | '∞'
+    )
+}
+
+/// Superscript and subscript letters are identifier characters to Unicode,
+/// but here they lex as their own tokens.
+fn is_script(ch: char) -> bool {
+    SUPERSCRIPT.contains(ch) || SUBSCRIPT.contains(ch)
+}
+
 pub struct Lexer {
     source: String,
     index: usize,
     current_char: char,
+    /// Added to every range, for sources that continue an earlier one.
+    offset: usize,
+}
+
+/// Moves `token`, including any superscript, subscript or interpolated
+/// tokens inside it,
+/// `offset` characters later in the source.
+fn shift(token: &mut Token, offset: usize) {
+    token.range = token.range.start + offset..token.range.end + offset;
+    match &mut token.ty {
+        Superscript(tokens) | Subscript(tokens) => {
+            for token in tokens {
+                shift(token, offset);
+            }
+        }
+        Str(parts) => {
+            for part in parts {
+                if let StrPart::Expr(tokens) = part {
+                    for token in tokens {
+                        shift(token, offset);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
 }
 
 impl Lexer {
@@ -19,6 +106,16 @@ impl Lexer {
             index: 0,
             current_char: source.chars().nth(0).unwrap_or('\0'),
             source,
+            offset: 0,
+        }
+    }
+
+    /// Lexes `source` as if it started `offset` characters into a larger one,
+    /// such as a REPL line following earlier input.
+    pub fn with_offset(source: String, offset: usize) -> Self {
+        Self {
+            offset,
+            ..Self::new(source)
         }
     }
 
@@ -32,6 +129,10 @@ impl Lexer {
         }
     }
 
+    fn peek(&self, n: usize) -> char {
+        self.source.chars().nth(self.index + n).unwrap_or('\0')
+    }
+
     fn error(&self, msg: String, reason: String, start: usize) -> SpectreError {
         SpectreError {
             msg,
@@ -41,13 +142,22 @@ impl Lexer {
     }
 
     pub fn lex(&mut self) -> Result<Vec<Token>, SpectreError> {
+        let offset = self.offset;
+        let shift_error = |mut e: SpectreError| {
+            e.range = e.range.start + offset..e.range.end + offset;
+            e
+        };
+
         let mut tokens: Vec<Token> = vec![];
-        let mut token = self.next_token()?;
+        let mut token = self.next_token().map_err(shift_error)?;
         while token.ty != EOF {
             tokens.push(token);
-            token = self.next_token()?;
+            token = self.next_token().map_err(shift_error)?;
         }
         tokens.push(token);
+        for token in &mut tokens {
+            shift(token, offset);
+        }
         Ok(tokens)
     }
 
@@ -59,12 +169,37 @@ impl Lexer {
         let start = self.index;
         match self.current_char {
             '0'..='9' => self.number(),
-            'a'..='z' | 'A'..='Z' | '_' | 'Α'..='ω' | '∞' => self.word(),
-            ch if SUPERSCRIPT.contains(ch) => self.superscript(),
+            '"' => self.string(),
+            ch if is_name_start(ch) => self.word(),
+            ch if SUPERSCRIPT.contains(ch) => Ok(Token {
+                ty: Superscript(self.script(SUPERSCRIPT, NORMALSCRIPT)?),
+                range: start..self.index,
+            }),
+            ch if SUBSCRIPT.contains(ch) => Ok(Token {
+                ty: Subscript(self.script(SUBSCRIPT, NORMALSUBSCRIPT)?),
+                range: start..self.index,
+            }),
             '=' => {
                 self.advance();
                 Ok(Token {
-                    ty: Eq,
+                    ty: match self.current_char {
+                        '=' => {
+                            self.advance();
+                            EqEq
+                        }
+                        '>' => {
+                            self.advance();
+                            FatArrow
+                        }
+                        _ => Eq,
+                    },
+                    range: start..self.index,
+                })
+            }
+            '↦' => {
+                self.advance();
+                Ok(Token {
+                    ty: MapsTo,
                     range: start..self.index,
                 })
             }
@@ -106,7 +241,7 @@ impl Lexer {
             '/' => {
                 self.advance();
                 if self.current_char == '/' {
-                    while self.current_char != '\n' {
+                    while !matches!(self.current_char, '\n' | '\0') {
                         self.advance();
                     }
                     self.next_token()
@@ -141,7 +276,20 @@ impl Lexer {
             '!' => {
                 self.advance();
                 Ok(Token {
-                    ty: Exclamation,
+                    ty: match self.current_char {
+                        '=' => {
+                            self.advance();
+                            Neq
+                        }
+                        _ => Exclamation,
+                    },
+                    range: start..self.index,
+                })
+            }
+            '\'' | '′' => {
+                self.advance();
+                Ok(Token {
+                    ty: Prime,
                     range: start..self.index,
                 })
             }
@@ -173,6 +321,27 @@ impl Lexer {
                     range: start..self.index,
                 })
             }
+            '∑' => {
+                self.advance();
+                Ok(Token {
+                    ty: Sum,
+                    range: start..self.index,
+                })
+            }
+            '∏' => {
+                self.advance();
+                Ok(Token {
+                    ty: Product,
+                    range: start..self.index,
+                })
+            }
+            '∫' => {
+                self.advance();
+                Ok(Token {
+                    ty: Integral,
+                    range: start..self.index,
+                })
+            }
             '<' => {
                 self.advance();
                 Ok(Token {
@@ -199,6 +368,76 @@ impl Lexer {
                     range: start..self.index,
                 })
             }
+            '≠' => {
+                self.advance();
+                Ok(Token {
+                    ty: Neq,
+                    range: start..self.index,
+                })
+            }
+            '≤' => {
+                self.advance();
+                Ok(Token {
+                    ty: Lte,
+                    range: start..self.index,
+                })
+            }
+            '≥' => {
+                self.advance();
+                Ok(Token {
+                    ty: Gte,
+                    range: start..self.index,
+                })
+            }
+            '≈' => {
+                self.advance();
+                Ok(Token {
+                    ty: Approx,
+                    range: start..self.index,
+                })
+            }
+            '∈' => {
+                self.advance();
+                Ok(Token {
+                    ty: In,
+                    range: start..self.index,
+                })
+            }
+            '∉' => {
+                self.advance();
+                Ok(Token {
+                    ty: NotIn,
+                    range: start..self.index,
+                })
+            }
+            '¬' => {
+                self.advance();
+                Ok(Token {
+                    ty: Not,
+                    range: start..self.index,
+                })
+            }
+            '∧' => {
+                self.advance();
+                Ok(Token {
+                    ty: And,
+                    range: start..self.index,
+                })
+            }
+            '∨' => {
+                self.advance();
+                Ok(Token {
+                    ty: Or,
+                    range: start..self.index,
+                })
+            }
+            '→' => {
+                self.advance();
+                Ok(Token {
+                    ty: Implies,
+                    range: start..self.index,
+                })
+            }
             '(' => {
                 self.advance();
                 Ok(Token {
@@ -227,6 +466,47 @@ impl Lexer {
                     range: start..self.index,
                 })
             }
+            '.' if self.peek(1) == '.' => {
+                self.advance();
+                self.advance();
+                Ok(Token {
+                    ty: if self.current_char == '=' {
+                        self.advance();
+                        DotDotEq
+                    } else {
+                        DotDot
+                    },
+                    range: start..self.index,
+                })
+            }
+            '⟨' => {
+                self.advance();
+                Ok(Token {
+                    ty: LeftAngle,
+                    range: start..self.index,
+                })
+            }
+            '⟩' => {
+                self.advance();
+                Ok(Token {
+                    ty: RightAngle,
+                    range: start..self.index,
+                })
+            }
+            '[' => {
+                self.advance();
+                Ok(Token {
+                    ty: LeftBracket,
+                    range: start..self.index,
+                })
+            }
+            ']' => {
+                self.advance();
+                Ok(Token {
+                    ty: RightBracket,
+                    range: start..self.index,
+                })
+            }
             '|' => {
                 self.advance();
                 Ok(Token {
@@ -290,36 +570,324 @@ impl Lexer {
 
     fn number(&mut self) -> LexResult {
         let start = self.index;
-        let mut num_str: String = self.current_char.to_string();
-        let mut decimals = 0;
+        let radix = match (self.current_char, self.peek(1)) {
+            ('0', 'x') => Some(16),
+            ('0', 'o') => Some(8),
+            ('0', 'b') => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            self.advance();
+            self.advance();
+            return self.radix_number(radix, start);
+        }
+
+        let mut num_str = self.digits(10, start)?;
+        let mut float = false;
+        // Two dots make a range, which the number ends before
+        if self.current_char == '.' && self.peek(1) != '.' {
+            float = true;
+            num_str.push('.');
+            self.advance();
+            num_str.push_str(&self.digits(10, start)?);
+            if self.current_char == '.' && self.peek(1) != '.' {
+                while self.current_char == '.' || self.current_char.is_ascii_digit() {
+                    self.advance();
+                }
+                return Err(self.error(
+                    "invalid number".to_string(),
+                    "A number can only have one decimal point".to_string(),
+                    start,
+                ));
+            }
+        }
+        if let Some(exponent) = self.exponent(start)? {
+            float = true;
+            num_str.push('e');
+            num_str.push_str(&exponent);
+        }
+
+        let ty = if float {
+            match num_str.parse::<f64>() {
+                Ok(value) if value.is_finite() => Float(value),
+                _ => {
+                    return Err(self.error(
+                        "invalid number".to_string(),
+                        format!("{} is too large to be a float", num_str),
+                        start,
+                    ))
+                }
+            }
+        } else {
+            integer(
+                num_str
+                    .parse()
+                    .expect("a string of digits always parses as a big integer"),
+            )
+        };
+        Ok(Token {
+            ty,
+            range: start..self.index,
+        })
+    }
+
+    /// Lexes the digits of an integer written in `radix`, after its prefix.
+    fn radix_number(&mut self, radix: u32, start: usize) -> LexResult {
+        let digits = self.digits(radix, start)?;
+        if digits.is_empty() || self.current_char.is_ascii_alphanumeric() {
+            let reason = match self.current_char {
+                ch if ch.is_ascii_alphanumeric() => {
+                    format!("'{}' is not a digit in base {}", ch, radix)
+                }
+                _ => format!("Expected digits in base {}", radix),
+            };
+            while self.current_char.is_ascii_alphanumeric() {
+                self.advance();
+            }
+            return Err(self.error("invalid number".to_string(), reason, start));
+        }
+        let value = num_bigint::BigInt::parse_bytes(digits.as_bytes(), radix)
+            .expect("digits in the radix always parse");
+        Ok(Token {
+            ty: integer(value),
+            range: start..self.index,
+        })
+    }
+
+    /// Lexes a run of digits in `radix`, which may be separated by single
+    /// underscores, returning them without the underscores.
+    fn digits(&mut self, radix: u32, start: usize) -> Result<String, SpectreError> {
+        let mut digits = String::new();
+        loop {
+            match self.current_char {
+                ch if ch.is_digit(radix) => digits.push(ch),
+                '_' if !digits.is_empty() && self.peek(1).is_digit(radix) => {}
+                '_' => {
+                    self.advance();
+                    return Err(self.error(
+                        "invalid number".to_string(),
+                        "Underscores can only separate digits".to_string(),
+                        start,
+                    ));
+                }
+                _ => break,
+            }
+            self.advance();
+        }
+        Ok(digits)
+    }
+
+    /// Lexes the exponent of a number in scientific notation, written either
+    /// like `e23` or like `×10²³`, if it has one. An `e` that isn't followed
+    /// by digits is left alone, so `2e` is still twice e.
+    fn exponent(&mut self, start: usize) -> Result<Option<String>, SpectreError> {
+        let mut exponent = String::new();
+        match self.current_char {
+            'e' | 'E' => {
+                let signed = matches!(self.peek(1), '+' | '-');
+                if !self.peek(1 + signed as usize).is_ascii_digit() {
+                    return Ok(None);
+                }
+                self.advance();
+                if signed {
+                    exponent.push(self.current_char);
+                    self.advance();
+                }
+                exponent.push_str(&self.digits(10, start)?);
+            }
+            '×' if self.peek(1) == '1'
+                && self.peek(2) == '0'
+                && (SUPERSCRIPT_DIGITS.contains(self.peek(3)) || "⁺⁻".contains(self.peek(3))) =>
+            {
+                for _ in 0..3 {
+                    self.advance();
+                }
+                if let '⁺' | '⁻' = self.current_char {
+                    if self.current_char == '⁻' {
+                        exponent.push('-');
+                    }
+                    self.advance();
+                }
+                while let Some(digit) = SUPERSCRIPT_DIGITS
+                    .chars()
+                    .position(|ch| ch == self.current_char)
+                {
+                    exponent.push_str(&digit.to_string());
+                    self.advance();
+                }
+                if !exponent.ends_with(|ch: char| ch.is_ascii_digit()) {
+                    return Err(self.error(
+                        "invalid number".to_string(),
+                        "Expected digits in the exponent".to_string(),
+                        start,
+                    ));
+                }
+            }
+            _ => return Ok(None),
+        }
+        Ok(Some(exponent))
+    }
+
+    /// Lexes a string literal, replacing its escapes and lexing each
+    /// expression interpolated in it separately.
+    fn string(&mut self) -> LexResult {
+        let start = self.index;
         self.advance();
+        let mut parts = vec![];
+        let mut text = String::new();
 
-        while "0123456789.".contains(self.current_char) {
-            if self.current_char == '.' {
-                decimals += 1;
+        loop {
+            match self.current_char {
+                '"' => break,
+                '\0' | '\n' => {
+                    return Err(self.error(
+                        "unterminated string".to_string(),
+                        "Expected a closing '\"'".to_string(),
+                        start,
+                    ))
+                }
+                '\\' => {
+                    text.push(self.escape()?);
+                    continue;
+                }
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(StrPart::Text(std::mem::take(&mut text).into()));
+                    }
+                    parts.push(StrPart::Expr(self.interpolation()?));
+                    continue;
+                }
+                '}' => {
+                    self.advance();
+                    return Err(self.error(
+                        "unmatched brace".to_string(),
+                        "Write '\\}' for a '}' in a string".to_string(),
+                        self.index - 1,
+                    ));
+                }
+                ch => text.push(ch),
             }
-            num_str.push(self.current_char);
             self.advance();
         }
+        self.advance();
 
+        if !text.is_empty() || parts.is_empty() {
+            parts.push(StrPart::Text(text.into()));
+        }
         Ok(Token {
-            ty: if decimals > 0 {
-                Float(num_str.parse::<f64>().unwrap())
-            } else {
-                Int(num_str.parse::<i32>().unwrap())
-            },
+            ty: Str(parts),
             range: start..self.index,
         })
     }
 
+    /// Lexes an escape sequence in a string, starting at its backslash.
+    fn escape(&mut self) -> Result<char, SpectreError> {
+        let start = self.index;
+        self.advance();
+        let ch = match self.current_char {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            ch @ ('\\' | '"' | '{' | '}') => ch,
+            '\0' | '\n' => {
+                return Err(self.error(
+                    "unterminated string".to_string(),
+                    "Expected a closing '\"'".to_string(),
+                    start,
+                ))
+            }
+            ch => {
+                self.advance();
+                return Err(self.error(
+                    "invalid escape".to_string(),
+                    format!("'\\{}' is not a valid escape", ch),
+                    start,
+                ));
+            }
+        };
+        self.advance();
+        Ok(ch)
+    }
+
+    /// Lexes the expression between a pair of braces in a string, starting at
+    /// the opening brace.
+    fn interpolation(&mut self) -> Result<Vec<Token>, SpectreError> {
+        let start = self.index;
+        self.advance();
+        let mut source = String::new();
+        self.interpolated_source(&mut source, start)?;
+
+        if source.trim().is_empty() {
+            return Err(self.error(
+                "invalid interpolation".to_string(),
+                "Expected an expression between the braces".to_string(),
+                start,
+            ));
+        }
+        // As with scripts, offsetting the sub-lexer recovers source positions
+        Lexer::with_offset(source, start + 1).lex()
+    }
+
+    /// Copies an interpolated expression onto `source` up to its closing
+    /// brace, which is skipped. Braces nest, and so do strings, as in
+    /// `"a{"b"}c"`, whose quotes and braces are their own.
+    fn interpolated_source(
+        &mut self,
+        source: &mut String,
+        start: usize,
+    ) -> Result<(), SpectreError> {
+        let mut depth = 0;
+        let mut in_string = false;
+
+        loop {
+            match self.current_char {
+                '\0' | '\n' => {
+                    return Err(self.error(
+                        "unterminated string".to_string(),
+                        "Expected a closing '}'".to_string(),
+                        start,
+                    ))
+                }
+                '"' => in_string = !in_string,
+                '\\' if in_string => {
+                    source.push('\\');
+                    self.advance();
+                    if matches!(self.current_char, '\0' | '\n') {
+                        continue;
+                    }
+                }
+                '{' if in_string => {
+                    source.push('{');
+                    self.advance();
+                    self.interpolated_source(source, start)?;
+                    source.push('}');
+                    continue;
+                }
+                '}' if in_string => {}
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                '{' => depth += 1,
+                _ => {}
+            }
+            source.push(self.current_char);
+            self.advance();
+        }
+        self.advance();
+        Ok(())
+    }
+
     fn word(&mut self) -> LexResult {
         let start = self.index;
         let mut word = self.current_char.to_string();
+        // Constants stand alone so that `2iπ` is a product
+        let constant = CONSTANTS.contains(self.current_char);
         self.advance();
 
-        while self.current_char != '\0' {
+        while !constant && self.current_char != '\0' {
             match self.current_char {
-                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | 'Α'..='ω' | '∞' => {
+                ch if CONSTANTS.contains(ch) => break,
+                ch if is_name_continue(ch) => {
                     word.push(self.current_char);
                     self.advance();
                 }
@@ -327,6 +895,8 @@ impl Lexer {
             };
         }
 
+        // Names that look the same are the same, however they were composed
+        let word: String = word.nfc().collect();
         Ok(Token {
             ty: match word.as_str() {
                 "not" => Not,
@@ -335,20 +905,28 @@ impl Lexer {
                 "if" => If,
                 "else" => Else,
                 "while" => While,
+                "for" => For,
+                "in" => In,
+                "step" => Step,
+                "break" => Break,
+                "continue" => Continue,
+                "return" => Return,
                 _ => Identifier(word.into()),
             },
             range: start..self.index,
         })
     }
 
-    fn superscript(&mut self) -> LexResult {
+    /// Lexes a run of characters from `script` as the corresponding characters
+    /// of `normal`.
+    fn script(&mut self, script: &str, normal: &str) -> Result<Vec<Token>, SpectreError> {
         let start = self.index;
         let mut source = String::new();
 
         while self.current_char != '\0' {
-            match SUPERSCRIPT.chars().position(|ch| ch == self.current_char) {
+            match script.chars().position(|ch| ch == self.current_char) {
                 Some(index) => {
-                    let normal_char = NORMALSCRIPT.chars().nth(index).unwrap();
+                    let normal_char = normal.chars().nth(index).unwrap();
                     source.push(normal_char);
                     self.advance();
                 }
@@ -356,12 +934,11 @@ impl Lexer {
             };
         }
 
-        let mut lexer = Lexer::new(source);
+        // Each character maps to exactly one normal character, so offsetting
+        // the sub-lexer by `start` recovers source positions
+        let mut lexer = Lexer::with_offset(source, start);
         let mut tokens = lexer.lex()?;
         tokens.pop();
-        Ok(Token {
-            ty: Superscript(tokens),
-            range: start..self.index,
-        })
+        Ok(tokens)
     }
 }
@@ -1,12 +1,30 @@
 use std::{fmt, ops::Range, rc::Rc};
 
+use num_bigint::BigInt;
+
+/// A piece of a string literal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrPart {
+    /// Text with its escapes already replaced.
+    Text(Rc<str>),
+    /// The tokens of an expression interpolated in braces, like `{x}`.
+    Expr(Vec<Token>),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     Int(i32),
+    /// An integer literal too large for an `i32`.
+    BigInt(BigInt),
     Float(f64),
     Identifier(Rc<str>),
+    /// A string literal, split where it interpolates expressions.
+    Str(Vec<StrPart>),
     Superscript(Vec<Token>),
+    Subscript(Vec<Token>),
     Eq,
+    MapsTo,
+    FatArrow,
     Plus,
     Minus,
     Star,
@@ -17,10 +35,14 @@ pub enum TokenType {
     Percent,
     Carrot,
     Exclamation,
+    Prime,
     Degree,
     Sqrt,
     Cbrt,
     Fort,
+    Sum,
+    Product,
+    Integral,
     Not,
     EqEq,
     Neq,
@@ -28,21 +50,36 @@ pub enum TokenType {
     Lte,
     Gt,
     Gte,
+    Approx,
+    In,
+    NotIn,
     And,
     Or,
+    Implies,
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftAngle,
+    RightAngle,
+    LeftBracket,
+    RightBracket,
     Pipe,
     LeftFloor,
     RightFloor,
     LeftCeil,
     RightCeil,
     Comma,
+    DotDot,
+    DotDotEq,
+    Step,
     If,
     Else,
     While,
+    For,
+    Break,
+    Continue,
+    Return,
     Newline,
     EOF,
 }
@@ -52,8 +89,10 @@ impl fmt::Display for TokenType {
         use TokenType::*;
         match self {
             Int(value) => write!(f, "{}", value),
+            BigInt(value) => write!(f, "{}", value),
             Float(value) => write!(f, "{}", value),
             Identifier(name) => write!(f, "{}", name),
+            Str(_) => write!(f, "string"),
             Superscript(tokens) => write!(
                 f,
                 "^({})",
@@ -63,7 +102,18 @@ impl fmt::Display for TokenType {
                     .collect::<Vec<_>>()
                     .join(" ")
             ),
+            Subscript(tokens) => write!(
+                f,
+                "_({})",
+                tokens
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
             Eq => write!(f, "'='"),
+            MapsTo => write!(f, "'↦'"),
+            FatArrow => write!(f, "'=>'"),
             Plus => write!(f, "'+'"),
             Minus => write!(f, "'-'"),
             Star => write!(f, "'*'"),
@@ -74,10 +124,14 @@ impl fmt::Display for TokenType {
             Percent => write!(f, "'%'"),
             Carrot => write!(f, "'^'"),
             Exclamation => write!(f, "'!'"),
+            Prime => write!(f, "'′'"),
             Degree => write!(f, "'°'"),
             Sqrt => write!(f, "'√'"),
             Cbrt => write!(f, "'∛'"),
             Fort => write!(f, "'∜'"),
+            Sum => write!(f, "'∑'"),
+            Product => write!(f, "'∏'"),
+            Integral => write!(f, "'∫'"),
             Or => write!(f, "'or'"),
             EqEq => write!(f, "'=='"),
             Neq => write!(f, "'!='"),
@@ -85,12 +139,20 @@ impl fmt::Display for TokenType {
             Lte => write!(f, "'<='"),
             Gt => write!(f, "'>'"),
             Gte => write!(f, "'>='"),
+            Approx => write!(f, "'≈'"),
+            In => write!(f, "'∈'"),
+            NotIn => write!(f, "'∉'"),
+            Implies => write!(f, "'→'"),
             Not => write!(f, "'not'"),
             And => write!(f, "'and'"),
             LeftParen => write!(f, "'('"),
             RightParen => write!(f, "')'"),
             LeftBrace => write!(f, "'{{'"),
             RightBrace => write!(f, "'}}'"),
+            LeftAngle => write!(f, "'⟨'"),
+            RightAngle => write!(f, "'⟩'"),
+            LeftBracket => write!(f, "'['"),
+            RightBracket => write!(f, "']'"),
             Pipe => write!(f, "'|'"),
             LeftFloor => write!(f, "'⌊'"),
             RightFloor => write!(f, "'⌋'"),
@@ -100,6 +162,13 @@ impl fmt::Display for TokenType {
             If => write!(f, "'if'"),
             Else => write!(f, "'else'"),
             While => write!(f, "'while'"),
+            For => write!(f, "'for'"),
+            Break => write!(f, "'break'"),
+            Continue => write!(f, "'continue'"),
+            Return => write!(f, "'return'"),
+            DotDot => write!(f, "'..'"),
+            DotDotEq => write!(f, "'..='"),
+            Step => write!(f, "'step'"),
             Newline => write!(f, "'\\n'"),
             EOF => write!(f, "<eof>"),
         }
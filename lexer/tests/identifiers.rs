@@ -0,0 +1,55 @@
+use lexer::{Lexer, TokenType};
+
+/// Lexes `source`, which should be a single name, and returns it.
+fn name(source: &str) -> String {
+    let tokens = match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    };
+    match &tokens[..] {
+        [token, eof] if eof.ty == TokenType::EOF => match &token.ty {
+            TokenType::Identifier(name) => name.to_string(),
+            ty => panic!("expected a name, got {}", ty),
+        },
+        tokens => panic!("expected one token, got {}", tokens.len() - 1),
+    }
+}
+
+#[test]
+fn unicode_letters_make_names() {
+    assert_eq!(name("café"), "café");
+    assert_eq!(name("αβ"), "αβ");
+    assert_eq!(name("Ωmega_2"), "Ωmega_2");
+    assert_eq!(name("_"), "_");
+}
+
+#[test]
+fn mathematical_letters_make_names() {
+    assert_eq!(name("𝜑"), "𝜑");
+    assert_eq!(name("𝐱"), "𝐱");
+    assert_eq!(name("ℏ"), "ℏ");
+    assert_eq!(name("ℓ"), "ℓ");
+    assert_eq!(name("ϵ"), "ϵ");
+}
+
+#[test]
+fn names_are_normalized() {
+    // e followed by a combining acute accent
+    assert_eq!(name("cafe\u{301}"), "café");
+    assert_eq!(name("cafe\u{301}"), name("caf\u{e9}"));
+}
+
+#[test]
+fn scripts_and_constants_are_not_part_of_names() {
+    let types = |source: &str| -> Vec<String> {
+        Lexer::new(source.to_string())
+            .lex()
+            .unwrap_or_else(|e| panic!("failed to lex {:?}: {}", source, e.reason))
+            .iter()
+            .map(|token| token.ty.to_string())
+            .collect()
+    };
+    assert_eq!(types("xᵀ"), ["x", "^(T)", "<eof>"]);
+    assert_eq!(types("2πr"), ["2", "π", "r", "<eof>"]);
+    assert_eq!(types("x∙y"), ["x", "'∙'", "y", "<eof>"]);
+}
@@ -0,0 +1,100 @@
+use std::ops::Range;
+
+use lexer::{Lexer, TokenType};
+
+/// Lexes `source` and returns the types of its tokens.
+fn lex(source: &str) -> Vec<TokenType> {
+    match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens.into_iter().map(|token| token.ty).collect(),
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    }
+}
+
+/// Lexes `source`, which should be a single number, and returns it.
+fn number(source: &str) -> TokenType {
+    match &lex(source)[..] {
+        [ty, TokenType::EOF] => ty.clone(),
+        tokens => panic!("expected one token, got {}", tokens.len() - 1),
+    }
+}
+
+/// Lexes `source`, which should fail, and returns the reason and range.
+fn error(source: &str) -> (String, Range<usize>) {
+    match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => panic!("expected {:?} to fail, got {} tokens", source, tokens.len()),
+        Err(e) => (e.reason, e.range),
+    }
+}
+
+#[test]
+fn scientific_notation() {
+    assert_eq!(number("6.022e23"), TokenType::Float(6.022e23));
+    assert_eq!(number("1.5E-3"), TokenType::Float(1.5e-3));
+    assert_eq!(number("2e+10"), TokenType::Float(2e10));
+    assert_eq!(number("6.022×10²³"), TokenType::Float(6.022e23));
+    assert_eq!(number("3×10⁻²"), TokenType::Float(0.03));
+}
+
+#[test]
+fn e_without_digits_is_a_name() {
+    assert_eq!(
+        lex("2e"),
+        [
+            TokenType::Int(2),
+            TokenType::Identifier("e".into()),
+            TokenType::EOF
+        ]
+    );
+}
+
+#[test]
+fn radix_prefixes() {
+    assert_eq!(number("0xFF"), TokenType::Int(255));
+    assert_eq!(number("0xff"), TokenType::Int(255));
+    assert_eq!(number("0b1010"), TokenType::Int(10));
+    assert_eq!(number("0o17"), TokenType::Int(15));
+    assert_eq!(
+        number("0x1_0000_0000"),
+        TokenType::BigInt(4294967296_u64.into())
+    );
+}
+
+#[test]
+fn underscores_separate_digits() {
+    assert_eq!(number("1_000_000"), TokenType::Int(1000000));
+    assert_eq!(number("1_000.000_1"), TokenType::Float(1000.0001));
+}
+
+#[test]
+fn malformed_numbers_are_errors() {
+    assert_eq!(
+        error("1.2.3"),
+        ("A number can only have one decimal point".to_string(), 0..5)
+    );
+    assert_eq!(
+        error("x = 0xFG"),
+        ("'G' is not a digit in base 16".to_string(), 4..8)
+    );
+    assert_eq!(error("0b"), ("Expected digits in base 2".to_string(), 0..2));
+    assert_eq!(
+        error("0b102"),
+        ("'2' is not a digit in base 2".to_string(), 0..5)
+    );
+    assert_eq!(
+        error("1__0"),
+        ("Underscores can only separate digits".to_string(), 0..2)
+    );
+    assert_eq!(
+        error("1e400"),
+        ("1e400 is too large to be a float".to_string(), 0..5)
+    );
+    assert_eq!(
+        error("1×10⁻"),
+        ("Expected digits in the exponent".to_string(), 0..5)
+    );
+}
+
+#[test]
+fn comments_can_end_the_source() {
+    assert_eq!(lex("2 // two"), [TokenType::Int(2), TokenType::EOF]);
+}
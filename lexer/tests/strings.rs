@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+use lexer::{Lexer, StrPart, TokenType};
+
+/// Lexes `source`, which should be a single string, and returns its parts.
+fn string(source: &str) -> Vec<StrPart> {
+    let tokens = match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    };
+    match &tokens[..] {
+        [token, eof] if eof.ty == TokenType::EOF => match &token.ty {
+            TokenType::Str(parts) => parts.clone(),
+            ty => panic!("expected a string, got {}", ty),
+        },
+        tokens => panic!("expected one token, got {}", tokens.len() - 1),
+    }
+}
+
+fn text(text: &str) -> StrPart {
+    StrPart::Text(text.into())
+}
+
+/// Lexes `source`, which should fail, and returns the reason and range.
+fn error(source: &str) -> (String, Range<usize>) {
+    match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => panic!("expected {:?} to fail, got {} tokens", source, tokens.len()),
+        Err(e) => (e.reason, e.range),
+    }
+}
+
+#[test]
+fn escapes_are_replaced() {
+    assert_eq!(string(r#""""#), [text("")]);
+    assert_eq!(string(r#""a\tb\n""#), [text("a\tb\n")]);
+    assert_eq!(string(r#""\"\\\{\}""#), [text("\"\\{}")]);
+}
+
+#[test]
+fn interpolations_are_lexed_in_place() {
+    let parts = string(r#""x = {x + 1}!""#);
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0], text("x = "));
+    assert_eq!(parts[2], text("!"));
+    let StrPart::Expr(tokens) = &parts[1] else {
+        panic!("expected an interpolation, got {:?}", parts[1]);
+    };
+    let tokens: Vec<_> = tokens
+        .iter()
+        .map(|token| (token.ty.clone(), token.range.clone()))
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            (TokenType::Identifier("x".into()), 6..7),
+            (TokenType::Plus, 8..9),
+            (TokenType::Int(1), 10..11),
+            (TokenType::EOF, 11..11),
+        ]
+    );
+}
+
+#[test]
+fn interpolations_can_hold_strings() {
+    let parts = string(r#""a{"b"}c""#);
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0], text("a"));
+    assert_eq!(parts[2], text("c"));
+    let StrPart::Expr(tokens) = &parts[1] else {
+        panic!("expected an interpolation, got {:?}", parts[1]);
+    };
+    let tokens: Vec<_> = tokens
+        .iter()
+        .map(|token| (token.ty.clone(), token.range.clone()))
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            (TokenType::Str(vec![text("b")]), 3..6),
+            (TokenType::EOF, 6..6),
+        ]
+    );
+    assert_eq!(string(r#""{"\"\}" + "{1}"}""#).len(), 1);
+}
+
+#[test]
+fn malformed_strings_are_errors() {
+    assert_eq!(
+        error(r#"x = "abc"#),
+        ("Expected a closing '\"'".to_string(), 4..8)
+    );
+    assert_eq!(
+        error(r#""a\qb""#),
+        ("'\\q' is not a valid escape".to_string(), 2..4)
+    );
+    // The last quote could start a string in the interpolation, so the
+    // error runs to the end of the line
+    assert_eq!(
+        error(r#""{x""#),
+        ("Expected a closing '}'".to_string(), 1..4)
+    );
+    assert_eq!(
+        error(r#""{ }""#),
+        (
+            "Expected an expression between the braces".to_string(),
+            1..4
+        )
+    );
+    assert_eq!(
+        error(r#""a}""#),
+        ("Write '\\}' for a '}' in a string".to_string(), 2..3)
+    );
+}
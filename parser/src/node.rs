@@ -1,4 +1,6 @@
-use std::{fmt, rc::Rc};
+use std::{fmt, ops::Range, rc::Rc};
+
+use num_bigint::BigInt;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum UnaryOp {
@@ -13,6 +15,9 @@ pub enum UnaryOp {
     Cbrt,
     Fort,
     Fact,
+    Transpose,
+    /// Differentiates a function.
+    Prime,
     Not,
 }
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -20,6 +25,8 @@ pub enum BinaryOp {
     Add,
     Sub,
     Mul,
+    Dot,
+    Cross,
     Div,
     Rem,
     Pow,
@@ -29,8 +36,37 @@ pub enum BinaryOp {
     Lte,
     Gt,
     Gte,
+    /// Equality to within the interpreter's tolerance.
+    Approx,
+    In,
+    NotIn,
     And,
     Or,
+    Implies,
+}
+
+/// Writes the symbol of the operator, with both sides of those that wrap
+/// their operand, as `⌊⌋` does.
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use UnaryOp::*;
+        match self {
+            Pos => write!(f, "+"),
+            Neg => write!(f, "-"),
+            Abs => write!(f, "||"),
+            Floor => write!(f, "⌊⌋"),
+            Ceil => write!(f, "⌈⌉"),
+            Round => write!(f, "⌊⌉"),
+            Degree => write!(f, "°"),
+            Sqrt => write!(f, "√"),
+            Cbrt => write!(f, "∛"),
+            Fort => write!(f, "∜"),
+            Fact => write!(f, "!"),
+            Transpose => write!(f, "ᵀ"),
+            Prime => write!(f, "′"),
+            Not => write!(f, "not"),
+        }
+    }
 }
 
 impl fmt::Display for BinaryOp {
@@ -40,6 +76,8 @@ impl fmt::Display for BinaryOp {
             Add => write!(f, "+"),
             Sub => write!(f, "-"),
             Mul => write!(f, "*"),
+            Dot => write!(f, "∙"),
+            Cross => write!(f, "×"),
             Div => write!(f, "/"),
             Rem => write!(f, "%"),
             Pow => write!(f, "^"),
@@ -49,34 +87,164 @@ impl fmt::Display for BinaryOp {
             Lte => write!(f, "<="),
             Gt => write!(f, ">"),
             Gte => write!(f, ">="),
+            Approx => write!(f, "≈"),
+            In => write!(f, "∈"),
+            NotIn => write!(f, "∉"),
             And => write!(f, "and"),
             Or => write!(f, "or"),
+            Implies => write!(f, "→"),
         }
     }
 }
 
+/// A value tagged with the range of source characters it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub range: Range<usize>,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, range: Range<usize>) -> Self {
+        Self { node, range }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Int(i32),
+    BigInt(BigInt),
     Float(f64),
+    Str(Rc<str>),
+    /// A string literal with expressions interpolated in it, as the pieces
+    /// to join.
+    Interpolation(Vec<Spanned<Node>>),
     Identifier(Rc<str>),
-    Assignment(Rc<str>, Box<Node>),
-    Unary(UnaryOp, Box<Node>),
-    Binary(Box<Node>, BinaryOp, Box<Node>),
-    If(Box<Node>, Box<Node>, Option<Box<Node>>),
-    While(Box<Node>, Box<Node>),
-    FnDef(Rc<str>, Vec<Rc<str>>, Box<Node>),
-    Call(Rc<str>, Vec<Node>),
-    Statements(Vec<Node>),
+    Vector(Vec<Spanned<Node>>),
+    /// A list of values of any types, like `[1, "two", 3]`.
+    List(Vec<Spanned<Node>>),
+    Matrix(Vec<Vec<Spanned<Node>>>),
+    Assignment(Rc<str>, Box<Spanned<Node>>),
+    Unary(UnaryOp, Box<Spanned<Node>>),
+    Binary(Box<Spanned<Node>>, BinaryOp, Box<Spanned<Node>>),
+    If(
+        Box<Spanned<Node>>,
+        Box<Spanned<Node>>,
+        Option<Box<Spanned<Node>>>,
+    ),
+    While(Box<Spanned<Node>>, Box<Spanned<Node>>),
+    /// A loop over the elements of a range or collection, with the loop
+    /// variable scoped to the body.
+    For(Rc<str>, Box<Spanned<Node>>, Box<Spanned<Node>>),
+    /// Evenly spaced numbers from the start up to the end, including it if
+    /// the flag is set, with an optional step.
+    Range(
+        Box<Spanned<Node>>,
+        Box<Spanned<Node>>,
+        Option<Box<Spanned<Node>>>,
+        bool,
+    ),
+    /// Leaves the innermost loop, which keeps the value of its last full
+    /// iteration.
+    Break,
+    /// Skips to the next iteration of the innermost loop.
+    Continue,
+    /// Leaves the innermost function with a value, or 0 without one.
+    Return(Option<Box<Spanned<Node>>>),
+    FnDef(Rc<str>, Vec<Rc<str>>, Box<Spanned<Node>>),
+    Lambda(Vec<Rc<str>>, Box<Spanned<Node>>),
+    Call(Box<Spanned<Node>>, Vec<Spanned<Node>>),
+    /// A name with a subscript, like `x₁`, along with the name before the
+    /// subscript and the subscript itself. It's the variable of the whole
+    /// name if there is one, and otherwise indexes the variable before the
+    /// subscript.
+    Subscript(Rc<str>, Rc<str>, Box<Spanned<Node>>),
+    /// An element of a collection, or a row of a matrix, counting from 0,
+    /// or the elements at each index of a range.
+    Index(Box<Spanned<Node>>, Box<Spanned<Node>>),
+    /// A `∑` or `∏` over the elements of a range or collection, like
+    /// `∑(k ∈ 1..=10, k²)`, with the operator that folds its terms. Bounds,
+    /// as in `∑(k = 1, 10, k²)`, are parsed as the range between them.
+    BigOperator(BinaryOp, Rc<str>, Box<Spanned<Node>>, Box<Spanned<Node>>),
+    /// A definite integral of the body over the variable between two bounds.
+    Integral(
+        Rc<str>,
+        Box<Spanned<Node>>,
+        Box<Spanned<Node>>,
+        Box<Spanned<Node>>,
+    ),
+    Statements(Vec<Spanned<Node>>),
     Eof,
 }
 
+/// Writes `text` as it would appear in a string literal.
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\n' => "\\n".to_string(),
+            '\t' => "\\t".to_string(),
+            '\r' => "\\r".to_string(),
+            '\0' => "\\0".to_string(),
+            '\\' | '"' | '{' | '}' => format!("\\{}", ch),
+            ch => ch.to_string(),
+        })
+        .collect()
+}
+
 impl fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Node::Int(x) => write!(f, "{}", x),
+            Node::BigInt(x) => write!(f, "{}", x),
             Node::Float(x) => write!(f, "{}", x),
+            Node::Str(text) => write!(f, "\"{}\"", escape(text)),
+            Node::Interpolation(parts) => {
+                write!(f, "\"")?;
+                for part in parts {
+                    match &part.node {
+                        Node::Str(text) => write!(f, "{}", escape(text))?,
+                        node => write!(f, "{{{}}}", node)?,
+                    }
+                }
+                write!(f, "\"")
+            }
             Node::Identifier(name) => write!(f, "{}", name),
+            Node::Vector(nodes) => write!(
+                f,
+                "⟨{}⟩",
+                nodes
+                    .iter()
+                    .map(|node| format!("{}", node))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Node::List(nodes) => write!(
+                f,
+                "[{}]",
+                nodes
+                    .iter()
+                    .map(|node| format!("{}", node))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Node::Matrix(rows) => write!(
+                f,
+                "[{}]",
+                rows.iter()
+                    .map(|row| row
+                        .iter()
+                        .map(|node| format!("{}", node))
+                        .collect::<Vec<String>>()
+                        .join(", "))
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ),
             Node::Assignment(name, node) => write!(f, "({} = {})", name, node),
             Node::Unary(op, node) => {
                 use UnaryOp::*;
@@ -89,6 +257,8 @@ impl fmt::Display for Node {
                     Round => write!(f, "⌊{}⌉", node),
                     Degree => write!(f, "({}°)", node),
                     Fact => write!(f, "({}!)", node),
+                    Transpose => write!(f, "({}ᵀ)", node),
+                    Prime => write!(f, "{}′", node),
                     Sqrt => write!(f, "(√{})", node),
                     Cbrt => write!(f, "(∛{})", node),
                     Fort => write!(f, "(∜{})", node),
@@ -101,6 +271,26 @@ impl fmt::Display for Node {
                 None => write!(f, "if {} then {}", cond, then),
             },
             Node::While(cond, body) => write!(f, "while {} do {}", cond, body),
+            Node::For(var, iterable, body) => {
+                write!(f, "for {} ∈ {} do {}", var, iterable, body)
+            }
+            Node::Range(start, end, step, inclusive) => {
+                write!(
+                    f,
+                    "({}{}{}",
+                    start,
+                    if *inclusive { "..=" } else { ".." },
+                    end
+                )?;
+                if let Some(step) = step {
+                    write!(f, " step {}", step)?;
+                }
+                write!(f, ")")
+            }
+            Node::Break => write!(f, "break"),
+            Node::Continue => write!(f, "continue"),
+            Node::Return(Some(value)) => write!(f, "return {}", value),
+            Node::Return(None) => write!(f, "return"),
             Node::FnDef(name, args, body) => write!(
                 f,
                 "fn {}({}) {{\n  {}\n}}",
@@ -111,15 +301,33 @@ impl fmt::Display for Node {
                     .join(", "),
                 body
             ),
-            Node::Call(name, args) => write!(
+            Node::Lambda(args, body) => write!(
+                f,
+                "({}) ↦ {}",
+                args.iter()
+                    .map(|arg| format!("{}", arg))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                body
+            ),
+            Node::Call(function, args) => write!(
                 f,
                 "{}({})",
-                name,
+                function,
                 args.iter()
                     .map(|arg| format!("{}", arg))
                     .collect::<Vec<String>>()
                     .join(", ")
             ),
+            Node::Subscript(name, _, _) => write!(f, "{}", name),
+            Node::Index(node, index) => write!(f, "{}[{}]", node, index),
+            Node::BigOperator(op, index, iterable, body) => {
+                let symbol = if *op == BinaryOp::Mul { "∏" } else { "∑" };
+                write!(f, "{}({} ∈ {}, {})", symbol, index, iterable, body)
+            }
+            Node::Integral(var, start, end, body) => {
+                write!(f, "∫({}, {}, {}, {})", start, end, body, var)
+            }
             Node::Statements(nodes) => write!(
                 f,
                 "{{\n  {}\n}}",
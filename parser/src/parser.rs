@@ -1,15 +1,161 @@
 use std::{iter::Peekable, rc::Rc, vec::IntoIter};
 
-use crate::{BinaryOp, Node, UnaryOp};
+use crate::{BinaryOp, Node, Spanned, UnaryOp};
 use common::SpectreError;
-use lexer::{Token, TokenType};
+use lexer::{StrPart, Token, TokenType};
 use TokenType::*;
 
-type ParseResult = Result<Node, SpectreError>;
+type ParseResult = Result<Spanned<Node>, SpectreError>;
+
+/// Binding power of prefix `not`, whose operand may contain comparisons but
+/// not `and` or `or`.
+const NOT_BP: u8 = 5;
+/// Left binding power of `+` and `-`, the loosest arithmetic operators.
+const ADDITIVE_BP: u8 = 9;
+/// Left binding power of explicit multiplicative operators like `*` and `/`.
+const MULTIPLICATIVE_BP: u8 = 11;
+/// Left binding power of `..` and `..=`, between comparisons and arithmetic
+/// so that `x ∈ 1..n+1` needs no parentheses.
+const RANGE_BP: u8 = 8;
+/// Binding power of prefix `+` and `-`; only `^` and postfix operators bind
+/// tighter, so `-x^2` is `-(x^2)` and `-2x` is `(-2)x`.
+const SIGN_BP: u8 = 15;
+
+/// Returns the left and right binding powers of the infix operator starting
+/// at `ty`. Higher powers bind tighter. An operator whose left power is below
+/// its right power is left-associative; the reverse makes it right-associative.
+fn infix_binding_power(ty: &TokenType) -> Option<(u8, u8, BinaryOp)> {
+    Some(match ty {
+        // Implication is looser than `or` and right-associative, so
+        // `a or b → c → d` is `(a or b) → (c → d)`
+        Implies => (1, 0, BinaryOp::Implies),
+        Or => (1, 2, BinaryOp::Or),
+        And => (3, 4, BinaryOp::And),
+        EqEq => (7, 8, BinaryOp::EqEq),
+        Neq => (7, 8, BinaryOp::Neq),
+        Lt => (7, 8, BinaryOp::Lt),
+        Lte => (7, 8, BinaryOp::Lte),
+        Gt => (7, 8, BinaryOp::Gt),
+        Gte => (7, 8, BinaryOp::Gte),
+        Approx => (7, 8, BinaryOp::Approx),
+        In => (7, 8, BinaryOp::In),
+        NotIn => (7, 8, BinaryOp::NotIn),
+        Plus => (ADDITIVE_BP, 10, BinaryOp::Add),
+        Minus => (ADDITIVE_BP, 10, BinaryOp::Sub),
+        Star => (MULTIPLICATIVE_BP, 12, BinaryOp::Mul),
+        Dot => (MULTIPLICATIVE_BP, 12, BinaryOp::Dot),
+        Cross => (MULTIPLICATIVE_BP, 12, BinaryOp::Cross),
+        Slash | Divide => (MULTIPLICATIVE_BP, 12, BinaryOp::Div),
+        Percent => (MULTIPLICATIVE_BP, 12, BinaryOp::Rem),
+        // Juxtaposition, as in `2x` or `n fact(n - 1)`, binds tighter than an
+        // explicit operator so that `1/2x` is `1/(2x)`
+        ty if starts_operand(ty) => (13, 14, BinaryOp::Mul),
+        Carrot => (18, 17, BinaryOp::Pow),
+        _ => return None,
+    })
+}
+
+/// The source text of a token lexed from a subscript, which only has
+/// names, numbers and the symbols of [`lexer::subscript`].
+fn source_text(token: &Token) -> String {
+    match &token.ty {
+        Identifier(name) => name.to_string(),
+        Int(x) => x.to_string(),
+        BigInt(x) => x.to_string(),
+        Plus => "+".to_string(),
+        Minus => "-".to_string(),
+        Eq => "=".to_string(),
+        EqEq => "==".to_string(),
+        LeftParen => "(".to_string(),
+        RightParen => ")".to_string(),
+        ty => ty.to_string(),
+    }
+}
+
+/// Whether `ty` can begin an operand of an implicit multiplication.
+fn starts_operand(ty: &TokenType) -> bool {
+    matches!(
+        ty,
+        Int(_)
+            | BigInt(_)
+            | Float(_)
+            | Identifier(_)
+            | LeftParen
+            | LeftAngle
+            | LeftBracket
+            | Sqrt
+            | Cbrt
+            | Fort
+            | Sum
+            | Product
+            | Integral
+            | LeftFloor
+            | LeftCeil
+    )
+}
+
+/// Whether `node` can be indexed with brackets right after it. Numbers can't,
+/// so that `2[1, 2; 3, 4]` is a product.
+fn indexable(node: &Node) -> bool {
+    !matches!(node, Node::Int(_) | Node::BigInt(_) | Node::Float(_))
+}
+
+/// Whether a superscript is `ᵀ`, which transposes rather than raises.
+fn is_transpose(tokens: &[Token]) -> bool {
+    matches!(tokens, [Token { ty: Identifier(name), .. }] if &**name == "T")
+}
+
+/// The range a `∑` or `∏` takes its index over between two bounds, both
+/// included.
+fn bounds(lower: Spanned<Node>, upper: Spanned<Node>) -> Box<Spanned<Node>> {
+    let range = lower.range.start..upper.range.end;
+    let node = Node::Range(Box::new(lower), Box::new(upper), None, true);
+    Box::new(Spanned::new(node, range))
+}
+
+/// Splits the differential, such as the `dx` in `x² dx`, off the end of an
+/// integrand, returning what's left and the variable it names.
+fn differential(node: Spanned<Node>) -> Option<(Spanned<Node>, Rc<str>)> {
+    let variable = |node: &Node| match node {
+        Node::Identifier(name) if name.len() > 1 && name.starts_with('d') => Some(name[1..].into()),
+        _ => None,
+    };
+    if let Some(var) = variable(&node.node) {
+        return Some((Spanned::new(Node::Int(1), node.range), var));
+    }
+    match node.node {
+        Node::Binary(left, BinaryOp::Mul, right) if variable(&right.node).is_some() => {
+            Some((*left, variable(&right.node)?))
+        }
+        Node::Binary(
+            left,
+            op @ (BinaryOp::Mul | BinaryOp::Div | BinaryOp::Dot | BinaryOp::Cross),
+            right,
+        ) => {
+            let (right, var) = differential(*right)?;
+            let range = left.range.start..right.range.end;
+            Some((
+                Spanned::new(Node::Binary(left, op, Box::new(right)), range),
+                var,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// What `break`, `continue` and `return` can leave where the parser is.
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    in_loop: bool,
+    in_function: bool,
+}
 
 pub struct Parser {
     tokens: Peekable<IntoIter<Token>>,
     token: Token,
+    /// Where the most recently consumed token ended.
+    end: usize,
+    context: Context,
 }
 
 impl Parser {
@@ -21,6 +167,8 @@ impl Parser {
                 range: Default::default(),
             }),
             tokens: iter,
+            end: 0,
+            context: Context::default(),
         }
     }
 
@@ -32,12 +180,19 @@ impl Parser {
     }
 
     fn advance(&mut self) {
+        self.end = self.token.range.end;
         self.token = self.tokens.next().unwrap_or(Token {
             ty: EOF,
-            range: Default::default(),
+            range: self.end..self.end,
         });
     }
 
+    /// Wraps `node` with the range from `start` to the end of the last
+    /// consumed token.
+    fn spanned(&self, node: Node, start: usize) -> Spanned<Node> {
+        Spanned::new(node, start..self.end.max(start))
+    }
+
     fn error<T>(&self, msg: String, reason: String, start: usize) -> Result<T, SpectreError> {
         Err(SpectreError {
             msg,
@@ -46,6 +201,37 @@ impl Parser {
         })
     }
 
+    /// Parses with `f` in `context`, restoring the current context afterwards.
+    fn within(
+        &mut self,
+        context: Context,
+        f: impl FnOnce(&mut Self) -> ParseResult,
+    ) -> ParseResult {
+        let outer = std::mem::replace(&mut self.context, context);
+        let result = f(self);
+        self.context = outer;
+        result
+    }
+
+    /// Parses the body of a function, which `return` can leave but which
+    /// isn't in any loop around it.
+    fn function_body(&mut self) -> ParseResult {
+        let context = Context {
+            in_loop: false,
+            in_function: true,
+        };
+        self.within(context, Self::expr)
+    }
+
+    /// Parses the block of a loop, which `break` and `continue` can leave.
+    fn loop_body(&mut self) -> ParseResult {
+        let context = Context {
+            in_loop: true,
+            ..self.context
+        };
+        self.within(context, Self::block)
+    }
+
     fn skip_newlines(&mut self) -> u32 {
         let mut newlines = 0u32;
         while self.token.ty == Newline {
@@ -60,32 +246,26 @@ impl Parser {
     }
 
     fn statements(&mut self) -> ParseResult {
-        let mut statements: Vec<Node> = vec![];
+        let mut statements: Vec<Spanned<Node>> = vec![];
         self.skip_newlines();
+        let start = self.token.range.start;
 
-        statements.push(self.statement()?);
-
-        let mut more_statements = true;
+        // A program of nothing but newlines does nothing
+        if self.token.ty == EOF {
+            statements.push(self.spanned(Node::Eof, start));
+        } else {
+            statements.push(self.statement()?);
+        }
 
         loop {
             let newlines = self.skip_newlines();
-            if newlines == 0 {
-                more_statements = false;
-            }
-
-            if !more_statements || self.token.ty == RightBrace {
+            if newlines == 0 || matches!(self.token.ty, RightBrace | EOF) {
                 break;
             }
-
-            let statement = self.statement()?;
-            if statement == Node::Eof {
-                more_statements = false;
-                continue;
-            }
-            statements.push(statement);
+            statements.push(self.statement()?);
         }
 
-        Ok(Node::Statements(statements))
+        Ok(self.spanned(Node::Statements(statements), start))
     }
 
     pub fn statement(&mut self) -> ParseResult {
@@ -93,273 +273,218 @@ impl Parser {
     }
 
     fn expr(&mut self) -> ParseResult {
+        let start = self.token.range.start;
         match (self.token.ty.clone(), self.peek()) {
             (Identifier(name), Eq) => {
                 self.advance();
                 self.advance();
-                Ok(Node::Assignment(name, Box::new(self.or_expr()?)))
+                let value = self.expr()?;
+                Ok(self.spanned(Node::Assignment(name, Box::new(value)), start))
             }
-            _ => self.or_expr(),
-        }
-    }
-
-    fn or_expr(&mut self) -> ParseResult {
-        let result = self.and_expr()?;
-
-        match self.token.ty {
-            Or => {
+            (Identifier(name), MapsTo | FatArrow) => {
                 self.advance();
-                Ok(Node::Binary(
-                    Box::new(result),
-                    BinaryOp::Or,
-                    Box::new(self.or_expr()?),
-                ))
-            }
-            _ => Ok(result),
-        }
-    }
-
-    fn and_expr(&mut self) -> ParseResult {
-        let result = self.not_expr()?;
-
-        match self.token.ty {
-            And => {
                 self.advance();
-                Ok(Node::Binary(
-                    Box::new(result),
-                    BinaryOp::And,
-                    Box::new(self.and_expr()?),
-                ))
+                let body = self.function_body()?;
+                Ok(self.spanned(Node::Lambda(vec![name], Box::new(body)), start))
             }
-            _ => Ok(result),
-        }
-    }
-
-    fn not_expr(&mut self) -> ParseResult {
-        match self.token.ty {
-            Not => {
-                self.advance();
-                Ok(Node::Unary(UnaryOp::Not, Box::new(self.not_expr()?)))
+            _ => {
+                let result = self.binary(0)?;
+                // A subscripted name is only known to be one once its
+                // subscript has been parsed
+                match (&result.node, &self.token.ty) {
+                    (Node::Subscript(name, ..), Eq) => {
+                        let name = Rc::clone(name);
+                        self.advance();
+                        let value = self.expr()?;
+                        Ok(self.spanned(Node::Assignment(name, Box::new(value)), start))
+                    }
+                    (Node::Subscript(name, ..), MapsTo | FatArrow) => {
+                        let name = Rc::clone(name);
+                        self.advance();
+                        let body = self.function_body()?;
+                        Ok(self.spanned(Node::Lambda(vec![name], Box::new(body)), start))
+                    }
+                    _ => Ok(result),
+                }
             }
-            _ => self.comp_expr(),
         }
     }
 
-    fn comp_expr(&mut self) -> ParseResult {
-        let result = self.arith_expr()?;
+    /// Parses a chain of infix operators whose left binding power is at least
+    /// `min_bp`, by precedence climbing over [`infix_binding_power`].
+    fn binary(&mut self, min_bp: u8) -> ParseResult {
+        let mut result = self.unary()?;
 
-        macro_rules! comp_expr {
-            ($($token:tt),*) => {
-                match self.token.ty {
-                    $(
-                        $token => {
-                            self.advance();
-                            Ok(Node::Binary(Box::new(result), BinaryOp::$token, Box::new(self.comp_expr()?)))
-                        },
-                    )*
-                    _ => Ok(result),
+        loop {
+            if let DotDot | DotDotEq = self.token.ty {
+                if RANGE_BP < min_bp {
+                    break;
                 }
+                result = self.range(result)?;
+                continue;
+            }
+            let Some((left_bp, right_bp, op)) = infix_binding_power(&self.token.ty) else {
+                break;
             };
-        }
-
-        comp_expr!(EqEq, Neq, Lt, Lte, Gt, Gte)
-    }
-
-    fn arith_expr(&mut self) -> ParseResult {
-        let result = self.term()?;
-
-        Ok(match self.token.ty {
-            Plus => {
-                self.advance();
-                Node::Binary(
-                    Box::new(result),
-                    BinaryOp::Add,
-                    Box::new(self.arith_expr()?),
-                )
+            if left_bp < min_bp {
+                break;
             }
-            Minus => {
+            if !starts_operand(&self.token.ty) {
                 self.advance();
-                Node::Binary(
-                    Box::new(result),
-                    BinaryOp::Sub,
-                    Box::new(self.arith_expr()?),
-                )
             }
-            _ => result,
-        })
-    }
+            let right = self.binary(right_bp)?;
+            let range = result.range.start..right.range.end;
+            result = Spanned::new(Node::Binary(Box::new(result), op, Box::new(right)), range);
+        }
 
-    fn term(&mut self) -> ParseResult {
-        if matches!(self.token.ty, Int(_) | Float(_))
-            && !matches!(
-                self.peek(),
-                Int(_)
-                    | Float(_)
-                    | Superscript(_)
-                    | Plus
-                    | Minus
-                    | Star
-                    | Dot
-                    | Cross
-                    | Slash
-                    | Divide
-                    | Percent
-                    | Carrot
-                    | Not
-                    | EqEq
-                    | Neq
-                    | Lt
-                    | Lte
-                    | Gt
-                    | Gte
-                    | And
-                    | Or
-                    | RightParen
-                    | LeftBrace
-                    | RightBrace
-                    | Pipe
-                    | RightFloor
-                    | RightCeil
-                    | Newline
-                    | EOF
-            )
-        {
-            return Ok(Node::Binary(
-                Box::new(self.atom()?),
-                BinaryOp::Mul,
-                Box::new(self.term()?),
-            ));
-        }
-
-        let result = self.factor()?;
-
-        Ok(match self.token.ty {
-            Star | Dot | Cross => {
-                self.advance();
-                Node::Binary(Box::new(result), BinaryOp::Mul, Box::new(self.term()?))
-            }
-            Slash | Divide => {
-                self.advance();
-                Node::Binary(Box::new(result), BinaryOp::Div, Box::new(self.term()?))
-            }
-            Percent => {
-                self.advance();
-                Node::Binary(Box::new(result), BinaryOp::Rem, Box::new(self.term()?))
-            }
-            _ => result,
-        })
+        Ok(result)
     }
 
-    fn factor(&mut self) -> ParseResult {
-        Ok(match self.token.ty {
-            Plus => {
-                self.advance();
-                Node::Unary(UnaryOp::Pos, Box::new(self.factor()?))
-            }
-            Minus => {
+    /// Parses the rest of a range starting with `start`, at its `..` or `..=`.
+    fn range(&mut self, start: Spanned<Node>) -> ParseResult {
+        let inclusive = self.token.ty == DotDotEq;
+        self.advance();
+        let end = self.binary(RANGE_BP + 1)?;
+        let step = match self.token.ty {
+            Step => {
                 self.advance();
-                Node::Unary(UnaryOp::Neg, Box::new(self.factor()?))
+                Some(Box::new(self.binary(RANGE_BP + 1)?))
             }
-            _ => self.power()?,
-        })
+            _ => None,
+        };
+        let range = start.range.start..self.end;
+        Ok(Spanned::new(
+            Node::Range(Box::new(start), Box::new(end), step, inclusive),
+            range,
+        ))
     }
 
-    fn power(&mut self) -> ParseResult {
-        let result = self.prefix()?;
+    fn unary(&mut self) -> ParseResult {
+        let start = self.token.range.start;
+        let op = match self.token.ty {
+            Not => UnaryOp::Not,
+            Plus => UnaryOp::Pos,
+            Minus => UnaryOp::Neg,
+            _ => return self.prefix(),
+        };
+        self.advance();
 
-        Ok(match self.token.ty {
-            Carrot => {
-                self.advance();
-                Node::Binary(Box::new(result), BinaryOp::Pow, Box::new(self.factor()?))
-            }
-            _ => result,
-        })
+        let bp = match op {
+            UnaryOp::Not => NOT_BP,
+            _ => SIGN_BP,
+        };
+        let operand = self.binary(bp)?;
+        Ok(self.spanned(Node::Unary(op, Box::new(operand)), start))
     }
 
     fn prefix(&mut self) -> ParseResult {
-        Ok(match self.token.ty {
-            Sqrt => {
-                self.advance();
-                Node::Unary(UnaryOp::Sqrt, Box::new(self.prefix()?))
-            }
-            Cbrt => {
-                self.advance();
-                Node::Unary(UnaryOp::Cbrt, Box::new(self.prefix()?))
-            }
-            Fort => {
-                self.advance();
-                Node::Unary(UnaryOp::Fort, Box::new(self.prefix()?))
-            }
-            _ => self.postfix()?,
-        })
+        let start = self.token.range.start;
+        let op = match self.token.ty {
+            Sqrt => UnaryOp::Sqrt,
+            Cbrt => UnaryOp::Cbrt,
+            Fort => UnaryOp::Fort,
+            _ => return self.postfix(),
+        };
+        self.advance();
+
+        let operand = self.prefix()?;
+        Ok(self.spanned(Node::Unary(op, Box::new(operand)), start))
     }
 
     fn postfix(&mut self) -> ParseResult {
-        let result = self.call()?;
+        let mut result = self.call()?;
 
-        Ok(match self.token.ty.clone() {
-            Exclamation => {
-                self.advance();
-                Node::Unary(UnaryOp::Fact, Box::new(result))
-            }
-            Degree => {
-                self.advance();
-                Node::Unary(UnaryOp::Degree, Box::new(result))
-            }
-            Superscript(tokens) => {
-                self.advance();
-                Node::Binary(
+        loop {
+            let range = result.range.start..self.token.range.end;
+            let node = match self.token.ty.clone() {
+                Exclamation => Node::Unary(UnaryOp::Fact, Box::new(result)),
+                Degree => Node::Unary(UnaryOp::Degree, Box::new(result)),
+                Superscript(tokens) if is_transpose(&tokens) => {
+                    Node::Unary(UnaryOp::Transpose, Box::new(result))
+                }
+                Superscript(tokens) => Node::Binary(
                     Box::new(result),
                     BinaryOp::Pow,
-                    Box::new(Parser::new(tokens).arith_expr()?),
-                )
-            }
-            _ => result,
-        })
+                    Box::new(Self::script_expr(tokens, "exponent")?),
+                ),
+                // Brackets right after an operand index it, while with space
+                // between, as in `2 [1, 2]`, they multiply it
+                LeftBracket if self.token.range.start == self.end && indexable(&result.node) => {
+                    self.advance();
+                    let index = self.expr()?;
+                    self.expect(RightBracket, range.start)?;
+                    let range = range.start..self.end;
+                    result = Spanned::new(Node::Index(Box::new(result), Box::new(index)), range);
+                    continue;
+                }
+                _ => return Ok(result),
+            };
+            result = Spanned::new(node, range);
+            self.advance();
+        }
     }
 
     fn call(&mut self) -> ParseResult {
         let start = self.token.range.start;
-        let result = self.atom()?;
+        let mut result = self.atom()?;
 
-        match self.token.ty {
-            LeftParen => {
-                let list_start = self.token.range.start;
-                let name = match result {
-                    Node::Identifier(ref name) => Rc::clone(name),
-                    _ => panic!("expected identifier"),
-                };
+        loop {
+            // Primes bind tighter than calls so that `f′(x)` calls `f′`
+            if self.token.ty == Prime {
+                self.advance();
+                result = self.spanned(Node::Unary(UnaryOp::Prime, Box::new(result)), start);
+                continue;
+            }
+            // So do subscripts, so that `log₂(8)` calls `log₂`
+            if let Subscript(tokens) = self.token.ty.clone() {
+                let index = Box::new(Self::script_expr(tokens.clone(), "subscript")?);
                 self.advance();
+                let node = match &result.node {
+                    Node::Identifier(name) => {
+                        let text: String = tokens.iter().map(source_text).collect();
+                        let full_name = format!("{}{}", name, lexer::subscript(&text));
+                        Node::Subscript(full_name.into(), Rc::clone(name), index)
+                    }
+                    _ => Node::Index(Box::new(result), index),
+                };
+                result = self.spanned(node, start);
+                continue;
+            }
+            let callable = matches!(
+                result.node,
+                Node::Identifier(_)
+                    | Node::Subscript(..)
+                    | Node::Index(..)
+                    | Node::Call(..)
+                    | Node::Lambda(..)
+                    | Node::Unary(UnaryOp::Prime, _)
+            );
+            if self.token.ty != LeftParen || !callable {
+                break;
+            }
 
-                let args = self.list(list_start, RightParen)?;
+            let list_start = self.token.range.start;
+            self.advance();
 
-                match self.token.ty {
-                    Eq => {
-                        self.advance();
-                        let body = self.expr()?;
-                        Ok(Node::FnDef(
-                            name,
-                            args.into_iter()
-                                .map(|node| match node {
-                                    Node::Identifier(name) => name,
-                                    _ => panic!("expected identifier"),
-                                })
-                                .collect(),
-                            Box::new(body),
-                        ))
-                    }
-                    _ => match result {
-                        Node::Identifier(name) => Ok(Node::Call(name, args)),
-                        _ => self.error(
-                            "expected token".to_string(),
-                            "there should be an identifier here".to_string(),
-                            start,
-                        ),
-                    },
-                }
+            let args = match &result.node {
+                Node::Identifier(name) if &**name == "solve" => self.solve_args(list_start)?,
+                _ => self.list(list_start, RightParen)?,
+            };
+
+            if let (Node::Identifier(name) | Node::Subscript(name, ..), Eq) =
+                (&result.node, &self.token.ty)
+            {
+                let name = Rc::clone(name);
+                let params = self.params(args, list_start)?;
+                self.advance();
+                let body = self.function_body()?;
+                return Ok(self.spanned(Node::FnDef(name, params, Box::new(body)), start));
             }
-            _ => Ok(result),
+
+            result = self.spanned(Node::Call(Box::new(result), args), start);
         }
+
+        Ok(result)
     }
 
     fn atom(&mut self) -> ParseResult {
@@ -368,30 +493,102 @@ impl Parser {
         match self.token.ty.clone() {
             Int(x) => {
                 self.advance();
-                Ok(Node::Int(x))
+                Ok(self.spanned(Node::Int(x), start))
+            }
+            BigInt(x) => {
+                self.advance();
+                Ok(self.spanned(Node::BigInt(x), start))
             }
             Float(x) => {
                 self.advance();
-                Ok(Node::Float(x))
+                Ok(self.spanned(Node::Float(x), start))
             }
             Identifier(name) => {
                 self.advance();
-                Ok(Node::Identifier(name))
+                Ok(self.spanned(Node::Identifier(name), start))
+            }
+            Str(parts) => {
+                self.advance();
+                let node = match &parts[..] {
+                    [StrPart::Text(text)] => Node::Str(Rc::clone(text)),
+                    _ => Node::Interpolation(
+                        parts
+                            .into_iter()
+                            .map(|part| match part {
+                                StrPart::Text(text) => Ok(self.spanned(Node::Str(text), start)),
+                                StrPart::Expr(tokens) => {
+                                    let mut parser = Parser::new(tokens);
+                                    parser.context = self.context;
+                                    parser.interpolated()
+                                }
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ),
+                };
+                Ok(self.spanned(node, start))
             }
             LeftParen => {
                 self.advance();
-                let result = self.expr()?;
+                let mut nodes = self.list(start, RightParen)?;
 
-                if self.token.ty != RightParen {
-                    return self.error(
+                match self.token.ty {
+                    MapsTo | FatArrow => {
+                        let params = self.params(nodes, start)?;
+                        self.advance();
+                        let body = self.function_body()?;
+                        Ok(self.spanned(Node::Lambda(params, Box::new(body)), start))
+                    }
+                    _ if nodes.len() == 1 => Ok(nodes.remove(0)),
+                    _ => self.error(
                         "expected token".to_string(),
-                        format!("expected {}", RightParen),
+                        format!("expected {} after parameter list", MapsTo),
                         start,
-                    );
+                    ),
+                }
+            }
+            LeftAngle => {
+                self.advance();
+                let nodes = self.list(start, RightAngle)?;
+                Ok(self.spanned(Node::Vector(nodes), start))
+            }
+            LeftBracket => {
+                self.advance();
+                self.skip_newlines();
+
+                // Brackets hold a matrix if they have more than one row, and
+                // a list otherwise. Newlines and `;` only separate rows, so
+                // ones before the closing bracket don't count.
+                let mut rows = vec![];
+                while self.token.ty != RightBracket {
+                    let mut row = vec![self.expr()?];
+                    while self.token.ty == Comma {
+                        self.advance();
+                        row.push(self.expr()?);
+                    }
+                    rows.push(row);
+
+                    match self.token.ty {
+                        Newline => {
+                            self.skip_newlines();
+                        }
+                        RightBracket => {}
+                        _ => {
+                            return self.error(
+                                "expected token".to_string(),
+                                format!("expected {}, {}, or {}", Comma, Newline, RightBracket),
+                                start,
+                            )
+                        }
+                    }
                 }
                 self.advance();
 
-                Ok(result)
+                let node = match rows.len() {
+                    0 => Node::List(vec![]),
+                    1 => Node::List(rows.pop().unwrap()),
+                    _ => Node::Matrix(rows),
+                };
+                Ok(self.spanned(node, start))
             }
             Pipe => {
                 self.advance();
@@ -406,7 +603,7 @@ impl Parser {
                 }
                 self.advance();
 
-                Ok(Node::Unary(UnaryOp::Abs, Box::new(result)))
+                Ok(self.spanned(Node::Unary(UnaryOp::Abs, Box::new(result)), start))
             }
             LeftFloor => {
                 self.advance();
@@ -415,11 +612,11 @@ impl Parser {
                 match self.token.ty {
                     RightFloor => {
                         self.advance();
-                        Ok(Node::Unary(UnaryOp::Floor, Box::new(result)))
+                        Ok(self.spanned(Node::Unary(UnaryOp::Floor, Box::new(result)), start))
                     }
                     RightCeil => {
                         self.advance();
-                        Ok(Node::Unary(UnaryOp::Abs, Box::new(result)))
+                        Ok(self.spanned(Node::Unary(UnaryOp::Abs, Box::new(result)), start))
                     }
                     _ => self.error(
                         "expected token".to_string(),
@@ -441,23 +638,252 @@ impl Parser {
                 }
                 self.advance();
 
-                Ok(Node::Unary(UnaryOp::Ceil, Box::new(result)))
+                Ok(self.spanned(Node::Unary(UnaryOp::Ceil, Box::new(result)), start))
             }
+            LeftBrace => self.block(),
+            Sum | Product => self.big_operator(),
+            Integral => self.integral(),
             If => self.if_expr(),
             While => self.while_expr(),
-            EOF => Ok(Node::Eof),
+            For => self.for_expr(),
+            Break | Continue => self.jump(),
+            Return => self.return_expr(),
             _ => self.error(
                 "expected token".to_string(),
                 format!(
-                    "expected int, float, identifier, {}, {}, {}, {}, or {}",
-                    LeftParen, Pipe, LeftFloor, LeftCeil, If
+                    "expected int, float, string, identifier, {}, {}, {}, {}, {}, {}, or {}",
+                    LeftParen, LeftAngle, LeftBracket, Pipe, LeftFloor, LeftCeil, If
                 ),
                 start,
             ),
         }
     }
 
+    /// Parses the whole of an expression interpolated in a string.
+    fn interpolated(mut self) -> ParseResult {
+        let start = self.token.range.start;
+        let result = self.expr()?;
+        if self.token.ty != EOF {
+            return self.error(
+                "expected token".to_string(),
+                "expected the end of the interpolated expression".to_string(),
+                start,
+            );
+        }
+        Ok(result)
+    }
+
+    /// Parses the whole of a subscript or superscript as an expression,
+    /// calling it `what` if anything is left over.
+    fn script_expr(tokens: Vec<Token>, what: &str) -> ParseResult {
+        let mut parser = Parser::new(tokens);
+        let start = parser.token.range.start;
+        let result = parser.binary(0)?;
+        if parser.token.ty != EOF {
+            return parser.error(
+                "expected token".to_string(),
+                format!("expected the end of the {}", what),
+                start,
+            );
+        }
+        Ok(result)
+    }
+
+    fn big_operator(&mut self) -> ParseResult {
+        let start = self.token.range.start;
+        let op = match self.token.ty {
+            Sum => BinaryOp::Add,
+            _ => BinaryOp::Mul,
+        };
+        self.advance();
+
+        if let Subscript(tokens) = self.token.ty.clone() {
+            self.advance();
+            return self.scripted_big_operator(op, tokens, start);
+        }
+
+        self.expect(LeftParen, start)?;
+        let index = match self.token.ty.clone() {
+            Identifier(name) => name,
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    "expected identifier".to_string(),
+                    start,
+                )
+            }
+        };
+        self.advance();
+        if self.token.ty == In {
+            self.advance();
+            let iterable = self.expr()?;
+            self.expect(Comma, start)?;
+            // Terms are evaluated on their own, so nothing can leave them early
+            let body = self.within(Context::default(), Self::expr)?;
+            self.expect(RightParen, start)?;
+
+            let node = Node::BigOperator(op, index, Box::new(iterable), Box::new(body));
+            return Ok(self.spanned(node, start));
+        }
+        self.expect(Eq, start)?;
+        let lower = self.expr()?;
+        self.expect(Comma, start)?;
+        let upper = self.expr()?;
+        self.expect(Comma, start)?;
+        let body = self.within(Context::default(), Self::expr)?;
+        self.expect(RightParen, start)?;
+
+        let node = Node::BigOperator(op, index, bounds(lower, upper), Box::new(body));
+        Ok(self.spanned(node, start))
+    }
+
+    /// Parses the rest of a `∑` or `∏` bounded by scripts, as in `∑ₖ₌₁ⁿ k²`,
+    /// after its subscript. The upper bound may also follow a `^`, and the
+    /// body ends at the first `+` or `-`, as an integrand does.
+    fn scripted_big_operator(
+        &mut self,
+        op: BinaryOp,
+        subscript: Vec<Token>,
+        start: usize,
+    ) -> ParseResult {
+        let mut parser = Parser::new(subscript);
+        let index = match parser.token.ty.clone() {
+            Identifier(name) => name,
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    "expected the index in the subscript, as in ∑ₖ₌₁ⁿ".to_string(),
+                    start,
+                )
+            }
+        };
+        parser.advance();
+        parser.expect(Eq, start)?;
+        let lower = parser.binary(0)?;
+        if parser.token.ty != EOF {
+            return parser.error(
+                "expected token".to_string(),
+                "expected the end of the lower bound".to_string(),
+                start,
+            );
+        }
+
+        let upper = match self.token.ty.clone() {
+            Superscript(tokens) => {
+                self.advance();
+                Self::script_expr(tokens, "upper bound")?
+            }
+            Carrot => {
+                self.advance();
+                self.postfix()?
+            }
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    format!("expected {} or a superscript upper bound", Carrot),
+                    start,
+                )
+            }
+        };
+        let body = self.within(Context::default(), |parser| {
+            parser.binary(MULTIPLICATIVE_BP)
+        })?;
+
+        let node = Node::BigOperator(op, index, bounds(lower, upper), Box::new(body));
+        Ok(self.spanned(node, start))
+    }
+
+    /// Parses either `∫(a, b, body, x)` or `∫ₐ^b body dx`, where the upper
+    /// bound may also be a superscript.
+    fn integral(&mut self) -> ParseResult {
+        let start = self.token.range.start;
+        self.advance();
+
+        if self.token.ty == LeftParen {
+            self.advance();
+            let lower = self.expr()?;
+            self.expect(Comma, start)?;
+            let upper = self.expr()?;
+            self.expect(Comma, start)?;
+            let body = self.within(Context::default(), Self::expr)?;
+            self.expect(Comma, start)?;
+            let var = match self.token.ty.clone() {
+                Identifier(name) => name,
+                _ => {
+                    return self.error(
+                        "expected token".to_string(),
+                        "expected identifier".to_string(),
+                        start,
+                    )
+                }
+            };
+            self.advance();
+            self.expect(RightParen, start)?;
+
+            let node = Node::Integral(var, Box::new(lower), Box::new(upper), Box::new(body));
+            return Ok(self.spanned(node, start));
+        }
+
+        let lower = match self.token.ty.clone() {
+            Subscript(tokens) => {
+                self.advance();
+                Self::script_expr(tokens, "lower bound")?
+            }
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    format!("expected {} or a subscript lower bound", LeftParen),
+                    start,
+                )
+            }
+        };
+        let upper = match self.token.ty.clone() {
+            Superscript(tokens) => {
+                self.advance();
+                Self::script_expr(tokens, "upper bound")?
+            }
+            Carrot => {
+                self.advance();
+                self.postfix()?
+            }
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    format!("expected {} or a superscript upper bound", Carrot),
+                    start,
+                )
+            }
+        };
+        // The integrand ends at the first `+` or `-`, as in `∫₀¹ x dx + 1`
+        let body = self.within(Context::default(), |parser| {
+            parser.binary(MULTIPLICATIVE_BP)
+        })?;
+        let Some((body, var)) = differential(body) else {
+            return self.error(
+                "expected token".to_string(),
+                "expected the integrand to end with a differential such as dx".to_string(),
+                start,
+            );
+        };
+
+        let node = Node::Integral(var, Box::new(lower), Box::new(upper), Box::new(body));
+        Ok(self.spanned(node, start))
+    }
+
+    fn expect(&mut self, ty: TokenType, start: usize) -> Result<(), SpectreError> {
+        if self.token.ty != ty {
+            return self.error(
+                "expected token".to_string(),
+                format!("expected {}", ty),
+                start,
+            );
+        }
+        self.advance();
+        Ok(())
+    }
+
     fn if_expr(&mut self) -> ParseResult {
+        let start = self.token.range.start;
         self.advance();
 
         let condition = self.expr()?;
@@ -472,14 +898,21 @@ impl Parser {
 
         let body = self.block()?;
 
-        self.skip_newlines();
+        // The newline only belongs to the `if` if an `else` follows it,
+        // otherwise it ends the statement
+        if self.token.ty == Newline && *self.peek() == Else {
+            self.skip_newlines();
+        }
 
-        let mut else_case: Option<Box<Node>> = None;
+        let mut else_case: Option<Box<Spanned<Node>>> = None;
         if self.token.ty == Else {
             else_case = Some(Box::new(self.else_expr()?));
         }
 
-        Ok(Node::If(Box::new(condition), Box::new(body), else_case))
+        Ok(self.spanned(
+            Node::If(Box::new(condition), Box::new(body), else_case),
+            start,
+        ))
     }
 
     fn else_expr(&mut self) -> ParseResult {
@@ -497,6 +930,7 @@ impl Parser {
     }
 
     fn while_expr(&mut self) -> ParseResult {
+        let start = self.token.range.start;
         self.advance();
 
         let condition = self.expr()?;
@@ -509,13 +943,84 @@ impl Parser {
             );
         }
 
-        let body = self.block()?;
+        let body = self.loop_body()?;
 
-        Ok(Node::While(Box::new(condition), Box::new(body)))
+        Ok(self.spanned(Node::While(Box::new(condition), Box::new(body)), start))
     }
 
-    fn list(&mut self, start: usize, end: TokenType) -> Result<Vec<Node>, SpectreError> {
-        let mut nodes: Vec<Node> = vec![];
+    /// Parses `for x in xs { ... }`, where `∈` may stand for `in`.
+    fn for_expr(&mut self) -> ParseResult {
+        let start = self.token.range.start;
+        self.advance();
+
+        let var = match self.token.ty.clone() {
+            Identifier(name) => name,
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    "expected identifier".to_string(),
+                    start,
+                )
+            }
+        };
+        self.advance();
+        self.expect(In, start)?;
+        let iterable = self.expr()?;
+
+        if self.token.ty != LeftBrace {
+            return self.error(
+                "expected token".to_string(),
+                format!("expected {}", LeftBrace),
+                self.token.range.start,
+            );
+        }
+
+        let body = self.loop_body()?;
+
+        Ok(self.spanned(Node::For(var, Box::new(iterable), Box::new(body)), start))
+    }
+
+    /// Parses `break` or `continue`, which must be inside a loop.
+    fn jump(&mut self) -> ParseResult {
+        let start = self.token.range.start;
+        let keyword = self.token.ty.clone();
+        if !self.context.in_loop {
+            return self.error(
+                "invalid control flow".to_string(),
+                format!("{} can only be used inside a loop", keyword),
+                start,
+            );
+        }
+        self.advance();
+
+        let node = match keyword {
+            Break => Node::Break,
+            _ => Node::Continue,
+        };
+        Ok(self.spanned(node, start))
+    }
+
+    /// Parses `return`, with a value unless the statement ends after it.
+    fn return_expr(&mut self) -> ParseResult {
+        let start = self.token.range.start;
+        if !self.context.in_function {
+            return self.error(
+                "invalid control flow".to_string(),
+                format!("{} can only be used inside a function", Return),
+                start,
+            );
+        }
+        self.advance();
+
+        let value = match self.token.ty {
+            Newline | RightBrace | RightParen | Comma | EOF => None,
+            _ => Some(Box::new(self.expr()?)),
+        };
+        Ok(self.spanned(Node::Return(value), start))
+    }
+
+    fn list(&mut self, start: usize, end: TokenType) -> Result<Vec<Spanned<Node>>, SpectreError> {
+        let mut nodes: Vec<Spanned<Node>> = vec![];
 
         while self.token.ty != end {
             nodes.push(self.expr()?);
@@ -544,6 +1049,87 @@ impl Parser {
         Ok(nodes)
     }
 
+    /// Parses the arguments of a call to `solve`, which may start with an
+    /// equation and the variable to solve it for: `solve(lhs = rhs, x, ...)` is
+    /// shorthand for `solve(x ↦ lhs - rhs, ...)`.
+    fn solve_args(&mut self, start: usize) -> Result<Vec<Spanned<Node>>, SpectreError> {
+        if self.token.ty == RightParen {
+            return self.list(start, RightParen);
+        }
+
+        // The equation becomes the body of a function, so nothing in it can
+        // leave the loop or function around the call
+        let first = self.within(Context::default(), Self::expr)?;
+        let range = first.range;
+        // `f(x) = y` and `x = y` parse as definitions, but here they're
+        // equations
+        let (lhs, rhs) = match first.node {
+            Node::FnDef(name, params, rhs) => {
+                let lhs_range = range.start..rhs.range.start;
+                let args = params
+                    .into_iter()
+                    .map(|param| Spanned::new(Node::Identifier(param), lhs_range.clone()))
+                    .collect();
+                let function = Spanned::new(Node::Identifier(name), lhs_range.clone());
+                let lhs = Node::Call(Box::new(function), args);
+                (Spanned::new(lhs, lhs_range), *rhs)
+            }
+            Node::Assignment(name, rhs) => {
+                let lhs = Node::Identifier(name);
+                (Spanned::new(lhs, range.start..rhs.range.start), *rhs)
+            }
+            lhs if self.token.ty == Eq => {
+                self.advance();
+                let rhs = self.within(Context::default(), Self::expr)?;
+                (Spanned::new(lhs, range), rhs)
+            }
+            node => {
+                let mut args = vec![Spanned::new(node, range)];
+                if self.token.ty == Comma {
+                    self.advance();
+                }
+                args.extend(self.list(start, RightParen)?);
+                return Ok(args);
+            }
+        };
+
+        self.expect(Comma, start)?;
+        let var = match self.token.ty.clone() {
+            Identifier(name) => name,
+            _ => {
+                return self.error(
+                    "expected token".to_string(),
+                    "expected the variable to solve for".to_string(),
+                    start,
+                )
+            }
+        };
+        self.advance();
+        if self.token.ty == Comma {
+            self.advance();
+        }
+
+        let range = lhs.range.start..rhs.range.end;
+        let body = Node::Binary(Box::new(lhs), BinaryOp::Sub, Box::new(rhs));
+        let function = Node::Lambda(vec![var], Box::new(Spanned::new(body, range.clone())));
+        let mut args = vec![Spanned::new(function, range)];
+        args.extend(self.list(start, RightParen)?);
+        Ok(args)
+    }
+
+    fn params(&self, args: Vec<Spanned<Node>>, start: usize) -> Result<Vec<Rc<str>>, SpectreError> {
+        args.into_iter()
+            .map(|arg| match arg.node {
+                Node::Identifier(name) | Node::Subscript(name, ..) => Ok(name),
+                _ => self.error(
+                    "expected token".to_string(),
+                    "parameters must be identifiers".to_string(),
+                    start,
+                ),
+            })
+            .collect()
+    }
+
     fn block(&mut self) -> ParseResult {
         self.advance();
 
@@ -0,0 +1,274 @@
+use lexer::Lexer;
+use parser::Parser;
+
+/// Parses a single expression and prints it fully parenthesized.
+fn parse(source: &str) -> String {
+    let tokens = match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    };
+    match Parser::new(tokens).statement() {
+        Ok(node) => node.to_string(),
+        Err(e) => panic!("failed to parse {:?}: {}", source, e.reason),
+    }
+}
+
+#[test]
+fn additive_operators_are_left_associative() {
+    assert_eq!(parse("10 - 3 - 2"), "((10 - 3) - 2)");
+    assert_eq!(parse("a + b + c"), "((a + b) + c)");
+    assert_eq!(parse("a - b + c"), "((a - b) + c)");
+    assert_eq!(parse("a + b - c"), "((a + b) - c)");
+}
+
+#[test]
+fn multiplicative_operators_are_left_associative() {
+    assert_eq!(parse("8 / 4 / 2"), "((8 / 4) / 2)");
+    assert_eq!(parse("8 ÷ 4 ÷ 2"), "((8 / 4) / 2)");
+    assert_eq!(parse("a * b * c"), "((a * b) * c)");
+    assert_eq!(parse("a % b % c"), "((a % b) % c)");
+    assert_eq!(parse("a ∙ b ∙ c"), "((a ∙ b) ∙ c)");
+    assert_eq!(parse("a × b × c"), "((a × b) × c)");
+    assert_eq!(parse("a / b * c"), "((a / b) * c)");
+    assert_eq!(parse("a * b / c"), "((a * b) / c)");
+    assert_eq!(parse("a % b * c"), "((a % b) * c)");
+}
+
+#[test]
+fn power_is_right_associative() {
+    assert_eq!(parse("2 ^ 3 ^ 2"), "(2 ^ (3 ^ 2))");
+    assert_eq!(parse("a ^ b ^ c ^ d"), "(a ^ (b ^ (c ^ d)))");
+}
+
+#[test]
+fn comparison_operators_are_left_associative() {
+    assert_eq!(parse("a < b < c"), "((a < b) < c)");
+    assert_eq!(parse("a == b != c"), "((a == b) != c)");
+    assert_eq!(parse("a <= b >= c"), "((a <= b) >= c)");
+    assert_eq!(parse("a > b == c"), "((a > b) == c)");
+    assert_eq!(parse("a ≈ b ∈ c"), "((a ≈ b) ∈ c)");
+}
+
+#[test]
+fn logical_operators_are_left_associative() {
+    assert_eq!(parse("a or b or c"), "((a or b) or c)");
+    assert_eq!(parse("a and b and c"), "((a and b) and c)");
+}
+
+#[test]
+fn multiplication_binds_tighter_than_addition() {
+    assert_eq!(parse("a + b * c"), "(a + (b * c))");
+    assert_eq!(parse("a * b + c"), "((a * b) + c)");
+    assert_eq!(parse("a - b / c"), "(a - (b / c))");
+    assert_eq!(parse("a - b % c"), "(a - (b % c))");
+    assert_eq!(parse("a + b ∙ c"), "(a + (b ∙ c))");
+    assert_eq!(parse("a + b × c"), "(a + (b × c))");
+}
+
+#[test]
+fn power_binds_tighter_than_multiplication() {
+    assert_eq!(parse("a * b ^ c"), "(a * (b ^ c))");
+    assert_eq!(parse("a ^ b * c"), "((a ^ b) * c)");
+    assert_eq!(parse("a / b ^ c"), "(a / (b ^ c))");
+}
+
+#[test]
+fn arithmetic_binds_tighter_than_comparison() {
+    for op in ["==", "!=", "<", "<=", ">", ">=", "≈", "∈", "∉"] {
+        assert_eq!(
+            parse(&format!("a + b {} c * d", op)),
+            format!("((a + b) {} (c * d))", op)
+        );
+    }
+}
+
+#[test]
+fn comparison_binds_tighter_than_logic() {
+    assert_eq!(parse("a < b and c > d"), "((a < b) and (c > d))");
+    assert_eq!(parse("a == b or c != d"), "((a == b) or (c != d))");
+}
+
+#[test]
+fn unicode_operators_mean_their_ascii_spellings() {
+    assert_eq!(parse("a ≠ b"), "(a != b)");
+    assert_eq!(parse("a ≤ b ∧ c ≥ d"), "((a <= b) and (c >= d))");
+    assert_eq!(parse("¬a ∨ b"), "((not a) or b)");
+}
+
+#[test]
+fn implication_binds_loosest_and_is_right_associative() {
+    assert_eq!(parse("a → b → c"), "(a → (b → c))");
+    assert_eq!(parse("a or b → c and d"), "((a or b) → (c and d))");
+    assert_eq!(parse("¬a → b"), "((not a) → b)");
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!(parse("a or b and c"), "(a or (b and c))");
+    assert_eq!(parse("a and b or c"), "((a and b) or c)");
+}
+
+#[test]
+fn not_binds_looser_than_comparison() {
+    assert_eq!(parse("not a == b"), "(not (a == b))");
+    assert_eq!(parse("not a and b"), "((not a) and b)");
+    assert_eq!(parse("not a or b"), "((not a) or b)");
+    assert_eq!(parse("not not a"), "(not (not a))");
+}
+
+#[test]
+fn signs_bind_looser_than_power() {
+    assert_eq!(parse("-a ^ b"), "(-(a ^ b))");
+    assert_eq!(parse("-a * b"), "((-a) * b)");
+    assert_eq!(parse("-a + b"), "((-a) + b)");
+    assert_eq!(parse("+a - b"), "((+a) - b)");
+    assert_eq!(parse("a - -b"), "(a - (-b))");
+    assert_eq!(parse("a ^ -b"), "(a ^ (-b))");
+    assert_eq!(parse("a * -b ^ c"), "(a * (-(b ^ c)))");
+}
+
+#[test]
+fn juxtaposition_binds_tighter_than_explicit_multiplication() {
+    assert_eq!(parse("2x"), "(2 * x)");
+    assert_eq!(parse("1 / 2x"), "(1 / (2 * x))");
+    assert_eq!(parse("2x * y"), "((2 * x) * y)");
+    assert_eq!(parse("a b c"), "((a * b) * c)");
+    assert_eq!(parse("2x + 1"), "((2 * x) + 1)");
+    assert_eq!(parse("-2x"), "((-2) * x)");
+    assert_eq!(parse("n fact(n - 1)"), "(n * fact((n - 1)))");
+}
+
+#[test]
+fn juxtaposition_binds_looser_than_power() {
+    assert_eq!(parse("2x²"), "(2 * (x ^ 2))");
+    assert_eq!(parse("2x ^ 3"), "(2 * (x ^ 3))");
+    assert_eq!(parse("2 ^ x y"), "((2 ^ x) * y)");
+}
+
+#[test]
+fn postfix_operators_bind_tightest() {
+    assert_eq!(parse("-x!"), "(-(x!))");
+    assert_eq!(parse("x²!"), "((x ^ 2)!)");
+    assert_eq!(parse("2 ^ x!"), "(2 ^ (x!))");
+    assert_eq!(parse("a * b°"), "(a * (b°))");
+    assert_eq!(parse("Aᵀ B"), "((Aᵀ) * B)");
+}
+
+#[test]
+fn superscripts_group_their_own_expression() {
+    assert_eq!(parse("xⁿ⁺¹"), "(x ^ (n + 1))");
+    assert_eq!(parse("2ⁿ⁻¹ - 1"), "((2 ^ (n - 1)) - 1)");
+}
+
+#[test]
+fn roots_bind_tighter_than_power() {
+    assert_eq!(parse("√x ^ 2"), "((√x) ^ 2)");
+    assert_eq!(parse("√x²"), "(√(x ^ 2))");
+    assert_eq!(parse("∛x * ∜y"), "((∛x) * (∜y))");
+}
+
+#[test]
+fn parentheses_override_precedence() {
+    assert_eq!(parse("(a + b) * c"), "((a + b) * c)");
+    assert_eq!(parse("a - (b - c)"), "(a - (b - c))");
+    assert_eq!(parse("(2 ^ 3) ^ 2"), "((2 ^ 3) ^ 2)");
+    assert_eq!(parse("(a + b)(a - b)"), "((a + b) * (a - b))");
+}
+
+#[test]
+fn assignment_and_lambdas_bind_loosest() {
+    assert_eq!(parse("x = a or b"), "(x = (a or b))");
+    assert_eq!(parse("f = x ↦ x + 1"), "(f = (x) ↦ (x + 1))");
+}
+
+#[test]
+fn integrands_end_at_their_differential() {
+    assert_eq!(parse("∫₀^π sin(x) dx"), "∫(0, π, sin(x), x)");
+    assert_eq!(parse("∫₀¹ x² dt + 1"), "(∫(0, 1, (x ^ 2), t) + 1)");
+    assert_eq!(parse("∫₋₁^∞ 1/x² dx"), "∫((-1), ∞, (1 / (x ^ 2)), x)");
+    assert_eq!(parse("∫₀¹ dx"), "∫(0, 1, 1, x)");
+    assert_eq!(parse("∫(a, b, x + 1, x)"), "∫(a, b, (x + 1), x)");
+}
+
+#[test]
+fn scripted_big_operators_end_like_integrands() {
+    assert_eq!(parse("∑ₖ₌₁¹⁰ k²"), "∑(k ∈ (1..=10), (k ^ 2))");
+    assert_eq!(parse("∑ₖ₌₁ⁿ k + 1"), "(∑(k ∈ (1..=n), k) + 1)");
+    assert_eq!(parse("∏ᵢ₌₀^(n-1) 2i"), "∏(i ∈ (0..=(n - 1)), (2 * i))");
+    assert_eq!(parse("∑ₖ₌₁^∞ 1/k²"), "∑(k ∈ (1..=∞), (1 / (k ^ 2)))");
+}
+
+#[test]
+fn equations_in_solve_become_functions() {
+    assert_eq!(
+        parse("solve(x² - 2 = 0, x)"),
+        "solve((x) ↦ (((x ^ 2) - 2) - 0))"
+    );
+    assert_eq!(
+        parse("solve(ln(t) = 1, t, 2)"),
+        "solve((t) ↦ (ln(t) - 1), 2)"
+    );
+    assert_eq!(parse("solve(x = y, y, 0, 1)"), "solve((y) ↦ (x - y), 0, 1)");
+    assert_eq!(parse("solve(f, 1)"), "solve(f, 1)");
+}
+
+#[test]
+fn subscripts_are_part_of_names_and_calls() {
+    assert_eq!(parse("x₁ + 1"), "(x₁ + 1)");
+    assert_eq!(parse("aₙ₊₁"), "aₙ₊₁");
+    assert_eq!(parse("log₂(8)"), "log₂(8)");
+    assert_eq!(parse("x₁ = 3"), "(x₁ = 3)");
+    assert_eq!(parse("f₁(x) = x²"), "fn f₁(x) {\n  (x ^ 2)\n}");
+    assert_eq!(parse("2x₁²"), "(2 * (x₁ ^ 2))");
+}
+
+#[test]
+fn strings_keep_their_interpolations() {
+    assert_eq!(parse(r#""a" + "b""#), r#"("a" + "b")"#);
+    assert_eq!(parse(r#""x = {x + 1}\n""#), r#""x = {(x + 1)}\n""#);
+    assert_eq!(parse(r#"f("{x}", y)"#), r#"f("{x}", y)"#);
+}
+
+#[test]
+fn ranges_bind_between_comparisons_and_arithmetic() {
+    assert_eq!(parse("1..n+1"), "(1..(n + 1))");
+    assert_eq!(parse("0..=2π step π/4"), "(0..=(2 * π) step (π / 4))");
+    assert_eq!(parse("x ∈ 1..10"), "(x ∈ (1..10))");
+    assert_eq!(parse("k in a..b == r"), "((k ∈ (a..b)) == r)");
+    assert_eq!(parse("∑(k ∈ 1..=n, k²)"), "∑(k ∈ (1..=n), (k ^ 2))");
+    assert_eq!(parse("for x in 1..3 { x }"), "for x ∈ (1..3) do {\n  x\n}");
+}
+
+#[test]
+fn control_flow_takes_a_value_only_where_one_is_given() {
+    assert_eq!(
+        parse("f(x) = return x + 1"),
+        "fn f(x) {\n  return (x + 1)\n}"
+    );
+    assert_eq!(
+        parse("f(x) = { if x { return } else { 1 } }"),
+        "fn f(x) {\n  {\n  if x then {\n  return\n} else {\n  1\n}\n}\n}"
+    );
+    assert_eq!(parse("while x { break }"), "while x do {\n  break\n}");
+}
+
+#[test]
+fn brackets_hold_lists_unless_they_have_rows() {
+    assert_eq!(parse("[1, 2, 3]"), "[1, 2, 3]");
+    assert_eq!(parse("[]"), "[]");
+    assert_eq!(parse("[1, 2; 3, 4]"), "[1, 2; 3, 4]");
+    assert_eq!(parse("[1, 2;]"), "[1, 2]");
+    assert_eq!(parse("[\n1, 2, 3\n]"), "[1, 2, 3]");
+    assert_eq!(parse("[\n1, 2\n3, 4\n]"), "[1, 2; 3, 4]");
+}
+
+#[test]
+fn brackets_right_after_an_operand_index_it() {
+    assert_eq!(parse("xs[i + 1]"), "xs[(i + 1)]");
+    assert_eq!(parse("xs[1..3]"), "xs[(1..3)]");
+    assert_eq!(parse("f(x)[0][1]"), "f(x)[0][1]");
+    assert_eq!(parse("-xs[0]²"), "(-(xs[0] ^ 2))");
+    assert_eq!(parse("[1, 2][0]"), "[1, 2][0]");
+    assert_eq!(parse("xs [1]"), "(xs * [1])");
+    assert_eq!(parse("2[1, 2; 3, 4]"), "(2 * [1, 2; 3, 4])");
+}
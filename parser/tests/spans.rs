@@ -0,0 +1,140 @@
+use std::ops::Range;
+
+use lexer::Lexer;
+use parser::{Node, Parser, Spanned};
+
+fn parse(source: &str) -> Spanned<Node> {
+    let tokens = match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    };
+    match Parser::new(tokens).statement() {
+        Ok(node) => node,
+        Err(e) => panic!("failed to parse {:?}: {}", source, e.reason),
+    }
+}
+
+fn binary(node: &Spanned<Node>) -> (&Spanned<Node>, &Spanned<Node>) {
+    match &node.node {
+        Node::Binary(left, _, right) => (left, right),
+        node => panic!("expected a binary operation, got {}", node),
+    }
+}
+
+#[test]
+fn binary_operations_span_both_operands() {
+    let node = parse("a + b * c");
+    assert_eq!(node.range, 0..9);
+    let (left, right) = binary(&node);
+    assert_eq!(left.range, 0..1);
+    assert_eq!(right.range, 4..9);
+}
+
+#[test]
+fn prefix_and_postfix_operators_span_their_operand() {
+    assert_eq!(parse("-x").range, 0..2);
+    assert_eq!(parse("√x").range, 0..2);
+    assert_eq!(parse("x!").range, 0..2);
+    assert_eq!(parse("|x - 1|").range, 0..7);
+}
+
+#[test]
+fn calls_span_their_arguments() {
+    let node = parse("f(x, y)");
+    assert_eq!(node.range, 0..7);
+    match &node.node {
+        Node::Call(function, args) => {
+            assert_eq!(function.range, 0..1);
+            let ranges: Vec<Range<usize>> = args.iter().map(|arg| arg.range.clone()).collect();
+            assert_eq!(ranges, [2..3, 5..6]);
+        }
+        node => panic!("expected a call, got {}", node),
+    }
+}
+
+#[test]
+fn superscripts_keep_their_source_offsets() {
+    let node = parse("2 + xⁿ⁺¹");
+    let (_, power) = binary(&node);
+    assert_eq!(power.range, 4..8);
+    let (base, exponent) = binary(power);
+    assert_eq!(base.range, 4..5);
+    assert_eq!(exponent.range, 5..8);
+    let (n, one) = binary(exponent);
+    assert_eq!(n.range, 5..6);
+    assert_eq!(one.range, 7..8);
+}
+
+#[test]
+fn indexing_spans_its_brackets() {
+    let node = parse("2 + xs[i]");
+    let (_, index) = binary(&node);
+    assert_eq!(index.range, 4..9);
+    match &index.node {
+        Node::Index(list, i) => {
+            assert_eq!(list.range, 4..6);
+            assert_eq!(i.range, 7..8);
+        }
+        node => panic!("expected an index, got {}", node),
+    }
+}
+
+/// Parses `source`, which should fail, returning the error and its range.
+fn parse_error(source: &str) -> (String, Range<usize>) {
+    let tokens = match Lexer::new(source.to_string()).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("failed to lex {:?}: {}", source, e.reason),
+    };
+    match Parser::new(tokens).parse() {
+        Ok(node) => panic!("expected {:?} not to parse, got {}", source, node.node),
+        Err(e) => (e.reason, e.range),
+    }
+}
+
+#[test]
+fn misplaced_control_flow_spans_its_keyword() {
+    assert_eq!(
+        parse_error("x = 1\nbreak"),
+        ("'break' can only be used inside a loop".to_string(), 6..11)
+    );
+    assert_eq!(
+        parse_error("while x { f(y) = continue }"),
+        (
+            "'continue' can only be used inside a loop".to_string(),
+            17..25
+        )
+    );
+    assert_eq!(
+        parse_error("for k in 1..3 { ∑(j ∈ 1..k, break) }"),
+        ("'break' can only be used inside a loop".to_string(), 28..33)
+    );
+    assert_eq!(
+        parse_error("while x { return 1 }"),
+        (
+            "'return' can only be used inside a function".to_string(),
+            10..16
+        )
+    );
+}
+
+#[test]
+fn a_missing_operand_is_an_error_at_the_end() {
+    let (reason, range) = parse_error("1 +");
+    assert!(reason.starts_with("expected int"), "{}", reason);
+    assert_eq!(range, 3..3);
+    let (reason, range) = parse_error("\"{3 *}\"");
+    assert!(reason.starts_with("expected int"), "{}", reason);
+    assert_eq!(range, 5..5);
+}
+
+#[test]
+fn leftover_exponent_tokens_span_the_exponent() {
+    assert_eq!(
+        parse_error("2³⁼⁴"),
+        ("expected the end of the exponent".to_string(), 1..3)
+    );
+    assert_eq!(
+        parse_error("2³⁾"),
+        ("expected the end of the exponent".to_string(), 1..3)
+    );
+}